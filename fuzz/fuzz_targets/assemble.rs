@@ -0,0 +1,8 @@
+#![no_main]
+#![allow(dead_code)]
+
+// The emulator is a binary crate, so the targets build its `vm` module directly.
+#[path = "../../src/vm/mod.rs"]
+mod vm;
+
+libfuzzer_sys::fuzz_target!(|data: &[u8]| vm::fuzz::assemble_target(data));
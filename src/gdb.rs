@@ -0,0 +1,486 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use crate::vm::{Fault, Helios32};
+use crate::vm::debugger::{Debugger, StopReason};
+use crate::vm::registers::*;
+
+/// Instructions executed between checks for a Ctrl-C from the client.
+const POLL_INTERVAL: u64 = 10_000;
+const PACKET_SIZE: usize = 0x4000;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.helios32.core">
+    <reg name="rds" bitsize="32" type="uint32" regnum="0"/>
+    <reg name="gr0" bitsize="32" type="uint32"/>
+    <reg name="gr1" bitsize="32" type="uint32"/>
+    <reg name="gr2" bitsize="32" type="uint32"/>
+    <reg name="gr3" bitsize="32" type="uint32"/>
+    <reg name="gr4" bitsize="32" type="uint32"/>
+    <reg name="gr5" bitsize="32" type="uint32"/>
+    <reg name="gr6" bitsize="32" type="uint32"/>
+    <reg name="gr7" bitsize="32" type="uint32"/>
+    <reg name="gr8" bitsize="32" type="uint32"/>
+    <reg name="gr9" bitsize="32" type="uint32"/>
+    <reg name="gra" bitsize="32" type="uint32"/>
+    <reg name="grb" bitsize="32" type="uint32"/>
+    <reg name="rsp" bitsize="32" type="data_ptr"/>
+    <reg name="csp" bitsize="32" type="data_ptr"/>
+    <reg name="rpc" bitsize="32" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+/// Serves one GDB remote serial protocol client on `addr`, returning the machine
+/// once the client detaches, kills the target or disconnects.
+///
+/// Register numbers follow `registers.rs`, so `rpc` is register 15.
+pub fn serve(vm: Helios32, addr: &str) -> Result<Helios32, String> {
+    let listener = TcpListener::bind(addr)
+        .map_err(|err| format!("failed to listen on {addr}: {err}"))?;
+    eprintln!("waiting for gdb on {addr}");
+
+    serve_next(vm, &listener)
+}
+
+/// Serves the next client to connect to `listener`.
+fn serve_next(vm: Helios32, listener: &TcpListener) -> Result<Helios32, String> {
+    let (stream, peer) = listener.accept()
+        .map_err(|err| err.to_string())?;
+    eprintln!("gdb connected from {peer}");
+
+    let mut session = Session {
+        dbg: Debugger::new(vm),
+        stream,
+        no_ack: false,
+        last_stop: "S05".to_string(),
+    };
+    session.run()?;
+
+    Ok(session.dbg.vm)
+}
+
+struct Session {
+    dbg: Debugger,
+    stream: TcpStream,
+    no_ack: bool,
+    last_stop: String,
+}
+
+enum Packet {
+    Data(String),
+    Interrupt,
+    Closed,
+}
+
+impl Session {
+    fn run(&mut self) -> Result<(), String> {
+        loop {
+            let packet = match self.read_packet()? {
+                Packet::Data(packet) => packet,
+                Packet::Interrupt => continue,
+                Packet::Closed => return Ok(()),
+            };
+
+            match &*packet {
+                "k" => return Ok(()),
+                "D" | "D;1" => {
+                    self.send("OK")?;
+                    return Ok(());
+                },
+                _ => (),
+            }
+
+            let reply = self.handle(&packet);
+            self.send(&reply)?;
+
+            if packet == "QStartNoAckMode" {
+                self.no_ack = true;
+            }
+        }
+    }
+
+    fn handle(&mut self, packet: &str) -> String {
+        let (cmd, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+
+        match cmd {
+            "?" => self.last_stop.clone(),
+            "g" => self.dbg.vm.core.registers.iter()
+                .map(|reg| hex(&reg.to_le_bytes()))
+                .collect(),
+            "G" => match unhex(args) {
+                Some(bytes) if bytes.len() == 16 * 4 => {
+                    self.dbg.modify(|vm| {
+                        for (reg, chunk) in vm.core.registers.iter_mut().zip(bytes.chunks_exact(4)) {
+                            *reg = u32::from_le_bytes(chunk.try_into().unwrap());
+                        }
+                    });
+                    "OK".to_string()
+                },
+                _ => "E01".to_string(),
+            },
+            "p" => match u32::from_str_radix(args, 16) {
+                Ok(reg) if reg < 16 => hex(&self.dbg.vm.core.registers[reg as usize].to_le_bytes()),
+                _ => "E01".to_string(),
+            },
+            "P" => {
+                let parsed = args.split_once('=').and_then(|(reg, value)| {
+                    let reg = u32::from_str_radix(reg, 16).ok().filter(|&reg| reg < 16)?;
+                    let value = unhex(value).filter(|bytes| bytes.len() == 4)?;
+                    Some((reg, u32::from_le_bytes(value.try_into().unwrap())))
+                });
+                match parsed {
+                    Some((reg, value)) => {
+                        self.dbg.modify(|vm| vm.core.registers[reg as usize] = value);
+                        "OK".to_string()
+                    },
+                    None => "E01".to_string(),
+                }
+            },
+            "m" => match parse_addr_len(args) {
+                Some((addr, len)) => {
+                    let bytes = (0..len.min(PACKET_SIZE as u32 / 2))
+                        .map(|i| self.dbg.vm.mem[addr.wrapping_add(i) as usize])
+                        .collect::<Vec<_>>();
+                    hex(&bytes)
+                },
+                None => "E01".to_string(),
+            },
+            "M" => {
+                let parsed = args.split_once(':').and_then(|(range, data)| {
+                    let (addr, len) = parse_addr_len(range)?;
+                    let bytes = unhex(data).filter(|bytes| bytes.len() == len as usize)?;
+                    Some((addr, bytes))
+                });
+                let writable = |(addr, bytes): &(u32, Vec<u8>)| {
+                    (0..bytes.len() as u32).all(|i| self.dbg.vm.is_writable(addr.wrapping_add(i)))
+                };
+                match parsed {
+                    // EFAULT: unmapped or ROM bytes would be silently dropped.
+                    Some(ref write) if !writable(write) => "E0e".to_string(),
+                    Some((addr, bytes)) => {
+                        self.dbg.modify(|vm| {
+                            for (i, byte) in bytes.into_iter().enumerate() {
+                                vm.write_u8(addr.wrapping_add(i as u32), byte);
+                            }
+                        });
+                        "OK".to_string()
+                    },
+                    None => "E01".to_string(),
+                }
+            },
+            "s" | "c" => {
+                if !args.is_empty() {
+                    match u32::from_str_radix(args, 16) {
+                        Ok(pc) => self.dbg.modify(|vm| vm.core.registers[RPC as usize] = pc),
+                        Err(_) => return "E01".to_string(),
+                    }
+                }
+                self.resume(cmd == "s")
+            },
+            "b" => match args {
+                "s" => {
+                    let reason = self.dbg.reverse_step();
+                    self.stop(reason)
+                },
+                "c" => {
+                    let reason = self.dbg.reverse_continue();
+                    self.stop(reason)
+                },
+                _ => String::new(),
+            },
+            "Z" | "z" => {
+                let mut fields = args.split(',');
+                let kind = fields.next();
+                let addr = fields.next().and_then(|addr| u32::from_str_radix(addr, 16).ok());
+                let len = fields.next().and_then(|len| u32::from_str_radix(len, 16).ok());
+                let (Some(kind), Some(addr), Some(len)) = (kind, addr, len) else {
+                    return "E01".to_string();
+                };
+
+                match (cmd, kind) {
+                    ("Z", "0" | "1") => self.dbg.add_breakpoint(addr),
+                    ("z", "0" | "1") => { self.dbg.remove_breakpoint(addr); },
+                    ("Z", "2") => self.dbg.add_watchpoint(addr, len),
+                    ("z", "2") => { self.dbg.remove_watchpoint(addr); },
+                    _ => return String::new(),
+                }
+                "OK".to_string()
+            },
+            "H" => "OK".to_string(),
+            "T" => "OK".to_string(),
+            "v" => match packet {
+                "vCont?" => "vCont;c;C;s;S".to_string(),
+                _ if packet.starts_with("vCont;") => {
+                    let action = packet["vCont;".len()..].chars().next();
+                    match action {
+                        Some('s' | 'S') => self.resume(true),
+                        Some('c' | 'C') => self.resume(false),
+                        _ => "E01".to_string(),
+                    }
+                },
+                _ => String::new(),
+            },
+            "q" => self.query(packet),
+            _ => String::new(),
+        }
+    }
+
+    fn query(&self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            return format!(
+                "PacketSize={PACKET_SIZE:x};qXfer:features:read+;swbreak+;hwbreak+;\
+                 QStartNoAckMode+;ReverseStep+;ReverseContinue+"
+            );
+        }
+
+        if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            let Some((offset, len)) = parse_addr_len(range) else {
+                return "E01".to_string();
+            };
+            let start = (offset as usize).min(TARGET_XML.len());
+            let end = (start + len as usize).min(TARGET_XML.len());
+            let marker = if end == TARGET_XML.len() { 'l' } else { 'm' };
+            return format!("{marker}{}", &TARGET_XML[start..end]);
+        }
+
+        match packet {
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Steps or continues, watching the connection for a Ctrl-C while running.
+    fn resume(&mut self, single_step: bool) -> String {
+        if single_step {
+            let reason = self.dbg.step();
+            return self.stop(reason);
+        }
+
+        loop {
+            if let Some(reason) = self.dbg.cont_for(POLL_INTERVAL) {
+                return self.stop(reason);
+            }
+            if self.poll_interrupt() {
+                self.last_stop = "T02".to_string();
+                return self.last_stop.clone();
+            }
+        }
+    }
+
+    fn stop(&mut self, reason: StopReason) -> String {
+        self.last_stop = match reason {
+            StopReason::Step => "T05".to_string(),
+            StopReason::Breakpoint(_) => "T05swbreak:;".to_string(),
+            StopReason::Watchpoint { addr, .. } => format!("T05watch:{addr:x};"),
+            StopReason::Halted => "W00".to_string(),
+            // SIGFPE
+            StopReason::Fault(Fault::FloatingPoint { .. }) => "T08".to_string(),
+            // SIGSEGV
+            StopReason::Fault(_) => "T0B".to_string(),
+            StopReason::HistoryStart => "T05replaylog:begin;".to_string(),
+        };
+        self.last_stop.clone()
+    }
+
+    fn poll_interrupt(&mut self) -> bool {
+        let mut byte = [0u8];
+        if self.stream.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let interrupted = matches!(self.stream.read(&mut byte), Ok(1) if byte[0] == 0x03);
+        self.stream.set_nonblocking(false).ok();
+        interrupted
+    }
+
+    fn read_byte(&mut self) -> Result<Option<u8>, String> {
+        let mut byte = [0u8];
+        loop {
+            return match self.stream.read(&mut byte) {
+                Ok(0) => Ok(None),
+                Ok(_) => Ok(Some(byte[0])),
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => Err(err.to_string()),
+            };
+        }
+    }
+
+    /// Reads the next packet, asking for a retransmission of any that fails
+    /// its checksum.
+    fn read_packet(&mut self) -> Result<Packet, String> {
+        loop {
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(Packet::Closed),
+                    Some(0x03) => return Ok(Packet::Interrupt),
+                    Some(b'$') => break,
+                    Some(_) => continue,
+                }
+            }
+
+            let mut data = Vec::new();
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(Packet::Closed),
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                }
+            }
+
+            let mut checksum = [0u8; 2];
+            for digit in &mut checksum {
+                match self.read_byte()? {
+                    None => return Ok(Packet::Closed),
+                    Some(byte) => *digit = byte,
+                }
+            }
+
+            let expected = std::str::from_utf8(&checksum).ok()
+                .and_then(|s| u8::from_str_radix(s, 16).ok());
+            let actual = data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+            let valid = expected == Some(actual);
+            if !self.no_ack {
+                let ack = if valid { b"+" } else { b"-" };
+                self.stream.write_all(ack).map_err(|err| err.to_string())?;
+            }
+            if valid {
+                return Ok(Packet::Data(String::from_utf8_lossy(&data).into_owned()));
+            }
+        }
+    }
+
+    fn send(&mut self, reply: &str) -> Result<(), String> {
+        let checksum = reply.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        let packet = format!("${reply}#{checksum:02x}");
+        self.stream.write_all(packet.as_bytes())
+            .map_err(|err| err.to_string())?;
+
+        if !self.no_ack {
+            // Wait for the client's acknowledgement, resending on `-`.
+            loop {
+                match self.read_byte()? {
+                    Some(b'+') | None => break,
+                    Some(b'-') => {
+                        self.stream.write_all(packet.as_bytes())
+                            .map_err(|err| err.to_string())?;
+                    },
+                    Some(_) => (),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_addr_len(s: &str) -> Option<(u32, u32)> {
+    let (addr, len) = s.split_once(',')?;
+    Some((u32::from_str_radix(addr, 16).ok()?, u32::from_str_radix(len, 16).ok()?))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use crate::vm::{assembler, CODE_BASE};
+    use super::*;
+
+    struct Client(TcpStream);
+
+    impl Client {
+        fn send_raw(&mut self, packet: &str, checksum: u8) -> u8 {
+            self.0.write_all(format!("${packet}#{checksum:02x}").as_bytes()).unwrap();
+            let mut ack = [0u8];
+            self.0.read_exact(&mut ack).unwrap();
+            ack[0]
+        }
+
+        fn request(&mut self, packet: &str) -> String {
+            let checksum = packet.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+            assert_eq!(self.send_raw(packet, checksum), b'+');
+
+            let mut reply = Vec::new();
+            let mut byte = [0u8];
+            while byte[0] != b'$' {
+                self.0.read_exact(&mut byte).unwrap();
+            }
+            loop {
+                self.0.read_exact(&mut byte).unwrap();
+                if byte[0] == b'#' {
+                    break;
+                }
+                reply.push(byte[0]);
+            }
+            self.0.read_exact(&mut [0u8; 2]).unwrap();
+            self.0.write_all(b"+").unwrap();
+            String::from_utf8(reply).unwrap()
+        }
+    }
+
+    #[test]
+    fn loopback_session() {
+        let assembly = assembler::assemble("
+            ldi gr0 0x3000
+        loop:
+            inc gr1
+            sw gr0 gr1
+            jmi loop
+        ", CODE_BASE).unwrap();
+        let mut vm = Helios32::new();
+        vm.load_program(assembly.origin, &assembly.bytes).unwrap();
+        vm.load_rom(&[0; 16]).unwrap();
+
+        // The machine stays on this thread; a panicking client closes the
+        // connection, which ends the session.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut client = Client(TcpStream::connect(addr).unwrap());
+
+            assert_eq!(client.request("?"), "S05");
+            let registers = client.request("g");
+            assert_eq!(registers.len(), 16 * 8);
+            assert_eq!(&registers[15 * 8..], hex(&CODE_BASE.to_le_bytes()));
+
+            // A corrupted packet is rejected and its retransmission answered.
+            assert_eq!(client.send_raw("g", 0), b'-');
+            assert_eq!(client.request("g"), registers);
+
+            assert_eq!(client.request("M3000,4:78563412"), "OK");
+            assert_eq!(client.request("m3000,4"), "78563412");
+            assert_eq!(client.request("Mffff0000,1:aa"), "E0e");
+            assert_eq!(client.request("mffff0000,1"), "00");
+
+            assert_eq!(client.request("s"), "T05");
+            assert_eq!(client.request("p1"), hex(&0x3000u32.to_le_bytes()));
+            assert_eq!(client.request(&format!("Z0,{:x},1", CODE_BASE + 12)), "OK");
+            assert_eq!(client.request("c"), "T05swbreak:;");
+            assert_eq!(client.request("?"), "T05swbreak:;");
+            assert_eq!(client.request("p2"), "01000000");
+            assert_eq!(client.request("m3000,4"), "78563412");
+
+            client.0.write_all(b"$k#6b").unwrap();
+        });
+
+        let vm = serve_next(vm, &listener).unwrap();
+        client.join().unwrap();
+        assert_eq!(vm.core.registers[RPC as usize], CODE_BASE + 12);
+        assert_eq!(vm.core.registers[GR1 as usize], 1);
+    }
+}
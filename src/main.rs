@@ -1,53 +1,597 @@
 mod vm;
+mod repl;
+mod gdb;
 
-use vm::Helios32;
+use vm::{Engine, Helios32, Stack, CODE_BASE};
+use vm::profiler::Profiler;
+use vm::coverage::Coverage;
+use vm::debuginfo::DebugInfo;
+use vm::symbols::SymbolMap;
+use vm::config::MachineConfig;
+use vm::expect::GuestTest;
+use vm::system::System;
 use vm::assembler;
-use vm::registers::RDS;
+use vm::disassembler;
+use vm::fuzz;
+use vm::registers::{is_pair, GR0, RDS, RPC};
 use std::env;
+use std::fs;
+
+const USAGE: &str = "Usage: helios32 asm <program>.h32 (-o <output>.bin) (--map <file>) (--base <addr>)
+       helios32 disasm <program>.bin (--base <addr>)
+       helios32 fuzz assemble|execute (--runs <n>) (--seed <n>) (<input>...)
+       helios32 test <test>.h32... (--machine <config>) (--engine interpreter|threaded)
+       helios32 <program>.h32 (<output register>) (--base <addr>) (--machine <config>) (--rom <boot image>) (-f|--float-output) (-d|--double-output) (--engine interpreter|threaded) (--max-cycles <n>)
+       (--load-state <file>) (--save-state <file>) (--debug) (--stack-stats)
+       (--gdb <host:port>) (--profile <report>) (--folded <file>)
+       (--coverage <lcov file>) (--annotate <file>)
+       (--emit-debug-info <file>) (--debug-info <file>) (--symbols <map>)
+       (--cores <n>) (--quantum <instructions>)";
 
 fn main() {
-    let args = env::args().collect::<Vec<_>>();
-    if args.len() > 5 || args.len() < 2 {
-        eprintln!("Usage: helios32 <program>.h32 (<output register>) (-f|--float-output)");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().is_some_and(|arg| arg == "asm") {
+        if let Err(err) = assemble(&args[1..]) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.first().is_some_and(|arg| arg == "disasm") {
+        if let Err(err) = disassemble(&args[1..]) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.first().is_some_and(|arg| arg == "fuzz") {
+        if let Err(err) = run_fuzzer(&args[1..]) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
         return;
     }
+    if args.first().is_some_and(|arg| arg == "test") {
+        match test(&args[1..]) {
+            Ok(true) => {},
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(2);
+            },
+        }
+        return;
+    }
+
+    let mut positional = Vec::new();
+    let mut float_output = false;
+    let mut double_output = false;
+    let mut engine = Engine::Interpreter;
+    let mut max_cycles = None;
+    let mut cores = None;
+    let mut quantum = 1;
+    let mut load_state = None;
+    let mut save_state = None;
+    let mut debug = false;
+    let mut stack_stats = false;
+    let mut gdb_addr = None;
+    let mut profile = None;
+    let mut folded = None;
+    let mut lcov = None;
+    let mut annotate = None;
+    let mut emit_debug_info = None;
+    let mut debug_info_path = None;
+    let mut symbols_path = None;
+    let mut base = None;
+    let mut machine = None;
+    let mut rom = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "-f" | "--float-output" => float_output = true,
+            "-d" | "--double-output" => double_output = true,
+            "--debug" => debug = true,
+            "--stack-stats" => stack_stats = true,
+            "--engine" => engine = match args.next().map(|s| &**s) {
+                Some("interpreter") => Engine::Interpreter,
+                Some("threaded") => Engine::Threaded,
+                _ => {
+                    eprintln!("expected `interpreter` or `threaded` after --engine");
+                    std::process::exit(1);
+                },
+            },
+            "--max-cycles" => max_cycles = match args.next().map(|s| s.parse::<u64>()) {
+                Some(Ok(n)) => Some(n),
+                _ => {
+                    eprintln!("expected a cycle count after --max-cycles");
+                    std::process::exit(1);
+                },
+            },
+            "--cores" => cores = match args.next().map(|s| s.parse::<u32>()) {
+                Some(Ok(n)) => Some(n),
+                _ => {
+                    eprintln!("expected a core count after --cores");
+                    std::process::exit(1);
+                },
+            },
+            "--quantum" => quantum = match args.next().map(|s| s.parse::<u64>()) {
+                Some(Ok(n)) => n,
+                _ => {
+                    eprintln!("expected an instruction count after --quantum");
+                    std::process::exit(1);
+                },
+            },
+            "--base" => base = match args.next().map(|s| assembler::parse_address(s)) {
+                Some(Ok(addr)) => Some(addr),
+                _ => {
+                    eprintln!("expected an address after --base");
+                    std::process::exit(1);
+                },
+            },
+            "--gdb" => {
+                let Some(addr) = args.next() else {
+                    eprintln!("expected an address such as 127.0.0.1:1234 after --gdb");
+                    std::process::exit(1);
+                };
+                gdb_addr = Some(addr);
+            },
+            "--profile" | "--folded" | "--coverage" | "--annotate" => {
+                let Some(path) = args.next() else {
+                    eprintln!("expected a file path after {arg}");
+                    std::process::exit(1);
+                };
+                match &**arg {
+                    "--profile" => profile = Some(path),
+                    "--folded" => folded = Some(path),
+                    "--coverage" => lcov = Some(path),
+                    _ => annotate = Some(path),
+                }
+            },
+            "--machine" | "--rom" => {
+                let Some(path) = args.next() else {
+                    eprintln!("expected a file path after {arg}");
+                    std::process::exit(1);
+                };
+                if arg == "--machine" {
+                    machine = Some(path);
+                } else {
+                    rom = Some(path);
+                }
+            },
+            "--emit-debug-info" | "--debug-info" | "--symbols" => {
+                let Some(path) = args.next() else {
+                    eprintln!("expected a file path after {arg}");
+                    std::process::exit(1);
+                };
+                match &**arg {
+                    "--emit-debug-info" => emit_debug_info = Some(path),
+                    "--debug-info" => debug_info_path = Some(path),
+                    _ => symbols_path = Some(path),
+                }
+            },
+            "--load-state" | "--save-state" => {
+                let Some(path) = args.next() else {
+                    eprintln!("expected a file path after {arg}");
+                    std::process::exit(1);
+                };
+                if arg == "--load-state" {
+                    load_state = Some(path);
+                } else {
+                    save_state = Some(path);
+                }
+            },
+            other if other.starts_with('-') => {
+                eprintln!("unrecognized flag: `{other}`");
+                eprintln!("{USAGE}");
+                std::process::exit(1);
+            },
+            other => positional.push(other),
+        }
+    }
 
-    let output = if args.len() >= 3 {
-        match assembler::parse_register(&args[2]) {
+    // A restored snapshot already contains the program, so only the output
+    // register may be given alongside --load-state.
+    let program = if load_state.is_some() { None } else { positional.first().copied() };
+    let register_args = &positional[program.is_some() as usize..];
+    if (program.is_none() && load_state.is_none()) || register_args.len() > 1 {
+        eprintln!("{USAGE}");
+        std::process::exit(1);
+    }
+
+    let output = match register_args.first() {
+        Some(reg) => match assembler::parse_register(reg) {
             Ok(reg) => reg,
             Err(err) => {
                 eprintln!("{err}");
-                return;
+                std::process::exit(1);
+            },
+        },
+        None if double_output => GR0,
+        None => RDS,
+    };
+    if double_output && !is_pair(output) {
+        eprintln!("--double-output needs a register pair: gr0, gr2, gr4, gr6, gr8 or gra");
+        std::process::exit(1);
+    }
+
+    let single_core_only = gdb_addr.is_some() || debug || load_state.is_some() || save_state.is_some()
+        || profile.is_some() || folded.is_some() || lcov.is_some() || annotate.is_some();
+    if cores.is_some() && single_core_only {
+        eprintln!("--cores cannot be combined with --gdb, --debug, --load-state, --save-state, --profile, --folded, --coverage or --annotate");
+        std::process::exit(1);
+    }
+
+    let mut builder = Helios32::builder().engine(engine);
+    if let Some(path) = machine {
+        match MachineConfig::load(path) {
+            Ok(config) => builder = builder.config(config),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
             },
         }
-    } else {
-        RDS
+    }
+    let mut vm = match builder.build() {
+        Ok(vm) => vm,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        },
     };
 
-    let mut vm = Helios32::new();
+    if let Some(path) = rom {
+        if let Err(err) = fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|image| vm.load_rom(&image))
+        {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        vm.reset();
+    }
+
+    if let Some(path) = load_state {
+        if let Err(err) = vm.load_snapshot_from_path(path) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+
+    let profiling = profile.is_some() || folded.is_some();
+    let covering = lcov.is_some() || annotate.is_some();
 
-    match assembler::assemble_from_path(&args[1]) {
-        Ok(path) => if let Err(err) = vm.load_program_from_path(path) {
+    // Source-level tools need the label and line tables, not just the binary.
+    let assembly = match program.map(|path| assembler::assemble_from_path(path, base.unwrap_or(vm.config.code_base), None)).transpose() {
+        Ok(assembly) => assembly,
+        Err(err) => {
             eprintln!("{err}");
-            return;
+            std::process::exit(1);
         },
+    };
+    if assembly.is_none() && (covering || emit_debug_info.is_some()) {
+        eprintln!("coverage and debug info need a program, not just --load-state");
+        std::process::exit(1);
+    }
+
+    let debug_info = match (debug_info_path, symbols_path, &assembly) {
+        (Some(path), _, _) => DebugInfo::load(path).map(Some),
+        (None, Some(path), _) => SymbolMap::load(path).map(|map| Some(DebugInfo::from_symbol_map(&map))),
+        (None, None, Some(assembly)) => Ok(Some(DebugInfo::from_assembly(program.unwrap(), assembly))),
+        (None, None, None) => Ok(None),
+    };
+    let debug_info = match debug_info {
+        Ok(info) => info,
         Err(err) => {
             eprintln!("{err}");
-            return;
+            std::process::exit(1);
         },
+    };
+    if let (Some(path), Some(info)) = (emit_debug_info, &debug_info) {
+        if let Err(err) = info.save(path) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
     }
-    
-    vm.run();
 
-    if args.len() == 4 {
-        if ["-f", "--float-output"].contains(&&*args[3]) {
-            println!("{:?}", f32::from_bits(vm.registers[output as usize]));
+    let mut profiler = profiling.then(|| {
+        let symbols = debug_info.iter()
+            .flat_map(|info| &info.symbols)
+            .map(|sym| (sym.name.clone(), sym.addr))
+            .collect();
+        Profiler::new(symbols)
+    });
+    let mut coverage = covering.then(Coverage::new);
+
+    if let Some(assembly) = &assembly {
+        if let Err(err) = vm.load_program(assembly.origin, &assembly.bytes) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        // Without a reset vector or boot ROM the machine boots straight into the program.
+        if vm.config.reset_vector.is_none() && rom.is_none() {
+            vm.core.registers[RPC as usize] = assembly.origin;
+        }
+    }
+
+    if let Some(cores) = cores {
+        let mut system = match System::new(vm, cores, quantum) {
+            Ok(system) => system,
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            },
+        };
+        let budget = max_cycles.unwrap_or(u64::MAX);
+        if max_cycles.is_some() {
+            system.run_for(budget);
         } else {
-            eprintln!("expected -f or --float-output flag");
+            system.run();
+        }
+        if max_cycles.is_some() && system.is_running() {
+            eprintln!("stopped after {budget} cycles without halting");
         }
 
+        for (id, core) in system.cores().iter().enumerate() {
+            if let Some(fault) = core.fault {
+                eprintln!("core {id} fault: {fault}");
+            } else if core.interrupts.waiting {
+                eprintln!("core {id} is still waiting for an interrupt");
+            }
+            if stack_stats {
+                eprintln!("core {id} data stack high-water mark: {} bytes", core.stack_high_water(Stack::Data));
+                eprintln!("core {id} call stack high-water mark: {} bytes", core.stack_high_water(Stack::Call));
+            }
+        }
+        // Output registers are read from core 0.
+        print_output(&system.cores()[0].registers, output, float_output, double_output);
         return;
     }
 
-    println!("{}", vm.registers[output as usize]);
+    if let Some(addr) = gdb_addr {
+        vm = match gdb::serve(vm, addr) {
+            Ok(vm) => vm,
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            },
+        };
+    } else if debug {
+        vm = repl::run(vm, debug_info.as_ref());
+    } else if program.is_some() || vm.core.is_running {
+        // A snapshot of a halted machine is only inspected, not resumed.
+        let budget = max_cycles.unwrap_or(u64::MAX);
+        if profiling || covering {
+            vm.run_observed(budget, |vm, pc, inst, before| {
+                if let Some(profiler) = &mut profiler {
+                    profiler.observe(vm, pc, inst, before);
+                }
+                if let Some(coverage) = &mut coverage {
+                    coverage.observe(pc, inst, before);
+                }
+            });
+        } else if max_cycles.is_some() {
+            vm.run_for(budget);
+        } else {
+            vm.run();
+        }
+        if max_cycles.is_some() && vm.core.is_running {
+            eprintln!("stopped after {budget} cycles without halting");
+        }
+    }
+
+    if let Some(fault) = vm.core.fault {
+        eprintln!("fault: {fault}");
+    }
+    if stack_stats {
+        eprintln!("data stack high-water mark: {} bytes", vm.stack_high_water(Stack::Data));
+        eprintln!("call stack high-water mark: {} bytes", vm.stack_high_water(Stack::Call));
+    }
+
+    let mut outputs = Vec::new();
+    if let Some(profiler) = &profiler {
+        outputs.push((profile, profiler.report()));
+        outputs.push((folded, profiler.folded_stacks()));
+    }
+    if let (Some(coverage), Some(assembly)) = (&coverage, &assembly) {
+        let text = match fs::read_to_string(program.unwrap()) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            },
+        };
+        outputs.push((lcov, coverage.lcov(program.unwrap(), assembly)));
+        outputs.push((annotate, coverage.annotate(&text, assembly)));
+    }
+    for (path, contents) in outputs {
+        if let Some(path) = path {
+            if let Err(err) = fs::write(path, contents) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(path) = save_state {
+        if let Err(err) = vm.save_snapshot_to_path(path) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+
+    print_output(&vm.core.registers, output, float_output, double_output);
+}
+
+/// Prints the output register as an integer, or as a float or the double in
+/// the pair starting at it.
+fn print_output(registers: &[u32; 16], output: u8, float_output: bool, double_output: bool) {
+    if float_output {
+        println!("{:?}", f32::from_bits(registers[output as usize]));
+    } else if double_output {
+        let (low, high) = (registers[output as usize], registers[output as usize + 1]);
+        println!("{:?}", f64::from_bits(low as u64 | (high as u64) << 32));
+    } else {
+        println!("{}", registers[output as usize]);
+    }
+}
+
+/// `helios32 asm`: assembles without running, optionally writing a symbol map.
+fn assemble(args: &[String]) -> Result<(), String> {
+    let mut program = None;
+    let mut output = None;
+    let mut map = None;
+    let mut base = CODE_BASE;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "-o" | "--output" | "--map" => {
+                let path = args.next().ok_or_else(|| format!("expected a file path after {arg}"))?;
+                if arg == "--map" {
+                    map = Some(path);
+                } else {
+                    output = Some(path.as_str());
+                }
+            },
+            "--base" => {
+                let addr = args.next().ok_or("expected an address after --base")?;
+                base = assembler::parse_address(addr)?;
+            },
+            other if other.starts_with('-') => return Err(format!("unrecognized flag: `{other}`\n{USAGE}")),
+            other if program.is_none() => program = Some(other),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let program = program.ok_or(USAGE)?;
+
+    let assembly = assembler::assemble_from_path(program, base, output)?;
+
+    if let Some(path) = map {
+        SymbolMap::from_assembly(&assembly).save(path)?;
+    }
+    Ok(())
+}
+
+/// `helios32 disasm`: prints a binary as source that assembles back to it.
+fn disassemble(args: &[String]) -> Result<(), String> {
+    let mut program = None;
+    let mut base = CODE_BASE;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--base" => {
+                let addr = args.next().ok_or("expected an address after --base")?;
+                base = assembler::parse_address(addr)?;
+            },
+            other if other.starts_with('-') => return Err(format!("unrecognized flag: `{other}`\n{USAGE}")),
+            other if program.is_none() => program = Some(other),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let program = program.ok_or(USAGE)?;
+
+    let bytes = fs::read(program).map_err(|err| err.to_string())?;
+    print!("{}", disassembler::disassemble(&bytes, base));
+    Ok(())
+}
+
+/// `helios32 fuzz`: replays the given inputs through a fuzz target, or feeds
+/// it generated inputs when there are none. A crashing generated input is
+/// saved as `crash-<target>-<seed>-<run>`.
+fn run_fuzzer(args: &[String]) -> Result<(), String> {
+    let mut target = None;
+    let mut runs = 10_000;
+    let mut seed = 1;
+    let mut inputs = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--runs" | "--seed" => {
+                let n = args.next()
+                    .and_then(|n| n.parse::<u64>().ok())
+                    .ok_or_else(|| format!("expected a number after {arg}"))?;
+                if arg == "--runs" {
+                    runs = n;
+                } else {
+                    seed = n;
+                }
+            },
+            other if other.starts_with('-') => return Err(format!("unrecognized flag: `{other}`\n{USAGE}")),
+            other if target.is_none() => target = Some(fuzz::Target::parse(other)?),
+            other => inputs.push(other),
+        }
+    }
+    let target = target.ok_or(USAGE)?;
+
+    if !inputs.is_empty() {
+        for path in inputs {
+            let input = fs::read(path).map_err(|err| err.to_string())?;
+            if !fuzz::survives(target, &input) {
+                return Err(format!("{path} crashed the {} target", target.name()));
+            }
+        }
+        return Ok(());
+    }
+
+    match fuzz::fuzz(target, seed, runs) {
+        None => {
+            println!("{runs} {} runs without a crash (seed {seed})", target.name());
+            Ok(())
+        },
+        Some((run, input)) => {
+            let path = format!("crash-{}-{seed}-{run}", target.name());
+            fs::write(&path, input).map_err(|err| err.to_string())?;
+            Err(format!("run {run} crashed the {} target; input saved to {path}", target.name()))
+        },
+    }
+}
+
+/// `helios32 test`: runs each file's `.expect` checks on a fresh machine.
+/// Returns whether every test passed.
+fn test(args: &[String]) -> Result<bool, String> {
+    let mut files = Vec::new();
+    let mut config = MachineConfig::default();
+    let mut engine = Engine::Interpreter;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &**arg {
+            "--machine" => {
+                let path = args.next().ok_or("expected a file path after --machine")?;
+                config = MachineConfig::load(path)?;
+            },
+            "--engine" => engine = match args.next().map(|s| &**s) {
+                Some("interpreter") => Engine::Interpreter,
+                Some("threaded") => Engine::Threaded,
+                _ => return Err("expected `interpreter` or `threaded` after --engine".to_string()),
+            },
+            other if other.starts_with('-') => return Err(format!("unrecognized flag: `{other}`\n{USAGE}")),
+            other => files.push(other),
+        }
+    }
+    if files.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut failed = 0;
+    for path in &files {
+        let result = GuestTest::load(path).and_then(|test| test.run(&config, engine));
+        match result {
+            Ok(failures) if failures.is_empty() => println!("test {path} ... ok"),
+            Ok(failures) => {
+                failed += 1;
+                println!("test {path} ... FAILED");
+                for failure in failures {
+                    println!("    {failure}");
+                }
+            },
+            Err(err) => {
+                failed += 1;
+                println!("test {path} ... FAILED");
+                println!("    {err}");
+            },
+        }
+    }
+    println!();
+    println!("{} passed; {failed} failed", files.len() - failed);
+
+    Ok(failed == 0)
 }
@@ -0,0 +1,228 @@
+use std::io::{self, BufRead, Write};
+use crate::vm::Helios32;
+use crate::vm::assembler;
+use crate::vm::float::{self, RoundingMode};
+use crate::vm::debugger::{Debugger, StopReason};
+use crate::vm::debuginfo::DebugInfo;
+use crate::vm::registers::*;
+
+const HELP: &str = "\
+commands:
+  s, step [n]              execute n instructions (default 1)
+  c, continue              run until a breakpoint, watchpoint, fault or HLT
+  rs, reverse-step [n]     undo n instructions (default 1)
+  rc, reverse-continue     run backwards until a breakpoint or watchpoint
+  b, break <addr>          set a breakpoint
+  d, delete <addr>         remove a breakpoint
+  w, watch <addr> [len]    stop on stores to len bytes (default 1)
+  unwatch <addr>           remove a watchpoint
+  r, regs                  print registers
+  set <reg> <value>        write a register or fcsr (clears reverse history)
+  reset                    return to the power-on state (clears reverse history)
+  x <addr> [len]           dump len bytes of memory (default 16)
+  who-wrote <addr>         show the last recorded store to addr
+  q, quit                  leave the debugger";
+
+/// Debug info and the source lines it refers to, if the file could be read.
+struct Source<'a> {
+    info: &'a DebugInfo,
+    lines: Vec<String>,
+}
+
+/// Interactive debugger on stdin/stdout. Returns the machine in its final state.
+///
+/// With `debug_info`, locations are shown as `symbol+offset` and `file:line:column`
+/// followed by the source line.
+pub fn run(vm: Helios32, debug_info: Option<&DebugInfo>) -> Helios32 {
+    let mut dbg = Debugger::new(vm);
+    let stdin = io::stdin();
+    let source = debug_info.map(|info| Source {
+        info,
+        lines: std::fs::read_to_string(&info.file)
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default(),
+    });
+    let source = source.as_ref();
+
+    print_location(&dbg, source);
+    loop {
+        print!("(h32) ");
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        if parts.is_empty() {
+            continue;
+        }
+
+        if let Err(err) = command(&mut dbg, source, &parts) {
+            if err.is_empty() {
+                break;
+            }
+            println!("{err}");
+        }
+    }
+
+    dbg.vm
+}
+
+/// Runs one command. An empty error means the user asked to quit.
+fn command(dbg: &mut Debugger, source: Option<&Source>, parts: &[&str]) -> Result<(), String> {
+    match parts[0] {
+        "s" | "step" => {
+            let count = parts.get(1).map(|s| parse_number(s)).transpose()?.unwrap_or(1);
+            let mut reason = StopReason::Step;
+            for _ in 0..count {
+                reason = dbg.step();
+                if reason != StopReason::Step {
+                    break;
+                }
+            }
+            report(dbg, source, reason);
+        },
+        "c" | "continue" => {
+            let reason = dbg.cont();
+            report(dbg, source, reason);
+        },
+        "rs" | "reverse-step" => {
+            let count = parts.get(1).map(|s| parse_number(s)).transpose()?.unwrap_or(1);
+            let mut reason = StopReason::Step;
+            for _ in 0..count {
+                reason = dbg.reverse_step();
+                if reason != StopReason::Step {
+                    break;
+                }
+            }
+            report(dbg, source, reason);
+        },
+        "rc" | "reverse-continue" => {
+            let reason = dbg.reverse_continue();
+            report(dbg, source, reason);
+        },
+        "b" | "break" => {
+            let addr = parse_number(parts.get(1).ok_or("expected an address")?)?;
+            dbg.add_breakpoint(addr);
+        },
+        "d" | "delete" => {
+            let addr = parse_number(parts.get(1).ok_or("expected an address")?)?;
+            if !dbg.remove_breakpoint(addr) {
+                return Err(format!("no breakpoint at 0x{addr:08X}"));
+            }
+        },
+        "w" | "watch" => {
+            let addr = parse_number(parts.get(1).ok_or("expected an address")?)?;
+            let len = parts.get(2).map(|s| parse_number(s)).transpose()?.unwrap_or(1);
+            dbg.add_watchpoint(addr, len);
+        },
+        "unwatch" => {
+            let addr = parse_number(parts.get(1).ok_or("expected an address")?)?;
+            if !dbg.remove_watchpoint(addr) {
+                return Err(format!("no watchpoint at 0x{addr:08X}"));
+            }
+        },
+        "r" | "regs" => {
+            for (i, name) in REGISTER_NAMES.iter().enumerate() {
+                print!("{name}=0x{:08X}{}", dbg.vm.core.registers[i], if i % 4 == 3 { "\n" } else { "  " });
+            }
+            let fcsr = dbg.vm.core.fcsr;
+            let mode = RoundingMode::from_bits((fcsr >> float::FCSR_ROUNDING_SHIFT) & 0x7);
+            println!(
+                "fcsr=0x{fcsr:08X}  flags: {}  rounding: {}  traps: {}",
+                or_none(float::flag_names(fcsr & float::FLAGS)),
+                mode.name(),
+                or_none(float::flag_names((fcsr >> float::FCSR_TRAP_SHIFT) & float::FLAGS)),
+            );
+        },
+        "set" => {
+            let name = parts.get(1).ok_or("expected a register")?;
+            let value = parse_number(parts.get(2).ok_or("expected a value")?)?;
+            if name.eq_ignore_ascii_case("fcsr") {
+                dbg.modify(|vm| vm.core.fcsr = value & float::FCSR_MASK);
+            } else {
+                let reg = assembler::parse_register(name)?;
+                dbg.modify(|vm| vm.core.registers[reg as usize] = value);
+            }
+        },
+        "reset" => {
+            dbg.modify(Helios32::reset);
+            print_location(dbg, source);
+        },
+        "x" => {
+            let addr = parse_number(parts.get(1).ok_or("expected an address")?)?;
+            let len = parts.get(2).map(|s| parse_number(s)).transpose()?.unwrap_or(16);
+            for row in (0..len).step_by(16) {
+                let start = addr.wrapping_add(row);
+                print!("0x{start:08X}:");
+                for i in row..(row + 16).min(len) {
+                    print!(" {:02X}", dbg.vm.mem[addr.wrapping_add(i) as usize]);
+                }
+                println!();
+            }
+        },
+        "who-wrote" => {
+            let addr = parse_number(parts.get(1).ok_or("expected an address")?)?;
+            match dbg.last_writer(addr) {
+                Some(write) => println!(
+                    "0x{addr:08X} last written at step {} by pc 0x{:08X} (was 0x{:02X})",
+                    write.step, write.pc, write.old
+                ),
+                None => println!("no recorded write to 0x{addr:08X}"),
+            }
+        },
+        "h" | "help" => println!("{HELP}"),
+        "q" | "quit" => return Err(String::new()),
+        other => return Err(format!("unknown command `{other}`, try `help`")),
+    }
+
+    Ok(())
+}
+
+fn report(dbg: &Debugger, source: Option<&Source>, reason: StopReason) {
+    match reason {
+        StopReason::Step => (),
+        StopReason::Breakpoint(addr) => println!("breakpoint at 0x{addr:08X}"),
+        StopReason::Watchpoint { addr, pc } => {
+            println!("watchpoint: 0x{addr:08X} written by pc 0x{pc:08X}")
+        },
+        StopReason::Halted => println!("machine halted"),
+        StopReason::Fault(fault) => println!("fault: {fault}"),
+        StopReason::HistoryStart => println!("reached start of recorded history"),
+    }
+    print_location(dbg, source);
+}
+
+fn print_location(dbg: &Debugger, source: Option<&Source>) {
+    let pc = dbg.vm.core.registers[RPC as usize];
+    let inst = dbg.vm.fetch(pc).to_le_bytes();
+    println!(
+        "step {} pc 0x{pc:08X}: {:02X} {:02X} {:02X} {:02X} {:02X} {:02X}",
+        dbg.steps(), inst[0], inst[1], inst[2], inst[3], inst[4], inst[5]
+    );
+
+    let Some(source) = source else { return };
+    let symbol = match source.info.symbolize(pc) {
+        Some((sym, 0)) => format!("{} ", sym.name),
+        Some((sym, offset)) => format!("{}+0x{offset:X} ", sym.name),
+        None => String::new(),
+    };
+    if let Some(entry) = source.info.location(pc) {
+        let text = source.lines.get(entry.line - 1).map_or("", |line| line.trim());
+        println!("  {symbol}at {}:{}:{}  {text}", source.info.file, entry.line, entry.column);
+    } else if !symbol.is_empty() {
+        println!("  in {}", symbol.trim_end());
+    }
+}
+
+fn or_none(list: String) -> String {
+    if list.is_empty() { "none".to_string() } else { list }
+}
+
+fn parse_number(s: &str) -> Result<u32, String> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => s.parse::<u32>(),
+    }.map_err(|_| format!("invalid number: `{s}`"))
+}
@@ -2,18 +2,72 @@ use std::collections::HashMap;
 use std::path::Path;
 use super::registers::*;
 use super::isa::*;
+use super::float::{self, RoundingMode};
 
 const REL_FLAGS: &[&str] = &["rel", "relative", "REL", "RELATIVE", "r", "R"];
 
-pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
+/// Assembled program plus the information tools need to map addresses back to source.
+pub struct Assembly {
+    pub bytes: Vec<u8>,
+    /// Address the first byte is loaded at, from `.org` or the assembler's base.
+    pub origin: u32,
+    /// Every label with its offset from the start of the program, sorted by offset.
+    pub labels: Vec<(String, u32)>,
+    /// Source position of every emitted instruction, in address order.
+    pub lines: Vec<LineInfo>,
+}
+
+/// Where the instruction at `offset` came from. Lines and columns are 1-based;
+/// the column is that of the mnemonic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineInfo {
+    pub offset: u32,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Assembly {
+    /// Every label with its offset and size, the distance to the next label at a
+    /// higher offset or to the end of the program.
+    pub fn sized_labels(&self) -> impl Iterator<Item = (&str, u32, u32)> {
+        let end = self.bytes.len() as u32;
+        self.labels.iter().map(move |(name, offset)| {
+            let next = self.labels.iter()
+                .map(|(_, other)| *other)
+                .find(|other| other > offset)
+                .unwrap_or(end);
+            (name.as_str(), *offset, next - offset)
+        })
+    }
+}
+
+/// Most zero bytes a `.org` after the first instruction may pad with.
+pub const MAX_ORG_GAP: u32 = 1 << 20;
+
+/// Assembles a program loaded at `base` unless the source sets its own origin
+/// with `.org`.
+///
+/// Labels resolve to absolute addresses: the absolute forms of `jmi`, `jii`,
+/// `cai` and `cii` and `ldi` encode the label's address, while the relative
+/// forms encode its distance from the instruction.
+///
+/// `.org <address>` before the first instruction sets the origin. Later
+/// occurrences move forward to `address`, filling the gap with zeros; gaps
+/// larger than [`MAX_ORG_GAP`] are rejected.
+///
+/// `ldd <pair> <double>` loads a double into a register pair and assembles to
+/// an `ldi` of each half.
+pub fn assemble(source: &str, base: u32) -> Result<Assembly, String> {
     let mut result = Vec::new();
     let lines = source.lines()
         .enumerate()
         .collect::<Vec<_>>();
 
     let mut labels = HashMap::new();
-    let mut current_addr: u32 = 0;
-    for (_, line) in &lines {
+    let mut origin = base;
+    let mut emitted = false;
+    let mut current_addr: u32 = base;
+    for (idx, line) in &lines {
         if line.is_empty() { continue }
         let line = line.split_terminator(";").collect::<Vec<_>>();
         let parts = line[0].split_whitespace()
@@ -21,31 +75,101 @@ pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
         if parts.is_empty() { continue }
 
         match parts[0] {
+            ".org" | ".ORG" => {
+                let addr = parse_org(*idx, &parts)?;
+                if !emitted {
+                    // Labels so far all point at the old origin.
+                    labels.values_mut().for_each(|label| *label = addr);
+                    origin = addr;
+                } else if addr < current_addr {
+                    return Err(format!(
+                        "error on line {}: `.org 0x{addr:08X}` is below the current address 0x{current_addr:08X}",
+                        idx + 1
+                    ));
+                } else if addr - current_addr > MAX_ORG_GAP {
+                    return Err(format!(
+                        "error on line {}: `.org 0x{addr:08X}` would pad 0x{:X} bytes after 0x{current_addr:08X}, more than the 0x{MAX_ORG_GAP:X} allowed",
+                        idx + 1, addr - current_addr
+                    ));
+                }
+                current_addr = addr;
+            },
             other if other.ends_with(":") => {
                 let label_name = other.strip_suffix(":").unwrap();
 
                 labels.insert(label_name, current_addr);
 
                 if parts.len() != 1 {
-                    current_addr += 6;
+                    emitted = true;
+                    current_addr = next_address(*idx, current_addr, parts[1])?;
                 }
             },
-            _ => current_addr += 6,
+            mnemonic => {
+                emitted = true;
+                current_addr = next_address(*idx, current_addr, mnemonic)?;
+            },
         }
     }
 
-    let mut current_addr: u32 = 0;
+    let mut line_info = Vec::new();
+    let mut current_addr: u32 = origin;
     for (idx, line) in lines {
         if line.is_empty() { continue }
+        let text = line;
         let line = line.split_terminator(";").collect::<Vec<_>>();
         let parts = line[0].split_whitespace()
             .collect::<Vec<_>>();
         if parts.is_empty() { continue }
 
+        if matches!(parts[0], ".org" | ".ORG") {
+            let addr = parse_org(idx, &parts)?;
+            // Only `.org`s after the first instruction pad; earlier ones just move the origin.
+            if !result.is_empty() {
+                result.resize(result.len() + (addr - current_addr) as usize, 0);
+            }
+            current_addr = addr;
+            continue;
+        }
+
+        let addr = current_addr;
         assemble_parts(idx, &parts, &mut result, &labels, &mut current_addr)?;
+        if current_addr != addr {
+            let mnemonic = if parts[0].ends_with(":") { parts[1] } else { parts[0] };
+            line_info.push(LineInfo {
+                offset: addr - origin,
+                line: idx + 1,
+                column: mnemonic.as_ptr() as usize - text.as_ptr() as usize + 1,
+            });
+        }
     }
 
-    Ok(result)
+    let mut labels = labels.into_iter()
+        .map(|(name, addr)| (name.to_string(), addr - origin))
+        .collect::<Vec<_>>();
+    labels.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+    Ok(Assembly { bytes: result, origin, labels, lines: line_info })
+}
+
+/// Address of the instruction after one at `addr`. `ldd` expands to two
+/// instructions.
+fn next_address(idx: usize, addr: u32, mnemonic: &str) -> Result<u32, String> {
+    let size = if matches!(mnemonic, "ldd" | "LDD") { 12 } else { 6 };
+    addr.checked_add(size).ok_or_else(|| format!(
+        "error on line {}: program does not fit in the 32-bit address space",
+        idx + 1
+    ))
+}
+
+fn parse_org(idx: usize, parts: &[&str]) -> Result<u32, String> {
+    if parts.len() != 2 {
+        return Err(format!(
+            "error on line {}: invalid operand count for .org directive",
+            idx + 1
+        ));
+    }
+    parse_address(parts[1])
+        .map_err(|err| format!("error on line {}: {err}", idx+1))
 }
 
 fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &HashMap<&str, u32>, current_addr: &mut u32) -> Result<(), String> {
@@ -78,21 +202,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
             let dest = parse_register(parts[1])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
 
-            let imm = if parts[2].starts_with("+") {
-                parse_immediate(
-                    parts[2].strip_prefix("+")
-                        .unwrap()
-                ).map_err(|err| format!("error on line {}: {err}", idx+1))?
-            } else if parts[2].starts_with("-") {
-                -(parse_immediate(
-                    parts[2].strip_prefix("-")
-                        .unwrap()
-                ).map_err(
-                    |err|
-                    format!("error on line {}: {err}", idx+1)
-                )? as i32) as u32
+            let imm = if let Some(addr) = labels.get(parts[2]) {
+                *addr
             } else {
-                parse_immediate(parts[2])
+                parse_signed_immediate(parts[2])
                     .map_err(|err| format!("error on line {}: {err}", idx+1))?
             };
             result.extend([
@@ -104,6 +217,31 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 ((imm >> 28) & 0xF) as u8
             ]);
         },
+        "ldd" | "LDD" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for LDD instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let bits = parse_double(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            // An `ldi` of each half, low word first.
+            for (dest, imm) in [(dest, bits as u32), (dest + 1, (bits >> 32) as u32)] {
+                result.extend([
+                    LDI,
+                    dest | (((imm & 0xF) as u8) << 4),
+                    ((imm >> 4) & 0xFF) as u8,
+                    ((imm >> 12) & 0xFF) as u8,
+                    ((imm >> 20) & 0xFF) as u8,
+                    ((imm >> 28) & 0xF) as u8
+                ]);
+            }
+            *current_addr += 6;
+        },
         "add" | "ADD" => {
             if parts.len() != 4 {
                 return Err(format!(
@@ -157,14 +295,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 BOR,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "band" | "BAND" => {
@@ -178,14 +316,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 BAND,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "bxor" | "BXOR" => {
@@ -199,20 +337,20 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 BXOR,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "bnot" | "BNOT" => {
             if parts.len() != 3 {
                 return Err(format!(
-                    "error on line {}: invalid operand count for BNOY instruction",
+                    "error on line {}: invalid operand count for BNOT instruction",
                     idx + 1
                 ));
             }
@@ -315,15 +453,16 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     idx + 1
                 ));
             }
-            let dest = parse_register(parts[1])
+            let (dest, offset) = parse_memory_operand(parts[1])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            
+            let offset = offset.to_le_bytes();
+
             result.extend([
                 SB,
                 dest | (src1 << 4),
-                0, 0, 0, 0
+                offset[0], offset[1], offset[2], offset[3]
             ]);
         },
         "sw" | "SW" => {
@@ -333,15 +472,16 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     idx + 1
                 ));
             }
-            let dest = parse_register(parts[1])
+            let (dest, offset) = parse_memory_operand(parts[1])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            
+            let offset = offset.to_le_bytes();
+
             result.extend([
                 SW,
                 dest | (src1 << 4),
-                0, 0, 0, 0
+                offset[0], offset[1], offset[2], offset[3]
             ]);
         },
         "lbs" | "LBS" => {
@@ -353,13 +493,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
             }
             let dest = parse_register(parts[1])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src1 = parse_register(parts[2])
+            let (src1, offset) = parse_memory_operand(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            
+            let offset = offset.to_le_bytes();
+
             result.extend([
                 LBS,
                 dest | (src1 << 4),
-                0, 0, 0, 0
+                offset[0], offset[1], offset[2], offset[3]
             ]);
         },
         "lbu" | "LBU" => {
@@ -371,13 +512,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
             }
             let dest = parse_register(parts[1])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src1 = parse_register(parts[2])
+            let (src1, offset) = parse_memory_operand(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            
+            let offset = offset.to_le_bytes();
+
             result.extend([
                 LBU,
                 dest | (src1 << 4),
-                0, 0, 0, 0
+                offset[0], offset[1], offset[2], offset[3]
             ]);
         },
         "lw" | "LW" => {
@@ -389,13 +531,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
             }
             let dest = parse_register(parts[1])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src1 = parse_register(parts[2])
+            let (src1, offset) = parse_memory_operand(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            
+            let offset = offset.to_le_bytes();
+
             result.extend([
                 LW,
                 dest | (src1 << 4),
-                0, 0, 0, 0
+                offset[0], offset[1], offset[2], offset[3]
             ]);
         },
         "jmr" | "JMR" => {
@@ -534,10 +677,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                             .unwrap()
                     ).map_err(|_| ())?
                 } else if parts[1].starts_with("-") {
-                    -(parse_immediate(
+                    parse_immediate(
                         parts[1].strip_prefix("-")
                             .unwrap()
-                    ).map_err(|_| ())? as i32) as u32
+                    ).map_err(|_| ())?.wrapping_neg()
                 } else {
                     parse_immediate(parts[1])
                         .map_err(|_| ())?
@@ -545,7 +688,7 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     Ok(d) => d,
                     Err(_) => {
                         if let Some(addr) = labels.get(parts[1]) {
-                            (*addr as i32 - *current_addr as i32) as u32
+                            *addr
                         } else {
                             return Err(format!(
                                 "error on line {}: invalid jump target `{}`",
@@ -576,10 +719,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                             .unwrap()
                     ).map_err(|_| ())?
                 } else if parts[2].starts_with("-") {
-                    -(parse_immediate(
+                    parse_immediate(
                         parts[2].strip_prefix("-")
                             .unwrap()
-                    ).map_err(|_| ())? as i32) as u32
+                    ).map_err(|_| ())?.wrapping_neg()
                 } else {
                     parse_immediate(parts[2])
                         .map_err(|_| ())?
@@ -587,12 +730,12 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     Ok(d) => d,
                     Err(_) => {
                         if let Some(addr) = labels.get(parts[2]) {
-                            (*addr as i32 - *current_addr as i32) as u32
+                            addr.wrapping_sub(*current_addr)
                         } else {
                             return Err(format!(
                                 "error on line {}: invalid jump target `{}`",
                                 idx+1,
-                                parts[1]
+                                parts[2]
                             ));
                         }
                     }
@@ -607,7 +750,7 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 ]);
             } else {
                 return Err(format!(
-                    "error on line {}: invalid operand count for JMR instruction",
+                    "error on line {}: invalid operand count for JMI instruction",
                     idx + 1
                 ));
             }
@@ -620,10 +763,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                             .unwrap()
                     ).map_err(|_| ())?
                 } else if parts[1].starts_with("-") {
-                    -(parse_immediate(
+                    parse_immediate(
                         parts[1].strip_prefix("-")
                             .unwrap()
-                    ).map_err(|_| ())? as i32) as u32
+                    ).map_err(|_| ())?.wrapping_neg()
                 } else {
                     parse_immediate(parts[1])
                         .map_err(|_| ())?
@@ -631,7 +774,7 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     Ok(d) => d,
                     Err(_) => {
                         if let Some(addr) = labels.get(parts[1]) {
-                            (*addr as i32 - *current_addr as i32) as u32
+                            *addr
                         } else {
                             return Err(format!(
                                 "error on line {}: invalid jump target `{}`",
@@ -664,10 +807,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                             .unwrap()
                     ).map_err(|_| ())?
                 } else if parts[2].starts_with("-") {
-                    -(parse_immediate(
+                    parse_immediate(
                         parts[2].strip_prefix("-")
                             .unwrap()
-                    ).map_err(|_| ())? as i32) as u32
+                    ).map_err(|_| ())?.wrapping_neg()
                 } else {
                     parse_immediate(parts[2])
                         .map_err(|_| ())?
@@ -675,12 +818,12 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     Ok(d) => d,
                     Err(_) => {
                         if let Some(addr) = labels.get(parts[2]) {
-                            (*addr as i32 - *current_addr as i32) as u32
+                            addr.wrapping_sub(*current_addr)
                         } else {
                             return Err(format!(
                                 "error on line {}: invalid jump target `{}`",
                                 idx+1,
-                                parts[1]
+                                parts[2]
                             ));
                         }
                     }
@@ -710,10 +853,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                             .unwrap()
                     ).map_err(|_| ())?
                 } else if parts[1].starts_with("-") {
-                    -(parse_immediate(
+                    parse_immediate(
                         parts[1].strip_prefix("-")
                             .unwrap()
-                    ).map_err(|_| ())? as i32) as u32
+                    ).map_err(|_| ())?.wrapping_neg()
                 } else {
                     parse_immediate(parts[1])
                         .map_err(|_| ())?
@@ -721,7 +864,7 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     Ok(d) => d,
                     Err(_) => {
                         if let Some(addr) = labels.get(parts[1]) {
-                            (*addr as i32 - *current_addr as i32) as u32
+                            *addr
                         } else {
                             return Err(format!(
                                 "error on line {}: invalid jump target `{}`",
@@ -752,10 +895,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                             .unwrap()
                     ).map_err(|_| ())?
                 } else if parts[2].starts_with("-") {
-                    -(parse_immediate(
+                    parse_immediate(
                         parts[2].strip_prefix("-")
                             .unwrap()
-                    ).map_err(|_| ())? as i32) as u32
+                    ).map_err(|_| ())?.wrapping_neg()
                 } else {
                     parse_immediate(parts[2])
                         .map_err(|_| ())?
@@ -763,12 +906,12 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     Ok(d) => d,
                     Err(_) => {
                         if let Some(addr) = labels.get(parts[2]) {
-                            (*addr as i32 - *current_addr as i32) as u32
+                            addr.wrapping_sub(*current_addr)
                         } else {
                             return Err(format!(
                                 "error on line {}: invalid jump target `{}`",
                                 idx+1,
-                                parts[1]
+                                parts[2]
                             ));
                         }
                     }
@@ -783,7 +926,7 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 ]);
             } else {
                 return Err(format!(
-                    "error on line {}: invalid operand count for CAi instruction",
+                    "error on line {}: invalid operand count for CAI instruction",
                     idx + 1
                 ));
             }
@@ -796,10 +939,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                             .unwrap()
                     ).map_err(|_| ())?
                 } else if parts[1].starts_with("-") {
-                    -(parse_immediate(
+                    parse_immediate(
                         parts[1].strip_prefix("-")
                             .unwrap()
-                    ).map_err(|_| ())? as i32) as u32
+                    ).map_err(|_| ())?.wrapping_neg()
                 } else {
                     parse_immediate(parts[1])
                         .map_err(|_| ())?
@@ -807,7 +950,7 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     Ok(d) => d,
                     Err(_) => {
                         if let Some(addr) = labels.get(parts[1]) {
-                            (*addr as i32 - *current_addr as i32) as u32
+                            *addr
                         } else {
                             return Err(format!(
                                 "error on line {}: invalid jump target `{}`",
@@ -840,10 +983,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                             .unwrap()
                     ).map_err(|_| ())?
                 } else if parts[2].starts_with("-") {
-                    -(parse_immediate(
+                    parse_immediate(
                         parts[2].strip_prefix("-")
                             .unwrap()
-                    ).map_err(|_| ())? as i32) as u32
+                    ).map_err(|_| ())?.wrapping_neg()
                 } else {
                     parse_immediate(parts[2])
                         .map_err(|_| ())?
@@ -851,12 +994,12 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                     Ok(d) => d,
                     Err(_) => {
                         if let Some(addr) = labels.get(parts[2]) {
-                            (*addr as i32 - *current_addr as i32) as u32
+                            addr.wrapping_sub(*current_addr)
                         } else {
                             return Err(format!(
                                 "error on line {}: invalid jump target `{}`",
                                 idx+1,
-                                parts[1]
+                                parts[2]
                             ));
                         }
                     }
@@ -898,14 +1041,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 EQ,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "ne" | "NE" => {
@@ -919,14 +1062,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 NE,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "gt" | "GT" => {
@@ -940,14 +1083,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 GT,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "lt" | "LT" => {
@@ -961,14 +1104,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 LT,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "ge" | "GE" => {
@@ -982,14 +1125,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 GE,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "le" | "LE" => {
@@ -1003,14 +1146,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 LE,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "inc" | "INC" => {
@@ -1062,10 +1205,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                         .unwrap()
                 ).map_err(|err| format!("error on line {}: {err}", idx+1))?
             } else if parts[3].starts_with("-") {
-                -(parse_immediate(
+                parse_immediate(
                     parts[3].strip_prefix("-")
                         .unwrap()
-                ).map_err(|err| format!("error on line {}: {err}", idx+1))? as i32) as u32
+                ).map_err(|err| format!("error on line {}: {err}", idx+1))?.wrapping_neg()
             } else {
                 parse_immediate(parts[3])
                     .map_err(|err| format!("error on line {}: {err}", idx+1))?
@@ -1094,10 +1237,10 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                         .unwrap()
                 ).map_err(|err| format!("error on line {}: {err}", idx+1))?
             } else if parts[3].starts_with("-") {
-                -(parse_immediate(
+                parse_immediate(
                     parts[3].strip_prefix("-")
                         .unwrap()
-                ).map_err(|err| format!("error on line {}: {err}", idx+1))? as i32) as u32
+                ).map_err(|err| format!("error on line {}: {err}", idx+1))?.wrapping_neg()
             } else {
                 parse_immediate(parts[3])
                     .map_err(|err| format!("error on line {}: {err}", idx+1))?
@@ -1120,14 +1263,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 SHL,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "lshr" | "LSHR" => {
@@ -1141,14 +1284,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 LSHR,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "ashr" | "ASHR" => {
@@ -1162,14 +1305,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 ASHR,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "rotl" | "ROTL" => {
@@ -1183,14 +1326,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 ROTL,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "rotr" | "ROTR" => {
@@ -1204,14 +1347,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 ROTR,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "pb" | "PB" => {
@@ -1305,14 +1448,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 MUL,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "div" | "DIV" => {
@@ -1326,14 +1469,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 DIV,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "rem" | "REM" => {
@@ -1347,14 +1490,14 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
             let src1 = parse_register(parts[2])
                 .map_err(|err| format!("error on line {}: {err}", idx+1))?;
-            let src2 = parse_register(parts[3])
-                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
             
             result.extend([
                 REM,
                 dest | (src1 << 4),
-                src2,
-                0, 0, 0
+                src2[0], src2[1], src2[2], src2[3]
             ]);
         },
         "fadd" | "FADD" => {
@@ -1504,63 +1647,1496 @@ fn assemble_parts(idx: usize, parts: &[&str], result: &mut Vec<u8>, labels: &Has
                 0, 0, 0
             ]);
         },
-        other if other.ends_with(":") => if parts.len() != 1 {
-            assemble_parts(idx, &parts[1..], result, labels, current_addr)?;
-            return Ok(());
-        } else {
-            return Ok(());
+        "sgt" | "SGT" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SGT instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
+
+            result.extend([
+                SGT,
+                dest | (src1 << 4),
+                src2[0], src2[1], src2[2], src2[3]
+            ]);
         },
-        other => return Err(format!(
-            "error on line {}: unrecognized instruction: `{other}`",
-            idx + 1
-        )),
-    }
+        "slt" | "SLT" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SLT instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
 
-    *current_addr += 6;
+            result.extend([
+                SLT,
+                dest | (src1 << 4),
+                src2[0], src2[1], src2[2], src2[3]
+            ]);
+        },
+        "sge" | "SGE" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SGE instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
 
-    Ok(())
-}
+            result.extend([
+                SGE,
+                dest | (src1 << 4),
+                src2[0], src2[1], src2[2], src2[3]
+            ]);
+        },
+        "sle" | "SLE" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SLE instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
 
-pub fn assemble_from_path<P: AsRef<Path>>(path: P) -> Result<String, String> {
-    use std::fs;
+            result.extend([
+                SLE,
+                dest | (src1 << 4),
+                src2[0], src2[1], src2[2], src2[3]
+            ]);
+        },
+        "sdiv" | "SDIV" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SDIV instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
 
-    let source = fs::read_to_string(&path)
-        .map_err(|err| err.to_string())?;
-    let bytes = assemble(&source)?;
-    let output = format!(
-        "{}.bin",
-        path.as_ref().file_stem().unwrap().display()
-    );
-    fs::write(&output, bytes)
-        .map_err(|err| err.to_string())?;
-    Ok(output)
-}
+            result.extend([
+                SDIV,
+                dest | (src1 << 4),
+                src2[0], src2[1], src2[2], src2[3]
+            ]);
+        },
+        "srem" | "SREM" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SREM instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_operand(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?
+                .to_le_bytes();
 
-pub fn parse_register(s: &str) -> Result<u8, String> {
-    match &*s.to_lowercase() {
-        "rds" => Ok(RDS),
-        "gr0" => Ok(GR0),
-        "gr1" => Ok(GR1),
-        "gr2" => Ok(GR2),
-        "gr3" => Ok(GR3),
-        "gr4" => Ok(GR4),
-        "gr5" => Ok(GR5),
-        "gr6" => Ok(GR6),
-        "gr7" => Ok(GR7),
-        "gr8" => Ok(GR8),
-        "gr9" => Ok(GR9),
-        "gr10" | "gra" => Ok(GRA),
-        "gr11" | "grb" => Ok(GRB),
-        "rsp" => Ok(RSP),
+            result.extend([
+                SREM,
+                dest | (src1 << 4),
+                src2[0], src2[1], src2[2], src2[3]
+            ]);
+        },
+        "feq" | "FEQ" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FEQ instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FEQ,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "flt" | "FLT" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FLT instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FLT,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "fle" | "FLE" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FLE instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FLE,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "fmin" | "FMIN" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FMIN instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FMIN,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "fmax" | "FMAX" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FMAX instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FMAX,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "fsqrt" | "FSQRT" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FSQRT instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FSQRT,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "fabs" | "FABS" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FABS instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FABS,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "fneg" | "FNEG" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FNEG instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FNEG,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "fma" | "FMA" => {
+            if parts.len() != 5 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FMA instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src3 = parse_register(parts[4])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FMA,
+                dest | (src1 << 4),
+                src2 | (src3 << 4),
+                0, 0, 0
+            ]);
+        },
+        "itof" | "ITOF" => {
+            if parts.len() != 3 && parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for ITOF instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let mode = match parts.get(3) {
+                Some(mode) => float::parse_rounding(mode)
+                    .ok_or_else(|| format!("error on line {}: invalid rounding mode `{mode}`", idx+1))?,
+                None => RoundingMode::NearestEven as u8,
+            };
+
+            result.extend([
+                ITOF,
+                dest | (src1 << 4),
+                mode,
+                0, 0, 0
+            ]);
+        },
+        "utof" | "UTOF" => {
+            if parts.len() != 3 && parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for UTOF instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let mode = match parts.get(3) {
+                Some(mode) => float::parse_rounding(mode)
+                    .ok_or_else(|| format!("error on line {}: invalid rounding mode `{mode}`", idx+1))?,
+                None => RoundingMode::NearestEven as u8,
+            };
+
+            result.extend([
+                UTOF,
+                dest | (src1 << 4),
+                mode,
+                0, 0, 0
+            ]);
+        },
+        "ftoi" | "FTOI" => {
+            if parts.len() != 3 && parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FTOI instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let mode = match parts.get(3) {
+                Some(mode) => float::parse_rounding(mode)
+                    .ok_or_else(|| format!("error on line {}: invalid rounding mode `{mode}`", idx+1))?,
+                None => RoundingMode::TowardZero as u8,
+            };
+
+            result.extend([
+                FTOI,
+                dest | (src1 << 4),
+                mode,
+                0, 0, 0
+            ]);
+        },
+        "ftou" | "FTOU" => {
+            if parts.len() != 3 && parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FTOU instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let mode = match parts.get(3) {
+                Some(mode) => float::parse_rounding(mode)
+                    .ok_or_else(|| format!("error on line {}: invalid rounding mode `{mode}`", idx+1))?,
+                None => RoundingMode::TowardZero as u8,
+            };
+
+            result.extend([
+                FTOU,
+                dest | (src1 << 4),
+                mode,
+                0, 0, 0
+            ]);
+        },
+        "frcsr" | "FRCSR" => {
+            if parts.len() != 2 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FRCSR instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FRCSR,
+                dest,
+                0, 0, 0, 0
+            ]);
+        },
+        "fwcsr" | "FWCSR" => {
+            if parts.len() != 2 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FWCSR instruction",
+                    idx + 1
+                ));
+            }
+            let src = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FWCSR,
+                src,
+                0, 0, 0, 0
+            ]);
+        },
+        "dadd" | "DADD" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DADD instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_pair(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DADD,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "dsub" | "DSUB" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DSUB instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_pair(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DSUB,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "dmul" | "DMUL" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DMUL instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_pair(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DMUL,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "ddiv" | "DDIV" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DDIV instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_pair(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DDIV,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "dsqrt" | "DSQRT" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DSQRT instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DSQRT,
+                dest | (src1 << 4),
+                0,
+                0, 0, 0
+            ]);
+        },
+        "dabs" | "DABS" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DABS instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DABS,
+                dest | (src1 << 4),
+                0,
+                0, 0, 0
+            ]);
+        },
+        "dneg" | "DNEG" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DNEG instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DNEG,
+                dest | (src1 << 4),
+                0,
+                0, 0, 0
+            ]);
+        },
+        "deq" | "DEQ" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DEQ instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_pair(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DEQ,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "dlt" | "DLT" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DLT instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_pair(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DLT,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "dle" | "DLE" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DLE instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_pair(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                DLE,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "ftod" | "FTOD" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FTOD instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                FTOD,
+                dest | (src1 << 4),
+                0,
+                0, 0, 0
+            ]);
+        },
+        "dtof" | "DTOF" => {
+            if parts.len() != 3 && parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DTOF instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let mode = match parts.get(3) {
+                Some(mode) => float::parse_rounding(mode)
+                    .ok_or_else(|| format!("error on line {}: invalid rounding mode `{mode}`", idx+1))?,
+                None => RoundingMode::NearestEven as u8,
+            };
+
+            result.extend([
+                DTOF,
+                dest | (src1 << 4),
+                mode,
+                0, 0, 0
+            ]);
+        },
+        "itod" | "ITOD" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for ITOD instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                ITOD,
+                dest | (src1 << 4),
+                0,
+                0, 0, 0
+            ]);
+        },
+        "utod" | "UTOD" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for UTOD instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_pair(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                UTOD,
+                dest | (src1 << 4),
+                0,
+                0, 0, 0
+            ]);
+        },
+        "dtoi" | "DTOI" => {
+            if parts.len() != 3 && parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DTOI instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let mode = match parts.get(3) {
+                Some(mode) => float::parse_rounding(mode)
+                    .ok_or_else(|| format!("error on line {}: invalid rounding mode `{mode}`", idx+1))?,
+                None => RoundingMode::TowardZero as u8,
+            };
+
+            result.extend([
+                DTOI,
+                dest | (src1 << 4),
+                mode,
+                0, 0, 0
+            ]);
+        },
+        "dtou" | "DTOU" => {
+            if parts.len() != 3 && parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for DTOU instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_pair(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let mode = match parts.get(3) {
+                Some(mode) => float::parse_rounding(mode)
+                    .ok_or_else(|| format!("error on line {}: invalid rounding mode `{mode}`", idx+1))?,
+                None => RoundingMode::TowardZero as u8,
+            };
+
+            result.extend([
+                DTOU,
+                dest | (src1 << 4),
+                mode,
+                0, 0, 0
+            ]);
+        },
+        "sh" | "SH" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SH instruction",
+                    idx + 1
+                ));
+            }
+            let (dest, offset) = parse_memory_operand(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let offset = offset.to_le_bytes();
+
+            result.extend([
+                SH,
+                dest | (src1 << 4),
+                offset[0], offset[1], offset[2], offset[3]
+            ]);
+        },
+        "lhs" | "LHS" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for LHS instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let (src1, offset) = parse_memory_operand(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let offset = offset.to_le_bytes();
+
+            result.extend([
+                LHS,
+                dest | (src1 << 4),
+                offset[0], offset[1], offset[2], offset[3]
+            ]);
+        },
+        "lhu" | "LHU" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for LHU instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let (src1, offset) = parse_memory_operand(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let offset = offset.to_le_bytes();
+
+            result.extend([
+                LHU,
+                dest | (src1 << 4),
+                offset[0], offset[1], offset[2], offset[3]
+            ]);
+        },
+        "swap" | "SWAP" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SWAP instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                SWAP,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "cas" | "CAS" => {
+            if parts.len() != 5 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for CAS instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src3 = parse_register(parts[4])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                CAS,
+                dest | (src1 << 4),
+                src2 | (src3 << 4),
+                0, 0, 0
+            ]);
+        },
+        "xadd" | "XADD" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for XADD instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                XADD,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "lr" | "LR" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for LR instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                LR,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "sc" | "SC" => {
+            if parts.len() != 4 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SC instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                SC,
+                dest | (src1 << 4),
+                src2,
+                0, 0, 0
+            ]);
+        },
+        "fence" | "FENCE" => {
+            if parts.len() != 1 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for FENCE instruction",
+                    idx + 1
+                ));
+            }
+
+            result.extend([FENCE, 0, 0, 0, 0, 0]);
+        },
+        "cid" | "CID" => {
+            if parts.len() != 2 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for CID instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                CID,
+                dest,
+                0, 0, 0, 0
+            ]);
+        },
+        "ncores" | "NCORES" => {
+            if parts.len() != 2 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for NCORES instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                NCORES,
+                dest,
+                0, 0, 0, 0
+            ]);
+        },
+        "ipi" | "IPI" => {
+            if parts.len() != 2 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for IPI instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                IPI,
+                dest,
+                0, 0, 0, 0
+            ]);
+        },
+        "siv" | "SIV" => {
+            if parts.len() != 2 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SIV instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                SIV,
+                dest,
+                0, 0, 0, 0
+            ]);
+        },
+        "iack" | "IACK" => {
+            if parts.len() != 2 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for IACK instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                IACK,
+                dest,
+                0, 0, 0, 0
+            ]);
+        },
+        "iret" | "IRET" => {
+            if parts.len() != 1 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for IRET instruction",
+                    idx + 1
+                ));
+            }
+
+            result.extend([IRET, 0, 0, 0, 0, 0]);
+        },
+        "wfi" | "WFI" => {
+            if parts.len() != 1 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for WFI instruction",
+                    idx + 1
+                ));
+            }
+
+            result.extend([WFI, 0, 0, 0, 0, 0]);
+        },
+        "clz" | "CLZ" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for CLZ instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                CLZ,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "ctz" | "CTZ" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for CTZ instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                CTZ,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "popc" | "POPC" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for POPC instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                POPC,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "bswap" | "BSWAP" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for BSWAP instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                BSWAP,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "bfx" | "BFX" => {
+            if parts.len() != 5 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for BFX instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let (lsb, width) = parse_bit_field(parts[3], parts[4])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                BFX,
+                dest | (src1 << 4),
+                lsb, width,
+                0, 0
+            ]);
+        },
+        "bfi" | "BFI" => {
+            if parts.len() != 5 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for BFI instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let (lsb, width) = parse_bit_field(parts[3], parts[4])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                BFI,
+                dest | (src1 << 4),
+                lsb, width,
+                0, 0
+            ]);
+        },
+        "sextb" | "SEXTB" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SEXTB instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                SEXTB,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "sexth" | "SEXTH" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SEXTH instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                SEXTH,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "zextb" | "ZEXTB" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for ZEXTB instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                ZEXTB,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "zexth" | "ZEXTH" => {
+            if parts.len() != 3 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for ZEXTH instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                ZEXTH,
+                dest | (src1 << 4),
+                0, 0, 0, 0
+            ]);
+        },
+        "adc" | "ADC" => {
+            if parts.len() != 5 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for ADC instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src3 = parse_register(parts[4])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                ADC,
+                dest | (src1 << 4),
+                src2 | (src3 << 4),
+                0, 0, 0
+            ]);
+        },
+        "sbb" | "SBB" => {
+            if parts.len() != 5 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SBB instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src3 = parse_register(parts[4])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                SBB,
+                dest | (src1 << 4),
+                src2 | (src3 << 4),
+                0, 0, 0
+            ]);
+        },
+        "addo" | "ADDO" => {
+            if parts.len() != 5 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for ADDO instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src3 = parse_register(parts[4])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                ADDO,
+                dest | (src1 << 4),
+                src2 | (src3 << 4),
+                0, 0, 0
+            ]);
+        },
+        "subo" | "SUBO" => {
+            if parts.len() != 5 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SUBO instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src3 = parse_register(parts[4])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                SUBO,
+                dest | (src1 << 4),
+                src2 | (src3 << 4),
+                0, 0, 0
+            ]);
+        },
+        "sel" | "SEL" => {
+            if parts.len() != 5 {
+                return Err(format!(
+                    "error on line {}: invalid operand count for SEL instruction",
+                    idx + 1
+                ));
+            }
+            let dest = parse_register(parts[1])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src1 = parse_register(parts[2])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src2 = parse_register(parts[3])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+            let src3 = parse_register(parts[4])
+                .map_err(|err| format!("error on line {}: {err}", idx+1))?;
+
+            result.extend([
+                SEL,
+                dest | (src1 << 4),
+                src2 | (src3 << 4),
+                0, 0, 0
+            ]);
+        },
+        other if other.ends_with(":") => if parts.len() != 1 {
+            assemble_parts(idx, &parts[1..], result, labels, current_addr)?;
+            return Ok(());
+        } else {
+            return Ok(());
+        },
+        other => return Err(format!(
+            "error on line {}: unrecognized instruction: `{other}`",
+            idx + 1
+        )),
+    }
+
+    *current_addr += 6;
+
+    Ok(())
+}
+
+/// Assembles the file at `path` for `base` and writes the binary to `output`,
+/// or to `<stem>.bin` in the working directory.
+pub fn assemble_from_path<P: AsRef<Path>>(path: P, base: u32, output: Option<&str>) -> Result<Assembly, String> {
+    use std::fs;
+
+    let source = fs::read_to_string(&path)
+        .map_err(|err| err.to_string())?;
+    let assembly = assemble(&source, base)?;
+    let output = output.map_or_else(
+        || format!("{}.bin", path.as_ref().file_stem().unwrap().display()),
+        str::to_string
+    );
+    fs::write(&output, &assembly.bytes)
+        .map_err(|err| err.to_string())?;
+    Ok(assembly)
+}
+
+pub fn parse_register(s: &str) -> Result<u8, String> {
+    match &*s.to_lowercase() {
+        "rds" => Ok(RDS),
+        "gr0" => Ok(GR0),
+        "gr1" => Ok(GR1),
+        "gr2" => Ok(GR2),
+        "gr3" => Ok(GR3),
+        "gr4" => Ok(GR4),
+        "gr5" => Ok(GR5),
+        "gr6" => Ok(GR6),
+        "gr7" => Ok(GR7),
+        "gr8" => Ok(GR8),
+        "gr9" => Ok(GR9),
+        "gr10" | "gra" => Ok(GRA),
+        "gr11" | "grb" => Ok(GRB),
+        "rsp" => Ok(RSP),
         "csp" => Ok(CSP),
         "rpc" => Ok(RPC),
         _ => Err(format!("invalid register: `{s}`"))
     }
 }
 
+/// Parses the low register of a pair: gr0, gr2, gr4, gr6, gr8 or gra.
+pub fn parse_pair(s: &str) -> Result<u8, String> {
+    let reg = parse_register(s)?;
+    if !is_pair(reg) {
+        return Err(format!("`{s}` is not a register pair; expected gr0, gr2, gr4, gr6, gr8 or gra"));
+    }
+    Ok(reg)
+}
+
+/// Parses a double: anything `f64` accepts, or its bit pattern in hex.
+fn parse_double(s: &str) -> Result<u64, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse::<f64>().ok().map(f64::to_bits),
+    }.ok_or_else(|| format!("invalid double: `{s}`"))
+}
+
+/// Parses the last operand of an instruction with an immediate form into
+/// bits 16..47 of its encoding: a register, or an immediate that survives
+/// sign extension from 31 bits, tagged with [`IMMEDIATE_FLAG`].
+fn parse_operand(s: &str) -> Result<u32, String> {
+    if let Ok(reg) = parse_register(s) {
+        return Ok(reg as u32);
+    }
+    let imm = parse_signed_immediate(s)
+        .map_err(|_| format!("invalid register or immediate: `{s}`"))?;
+
+    if (((imm << 1) as i32) >> 1) as u32 != imm {
+        return Err(format!("immediate `{s}` does not fit in 31 bits"));
+    }
+    Ok((imm & 0x7FFF_FFFF) | (IMMEDIATE_FLAG >> 16) as u32)
+}
+
+/// Parses the `lsb width` operands of `bfx` and `bfi`: a field of 1 to 32
+/// bits that lies entirely within a word.
+fn parse_bit_field(lsb: &str, width: &str) -> Result<(u8, u8), String> {
+    let number = |s: &str| parse_address(s).map_err(|_| format!("invalid bit position: `{s}`"));
+    let (lsb_bits, width_bits) = (number(lsb)?, number(width)?);
+    if !is_bit_field(lsb_bits, width_bits) {
+        return Err(format!("bit field of {width} bits at bit {lsb} does not fit in a word"));
+    }
+    Ok((lsb_bits as u8, width_bits as u8))
+}
+
+/// Parses the address operand of a load or store: `reg`, `[reg]`, or
+/// `[reg+offset]` and `[reg-offset]` with no spaces. The offset is an integer
+/// as for [`parse_address`].
+fn parse_memory_operand(s: &str) -> Result<(u8, u32), String> {
+    let Some(inner) = s.strip_prefix('[') else {
+        return Ok((parse_register(s)?, 0));
+    };
+    let inner = inner.strip_suffix(']')
+        .ok_or_else(|| format!("invalid address operand: `{s}`"))?;
+    let Some(at) = inner.find(['+', '-']) else {
+        return Ok((parse_register(inner)?, 0));
+    };
+    let base = parse_register(&inner[..at])?;
+    let offset = parse_address(&inner[at + 1..])
+        .map_err(|_| format!("invalid offset: `{}`", &inner[at + 1..]))?;
+    Ok((base, if inner[at..].starts_with('-') { offset.wrapping_neg() } else { offset }))
+}
+
+/// Parses an address or size: decimal, or `0x`/`0b`/`0o` prefixed.
+pub fn parse_address(s: &str) -> Result<u32, String> {
+    match s.parse::<u32>() {
+        Ok(addr) => Ok(addr),
+        Err(_) if s.starts_with("0x") || s.starts_with("0b") || s.starts_with("0o") => parse_immediate(s),
+        Err(_) => Err(format!("invalid address: `{s}`")),
+    }
+}
+
+/// Parses an immediate with an optional `+` or `-` sign. Negative integers
+/// are two's complement; negative floats (`-1.5`, `-inf`) are their `f32` bits.
+pub fn parse_signed_immediate(s: &str) -> Result<u32, String> {
+    if let Some(rest) = s.strip_prefix('+') {
+        parse_immediate(rest)
+    } else if let Some(rest) = s.strip_prefix('-') {
+        match s.parse::<f32>() {
+            Ok(f) if rest.parse::<u32>().is_err() => Ok(f.to_bits()),
+            _ => parse_immediate(rest).map(u32::wrapping_neg),
+        }
+    } else {
+        parse_immediate(s)
+    }
+}
+
 pub fn parse_immediate(s: &str) -> Result<u32, String> {
     let lowercase = s.to_lowercase();
-    if let Ok(f) = s.parse::<f32>() {
+    // Plain decimal integers are integers; anything else `f32` accepts
+    // (`1.5`, `1e3`, `inf`, `nan`) is a float.
+    if let Ok(n) = s.parse::<u32>() {
+        Ok(n)
+    } else if let Ok(f) = s.parse::<f32>() {
         Ok(f.to_bits())
     } else if s.starts_with("0x") {
         u32::from_str_radix(&lowercase[2..], 16)
@@ -1571,7 +3147,7 @@ pub fn parse_immediate(s: &str) -> Result<u32, String> {
     } else if s.starts_with("0o") {
         u32::from_str_radix(&lowercase[2..], 8)
             .map_err(|_| format!("invalid immediate: `{s}`"))
-    } else if s.starts_with("'") && s.starts_with("'") {
+    } else if s.len() >= 2 && s.starts_with('\'') && s.ends_with('\'') {
         let c = s[1..s.len()-1].chars().collect::<Vec<_>>();
         if c.is_empty() {
             return Err(format!("invalid immediate: `{s}`"));
@@ -1591,7 +3167,7 @@ pub fn parse_immediate(s: &str) -> Result<u32, String> {
             _ => Err(format!("invalid immediate: `{s}`"))
         }
     } else {
-        u32::from_str_radix(&lowercase, 10)
+        lowercase.parse::<u32>()
             .map_err(|_| format!("invalid immediate: `{s}`"))
     }
 }
\ No newline at end of file
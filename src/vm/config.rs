@@ -0,0 +1,270 @@
+use std::path::Path;
+use super::{Engine, Helios32, CODE_BASE};
+
+/// Size of the full 32-bit address space.
+pub const ADDRESS_SPACE: u64 = 1 << 32;
+
+/// A named address range reserved for a memory-mapped device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceWindow {
+    pub name: String,
+    pub base: u32,
+    pub size: u32,
+}
+
+impl DeviceWindow {
+    fn contains(&self, addr: u32) -> bool {
+        addr.wrapping_sub(self.base) < self.size
+    }
+}
+
+/// Memory map and power-on register state of a board.
+///
+/// RAM spans `0..ram_size`. Device windows and the boot ROM window are mapped
+/// on top of it or beyond it. Stores to unmapped addresses are dropped, so
+/// unmapped memory always reads as zero.
+///
+/// The text form is a TOML-like list of `key = value` lines, with one
+/// `[[device]]` table per device window:
+///
+/// ```text
+/// ram_size = 0x1_0000_0000
+/// code_base = 0xC0000000
+/// rsp = 0xAFFFFFFF
+/// csp = 0xBFFFFFFF
+/// rsp_limit = 0xA0000000
+/// csp_limit = 0xB0000000
+/// reset_vector = 0xC0000000
+/// rom_base = 0xFFFF0000
+/// rom_size = 0x10000
+///
+/// [[device]]
+/// name = "uart"
+/// base = 0xF0000000
+/// size = 0x1000
+/// ```
+///
+/// Numbers are decimal or `0x`/`0b`/`0o` prefixed and may contain `_`.
+/// Every key is optional; `#` outside quotes starts a comment. A stack limit of `none`
+/// turns off bounds checking for that stack.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MachineConfig {
+    pub ram_size: u64,
+    /// Default load address for programs.
+    pub code_base: u32,
+    /// Initial data stack pointer, the first byte `PB`/`PW` write.
+    pub rsp: u32,
+    /// Initial call stack pointer.
+    pub csp: u32,
+    /// Lowest address the data stack may grow to. Pushing past it or popping
+    /// above `rsp` raises a fault.
+    pub rsp_limit: Option<u32>,
+    /// Lowest address the call stack may grow to. Calling past it or
+    /// returning above `csp` raises a fault.
+    pub csp_limit: Option<u32>,
+    pub devices: Vec<DeviceWindow>,
+    /// Initial PC. When unset, execution starts in the boot ROM if one is
+    /// loaded, otherwise at the loaded program's origin.
+    pub reset_vector: Option<u32>,
+    /// Window a boot ROM is loaded into. It becomes read-only once loaded.
+    pub rom_base: u32,
+    pub rom_size: u32,
+}
+
+impl Default for MachineConfig {
+    fn default() -> Self {
+        Self {
+            ram_size: ADDRESS_SPACE,
+            code_base: CODE_BASE,
+            rsp: 0xAFFF_FFFF,
+            csp: 0xBFFF_FFFF,
+            rsp_limit: Some(0xA000_0000),
+            csp_limit: Some(0xB000_0000),
+            devices: Vec::new(),
+            reset_vector: None,
+            rom_base: 0xFFFF_0000,
+            rom_size: 0x1_0000,
+        }
+    }
+}
+
+impl MachineConfig {
+    pub fn is_mapped(&self, addr: u32) -> bool {
+        (addr as u64) < self.ram_size
+            || self.devices.iter().any(|dev| dev.contains(addr))
+            || self.in_rom(addr)
+    }
+
+    /// Top and optional limit of the data and call stacks.
+    pub fn stacks(&self) -> [(u32, Option<u32>); 2] {
+        [(self.rsp, self.rsp_limit), (self.csp, self.csp_limit)]
+    }
+
+    pub fn in_rom(&self, addr: u32) -> bool {
+        addr.wrapping_sub(self.rom_base) < self.rom_size
+    }
+
+    /// Whether `len` bytes from `start` lie entirely in RAM, one device window
+    /// or the ROM window.
+    pub fn is_mapped_range(&self, start: u32, len: u32) -> bool {
+        let end = start as u64 + len as u64;
+        let within = |base: u32, size: u32| start >= base && end <= base as u64 + size as u64;
+        end <= self.ram_size
+            || self.devices.iter().any(|dev| within(dev.base, dev.size))
+            || within(self.rom_base, self.rom_size)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ram_size > ADDRESS_SPACE {
+            return Err(format!("ram_size 0x{:X} exceeds the 4GiB address space", self.ram_size));
+        }
+        if self.rom_base as u64 + self.rom_size as u64 > ADDRESS_SPACE {
+            return Err("the ROM window does not fit in the address space".to_string());
+        }
+        for (i, dev) in self.devices.iter().enumerate() {
+            if dev.size == 0 || dev.base as u64 + dev.size as u64 > ADDRESS_SPACE {
+                return Err(format!("device `{}` does not fit in the address space", dev.name));
+            }
+            if let Some(other) = self.devices[..i].iter()
+                .find(|other| overlaps((dev.base, dev.size), (other.base, other.size)))
+            {
+                return Err(format!("device `{}` overlaps device `{}`", dev.name, other.name));
+            }
+            if overlaps((dev.base, dev.size), (self.rom_base, self.rom_size)) {
+                return Err(format!("device `{}` overlaps the ROM window", dev.name));
+            }
+        }
+
+        for (name, top, limit) in [("rsp", self.rsp, self.rsp_limit), ("csp", self.csp, self.csp_limit)] {
+            if limit.is_some_and(|limit| limit > top) {
+                return Err(format!("{name}_limit is above {name}"));
+            }
+        }
+
+        let required = [
+            ("code_base", Some(self.code_base)),
+            ("rsp", Some(self.rsp)),
+            ("csp", Some(self.csp)),
+            ("reset_vector", self.reset_vector),
+        ];
+        for (name, addr) in required {
+            if let Some(addr) = addr.filter(|addr| !self.is_mapped(*addr)) {
+                return Err(format!("{name} 0x{addr:08X} is not mapped"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut in_device = false;
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() { continue }
+
+            let err = |msg: &str| format!("error on line {} of machine config: {msg}", idx + 1);
+            if line == "[[device]]" {
+                config.devices.push(DeviceWindow { name: String::new(), base: 0, size: 0 });
+                in_device = true;
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(err("expected `key = value`"));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let number = || parse_number(value).ok_or_else(|| err(&format!("invalid number `{value}`")));
+            let address = || number().and_then(|n| u32::try_from(n).map_err(|_| err(&format!("`{value}` is not a 32-bit address"))));
+
+            match (in_device, key) {
+                (false, "ram_size") => config.ram_size = number()?,
+                (false, "code_base") => config.code_base = address()?,
+                (false, "rsp") => config.rsp = address()?,
+                (false, "csp") => config.csp = address()?,
+                (false, "rsp_limit") => config.rsp_limit = if value == "none" { None } else { Some(address()?) },
+                (false, "csp_limit") => config.csp_limit = if value == "none" { None } else { Some(address()?) },
+                (false, "reset_vector") => config.reset_vector = Some(address()?),
+                (false, "rom_base") => config.rom_base = address()?,
+                (false, "rom_size") => config.rom_size = address()?,
+                (true, "name") => {
+                    let name = value.strip_prefix('"')
+                        .and_then(|value| value.strip_suffix('"'))
+                        .ok_or_else(|| err("expected a quoted name"))?;
+                    config.devices.last_mut().unwrap().name = name.to_string();
+                },
+                (true, "base") => config.devices.last_mut().unwrap().base = address()?,
+                (true, "size") => config.devices.last_mut().unwrap().size = address()?,
+                _ => return Err(err(&format!("unknown key `{key}`"))),
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())?;
+
+        Self::parse(&text)
+    }
+}
+
+/// Whether two `(base, size)` windows share an address. Windows may end at
+/// the top of the address space.
+fn overlaps(a: (u32, u32), b: (u32, u32)) -> bool {
+    (a.0 as u64) < b.0 as u64 + b.1 as u64 && (b.0 as u64) < a.0 as u64 + a.1 as u64
+}
+
+/// `line` up to the first `#` outside a quoted string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn parse_number(s: &str) -> Option<u64> {
+    let s = s.replace('_', "");
+    let (digits, radix) = match s.get(..2) {
+        Some("0x") => (&s[2..], 16),
+        Some("0b") => (&s[2..], 2),
+        Some("0o") => (&s[2..], 8),
+        _ => (&s[..], 10),
+    };
+    u64::from_str_radix(digits, radix).ok()
+}
+
+/// Builds a [`Helios32`] for a [`MachineConfig`].
+#[derive(Default)]
+pub struct MachineBuilder {
+    config: MachineConfig,
+    engine: Engine,
+}
+
+impl MachineBuilder {
+    pub fn config(mut self, config: MachineConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Validates the configuration and creates the machine in its power-on state.
+    pub fn build(self) -> Result<Helios32, String> {
+        self.config.validate()?;
+
+        let mut vm = Helios32::new();
+        vm.engine = self.engine;
+        vm.config = self.config;
+        vm.reset();
+        Ok(vm)
+    }
+}
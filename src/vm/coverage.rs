@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use super::isa;
+use super::assembler::Assembly;
+use super::registers::*;
+
+/// Executed addresses and conditional branch outcomes of a run.
+///
+/// Branch outcomes are recorded for `JRI`, `JII`, `CRI` and `CII`.
+#[derive(Default)]
+pub struct Coverage {
+    hits: HashMap<u32, u64>,
+    /// `[taken, not taken]` counts per branch address.
+    branches: HashMap<u32, [u64; 2]>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one executed instruction, see [`Helios32::run_observed`](super::Helios32::run_observed).
+    pub fn observe(&mut self, pc: u32, inst: u64, before: &[u32; 16]) {
+        let src = match (inst & 0xFF) as u8 {
+            isa::JRI | isa::CRI => Some((inst >> 12) & 0xF),
+            isa::JII | isa::CII => Some((inst >> 40) & 0xF),
+            _ => None,
+        };
+        // RDS is cleared at the start of every instruction, before the condition is read.
+        let taken = src.map(|src| src != RDS as u64 && before[src as usize] != 0);
+
+        self.record(pc, taken);
+    }
+
+    fn record(&mut self, pc: u32, taken: Option<bool>) {
+        *self.hits.entry(pc).or_default() += 1;
+        if let Some(taken) = taken {
+            self.branches.entry(pc).or_default()[!taken as usize] += 1;
+        }
+    }
+
+    /// Per-line execution counts and branch outcomes.
+    fn line_stats(&self, assembly: &Assembly) -> BTreeMap<usize, (u64, Option<[u64; 2]>)> {
+        let mut lines = BTreeMap::<usize, (u64, Option<[u64; 2]>)>::new();
+        for info in &assembly.lines {
+            let addr = assembly.origin.wrapping_add(info.offset);
+            let stats = lines.entry(info.line).or_default();
+            stats.0 += self.hits.get(&addr).copied().unwrap_or(0);
+
+            let opcode = assembly.bytes[info.offset as usize];
+            if matches!(opcode, isa::JRI | isa::JII | isa::CRI | isa::CII) {
+                let outcome = self.branches.get(&addr).copied().unwrap_or([0; 2]);
+                let branch = stats.1.get_or_insert([0; 2]);
+                branch[0] += outcome[0];
+                branch[1] += outcome[1];
+            }
+        }
+        lines
+    }
+
+    /// LCOV tracefile for the program assembled from `source_path`.
+    ///
+    /// Labels are reported as functions (`FN`/`FNDA`), each conditional jump or
+    /// call as a two-way branch (`BRDA`).
+    pub fn lcov(&self, source_path: &str, assembly: &Assembly) -> String {
+        let mut out = String::new();
+        writeln!(out, "TN:").unwrap();
+        writeln!(out, "SF:{source_path}").unwrap();
+
+        let mut functions_hit = 0;
+        let mut functions = Vec::new();
+        for (name, offset) in &assembly.labels {
+            let Some(line) = assembly.lines.iter()
+                .find(|info| info.offset == *offset)
+                .map(|info| info.line)
+            else {
+                continue;
+            };
+            let count = self.hits.get(&assembly.origin.wrapping_add(*offset)).copied().unwrap_or(0);
+            writeln!(out, "FN:{line},{name}").unwrap();
+            functions.push((name, count));
+        }
+        for (name, count) in &functions {
+            writeln!(out, "FNDA:{count},{name}").unwrap();
+            functions_hit += (*count != 0) as usize;
+        }
+        writeln!(out, "FNF:{}", functions.len()).unwrap();
+        writeln!(out, "FNH:{functions_hit}").unwrap();
+
+        let lines = self.line_stats(assembly);
+        let (mut branches_found, mut branches_hit) = (0, 0);
+        for (line, (count, branch)) in &lines {
+            let Some([taken, not_taken]) = branch else { continue };
+            for (i, outcome) in [taken, not_taken].into_iter().enumerate() {
+                if *count == 0 {
+                    writeln!(out, "BRDA:{line},0,{i},-").unwrap();
+                } else {
+                    writeln!(out, "BRDA:{line},0,{i},{outcome}").unwrap();
+                }
+                branches_found += 1;
+                branches_hit += (*outcome != 0) as usize;
+            }
+        }
+        writeln!(out, "BRF:{branches_found}").unwrap();
+        writeln!(out, "BRH:{branches_hit}").unwrap();
+
+        for (line, (count, _)) in &lines {
+            writeln!(out, "DA:{line},{count}").unwrap();
+        }
+        writeln!(out, "LF:{}", lines.len()).unwrap();
+        writeln!(out, "LH:{}", lines.values().filter(|(count, _)| *count != 0).count()).unwrap();
+        writeln!(out, "end_of_record").unwrap();
+
+        out
+    }
+
+    /// The source text with execution counts in the margin.
+    ///
+    /// Lines without code show `-`, code that never ran shows `#####`, and
+    /// conditional branches list their taken/not-taken counts.
+    pub fn annotate(&self, source: &str, assembly: &Assembly) -> String {
+        let lines = self.line_stats(assembly);
+        let mut out = String::new();
+
+        for (idx, text) in source.lines().enumerate() {
+            let (count, branch) = match lines.get(&(idx + 1)) {
+                Some((0, branch)) => ("#####".to_string(), branch),
+                Some((count, branch)) => (count.to_string(), branch),
+                None => ("-".to_string(), &None),
+            };
+            let branch = branch.map_or_else(String::new, |[taken, not_taken]| {
+                format!("T:{taken} N:{not_taken}")
+            });
+            writeln!(out, "{count:>9} {branch:<16}{:>5}: {text}", idx + 1).unwrap();
+        }
+
+        out
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::{BTreeSet, VecDeque};
+use super::{Fault, Helios32, Interrupts};
+use super::registers::*;
+
+/// Why the debugger handed control back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// A single step (forward or reverse) completed.
+    Step,
+    /// Execution reached an address with a breakpoint.
+    Breakpoint(u32),
+    /// The instruction at `pc` wrote the watched byte at `addr`.
+    Watchpoint { addr: u32, pc: u32 },
+    /// The machine executed `HLT`.
+    Halted,
+    /// An instruction faulted and stopped the machine.
+    Fault(Fault),
+    /// Reverse execution ran out of recorded history.
+    HistoryStart,
+}
+
+/// The most recent recorded store to an address, see [`Debugger::last_writer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LastWrite {
+    /// Number of instructions executed before the writing instruction.
+    pub step: u64,
+    pub pc: u32,
+    /// The byte's value before the write.
+    pub old: u8,
+}
+
+/// Register state before an instruction, plus how many entries it pushed to
+/// [`History::writes`].
+struct UndoEntry {
+    registers: [u32; 16],
+    fcsr: u32,
+    reservation: Option<u32>,
+    interrupts: Interrupts,
+    is_running: bool,
+    fault: Option<Fault>,
+    write_count: usize,
+}
+
+/// Undo log of recent instructions plus periodic full snapshots.
+///
+/// Reverse steps within the ring buffer are undone directly. Older positions are
+/// reached by restoring the nearest earlier checkpoint and re-executing forward.
+struct History {
+    entries: VecDeque<UndoEntry>,
+    writes: VecDeque<(u32, u8)>,
+    /// The machine's write log, kept between steps to reuse its allocation.
+    write_log: Vec<(u32, u8)>,
+    capacity: usize,
+    checkpoints: VecDeque<(u64, Vec<u8>)>,
+    checkpoint_interval: u64,
+    max_checkpoints: usize,
+}
+
+pub struct Debugger {
+    pub vm: Helios32,
+    breakpoints: BTreeSet<u32>,
+    watchpoints: Vec<(u32, u32)>,
+    history: History,
+    steps: u64,
+}
+
+impl Debugger {
+    pub const DEFAULT_HISTORY: usize = 100_000;
+    pub const DEFAULT_CHECKPOINT_INTERVAL: u64 = 100_000;
+    pub const DEFAULT_MAX_CHECKPOINTS: usize = 16;
+
+    pub fn new(vm: Helios32) -> Self {
+        let mut debugger = Self {
+            vm,
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+            history: History {
+                entries: VecDeque::new(),
+                writes: VecDeque::new(),
+                write_log: Vec::new(),
+                capacity: Self::DEFAULT_HISTORY,
+                checkpoints: VecDeque::new(),
+                checkpoint_interval: Self::DEFAULT_CHECKPOINT_INTERVAL,
+                max_checkpoints: Self::DEFAULT_MAX_CHECKPOINTS,
+            },
+            steps: 0,
+        };
+        debugger.vm.core.is_running = true;
+        debugger.checkpoint();
+        debugger
+    }
+
+    /// Number of instructions between the start of recording and the current state.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u32) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u32) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    /// Watches stores to the `len` bytes starting at `addr`.
+    pub fn add_watchpoint(&mut self, addr: u32, len: u32) {
+        self.watchpoints.push((addr, len.max(1)));
+    }
+
+    pub fn remove_watchpoint(&mut self, addr: u32) -> bool {
+        let before = self.watchpoints.len();
+        self.watchpoints.retain(|&(start, _)| start != addr);
+        self.watchpoints.len() != before
+    }
+
+    /// Applies an out-of-band change (e.g. a register edit from a front-end).
+    ///
+    /// Recorded history no longer leads to the modified state, so it is discarded
+    /// and recording restarts from here.
+    pub fn modify<R>(&mut self, f: impl FnOnce(&mut Helios32) -> R) -> R {
+        let result = f(&mut self.vm);
+        self.history.entries.clear();
+        self.history.writes.clear();
+        self.history.checkpoints.clear();
+        self.checkpoint();
+        result
+    }
+
+    /// Executes one instruction.
+    pub fn step(&mut self) -> StopReason {
+        self.vm.core.is_running = true;
+        self.record_step();
+
+        if let Some(hit) = self.watch_hit(self.history.entries.back().unwrap()) {
+            return hit;
+        }
+        if let Some(fault) = self.vm.core.fault {
+            return StopReason::Fault(fault);
+        }
+        if !self.vm.core.is_running {
+            return StopReason::Halted;
+        }
+        StopReason::Step
+    }
+
+    /// Executes until a breakpoint, watchpoint, fault or `HLT`.
+    pub fn cont(&mut self) -> StopReason {
+        loop {
+            if let Some(reason) = self.cont_for(u64::MAX) {
+                return reason;
+            }
+        }
+    }
+
+    /// Like [`Debugger::cont`], but gives up after `budget` instructions and
+    /// returns `None` so callers can poll for interruption.
+    pub fn cont_for(&mut self, budget: u64) -> Option<StopReason> {
+        for _ in 0..budget {
+            match self.step() {
+                StopReason::Step => (),
+                other => return Some(other),
+            }
+
+            let pc = self.vm.core.registers[RPC as usize];
+            if self.breakpoints.contains(&pc) {
+                return Some(StopReason::Breakpoint(pc));
+            }
+        }
+        None
+    }
+
+    /// Undoes the most recent instruction.
+    pub fn reverse_step(&mut self) -> StopReason {
+        match self.undo() {
+            Some(_) => StopReason::Step,
+            None => StopReason::HistoryStart,
+        }
+    }
+
+    /// Runs backwards until a breakpoint, a store to a watched byte, or the start
+    /// of recorded history.
+    pub fn reverse_continue(&mut self) -> StopReason {
+        loop {
+            let Some(hit) = self.undo() else {
+                return StopReason::HistoryStart;
+            };
+            if let Some(hit) = hit {
+                return hit;
+            }
+
+            let pc = self.vm.core.registers[RPC as usize];
+            if self.breakpoints.contains(&pc) {
+                return StopReason::Breakpoint(pc);
+            }
+        }
+    }
+
+    /// Finds the most recent recorded instruction that stored to `addr`.
+    ///
+    /// Only the undo ring buffer is searched, so writes older than
+    /// [`Debugger::DEFAULT_HISTORY`] instructions are not found.
+    pub fn last_writer(&self, addr: u32) -> Option<LastWrite> {
+        let mut end = self.history.writes.len();
+        for (i, entry) in self.history.entries.iter().enumerate().rev() {
+            let start = end - entry.write_count;
+            for &(written, old) in self.history.writes.range(start..end).rev() {
+                if written == addr {
+                    let age = (self.history.entries.len() - i) as u64;
+                    return Some(LastWrite {
+                        step: self.steps - age,
+                        pc: entry.registers[RPC as usize],
+                        old,
+                    });
+                }
+            }
+            end = start;
+        }
+        None
+    }
+
+    fn record_step(&mut self) {
+        let registers = self.vm.core.registers;
+        let fcsr = self.vm.core.fcsr;
+        let reservation = self.vm.core.reservation;
+        let interrupts = self.vm.core.interrupts;
+        let is_running = self.vm.core.is_running;
+        let fault = self.vm.core.fault;
+
+        let mut write_log = std::mem::take(&mut self.history.write_log);
+        write_log.clear();
+        self.vm.write_log = Some(write_log);
+        self.vm.cycle();
+        let writes = self.vm.write_log.take().unwrap_or_default();
+
+        // Forward execution from a rewound position replaces the recorded future.
+        let steps = self.steps;
+        self.history.checkpoints.retain(|&(at, _)| at <= steps);
+
+        self.history.entries.push_back(UndoEntry {
+            registers,
+            fcsr,
+            reservation,
+            interrupts,
+            is_running,
+            fault,
+            write_count: writes.len(),
+        });
+        self.history.writes.extend(&writes);
+        self.history.write_log = writes;
+        if self.history.entries.len() > self.history.capacity {
+            let oldest = self.history.entries.pop_front().unwrap();
+            self.history.writes.drain(..oldest.write_count);
+        }
+
+        self.steps += 1;
+        if self.steps.is_multiple_of(self.history.checkpoint_interval) {
+            self.checkpoint();
+        }
+    }
+
+    /// Reverts one instruction. Returns `None` without history, otherwise the
+    /// watchpoint the undone instruction triggered, if any.
+    fn undo(&mut self) -> Option<Option<StopReason>> {
+        if self.history.entries.is_empty() && !self.replay_to(self.steps.checked_sub(1)?) {
+            return None;
+        }
+
+        let hit = self.watch_hit(self.history.entries.back()?);
+        let entry = self.history.entries.pop_back()?;
+        for _ in 0..entry.write_count {
+            let (addr, old) = self.history.writes.pop_back().unwrap();
+            self.vm.write_u8(addr, old);
+        }
+        self.vm.core.registers = entry.registers;
+        self.vm.core.fcsr = entry.fcsr;
+        self.vm.core.reservation = entry.reservation;
+        self.vm.core.interrupts = entry.interrupts;
+        self.vm.core.is_running = entry.is_running;
+        self.vm.core.fault = entry.fault;
+        self.steps -= 1;
+
+        Some(hit)
+    }
+
+    /// Restores the latest checkpoint before `target` and re-executes forward
+    /// to `target + 1`, refilling the undo log along the way.
+    fn replay_to(&mut self, target: u64) -> bool {
+        let Some((at, snapshot)) = self.history.checkpoints.iter()
+            .rev()
+            .find(|(at, _)| *at <= target)
+        else {
+            return false;
+        };
+
+        let at = *at;
+        if self.vm.load_snapshot(&snapshot.clone()).is_err() {
+            return false;
+        }
+        self.steps = at;
+        while self.steps <= target {
+            self.record_step();
+        }
+        true
+    }
+
+    fn checkpoint(&mut self) {
+        let steps = self.steps;
+        if self.history.checkpoints.back().is_some_and(|(at, _)| *at == steps) {
+            return;
+        }
+        self.history.checkpoints.push_back((steps, self.vm.save_snapshot()));
+        if self.history.checkpoints.len() > self.history.max_checkpoints {
+            self.history.checkpoints.pop_front();
+        }
+    }
+
+    /// Checks the stores of `entry`, which must be the newest undo entry.
+    fn watch_hit(&self, entry: &UndoEntry) -> Option<StopReason> {
+        if self.watchpoints.is_empty() {
+            return None;
+        }
+
+        let start = self.history.writes.len() - entry.write_count;
+        self.history.writes.range(start..)
+            .find(|(addr, _)| self.is_watched(*addr))
+            .map(|&(addr, _)| StopReason::Watchpoint {
+                addr,
+                pc: entry.registers[RPC as usize],
+            })
+    }
+
+    fn is_watched(&self, addr: u32) -> bool {
+        self.watchpoints.iter()
+            .any(|&(start, len)| addr.wrapping_sub(start) < len)
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt::Write;
+use std::path::Path;
+use super::assembler::Assembly;
+use super::symbols::SymbolMap;
+
+const HEADER: &str = "helios32-debug-info 1";
+
+/// A source position for the instruction at `addr`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineEntry {
+    pub addr: u32,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A label and the number of bytes up to the next label or the end of the program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub addr: u32,
+    pub size: u32,
+}
+
+/// Side table mapping absolute addresses of an assembled program back to source.
+///
+/// The on-disk form is line-oriented text:
+///
+/// ```text
+/// helios32-debug-info 1
+/// file <source path>
+/// sym <address> <size> <name>
+/// line <address> <line> <column>
+/// ```
+///
+/// Addresses are absolute `0x`-prefixed hexadecimal, sizes are bytes, lines and
+/// columns are 1-based decimal. `sym` and `line` records are sorted by address.
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DebugInfo {
+    pub file: String,
+    pub symbols: Vec<Symbol>,
+    pub lines: Vec<LineEntry>,
+}
+
+impl DebugInfo {
+    /// Builds the table for `assembly` assembled from `file`.
+    pub fn from_assembly(file: &str, assembly: &Assembly) -> Self {
+        let symbols = assembly.sized_labels()
+            .map(|(name, offset, size)| Symbol {
+                name: name.to_string(),
+                addr: assembly.origin.wrapping_add(offset),
+                size,
+            })
+            .collect();
+        let lines = assembly.lines.iter()
+            .map(|info| LineEntry {
+                addr: assembly.origin.wrapping_add(info.offset),
+                line: info.line,
+                column: info.column,
+            })
+            .collect();
+
+        Self { file: file.to_string(), symbols, lines }
+    }
+
+    /// Symbols only, for programs where just a [`SymbolMap`] is available.
+    pub fn from_symbol_map(map: &SymbolMap) -> Self {
+        let symbols = map.entries.iter()
+            .map(|entry| Symbol {
+                name: entry.name.clone(),
+                addr: entry.addr,
+                size: entry.size,
+            })
+            .collect();
+
+        Self { file: String::new(), symbols, lines: Vec::new() }
+    }
+
+    /// The source position of the instruction at `addr`.
+    pub fn location(&self, addr: u32) -> Option<&LineEntry> {
+        let i = self.lines.binary_search_by_key(&addr, |entry| entry.addr).ok()?;
+        Some(&self.lines[i])
+    }
+
+    /// The symbol covering `addr` and the offset into it.
+    pub fn symbolize(&self, addr: u32) -> Option<(&Symbol, u32)> {
+        let i = self.symbols.partition_point(|sym| sym.addr <= addr).checked_sub(1)?;
+        let sym = &self.symbols[i];
+        (addr - sym.addr < sym.size.max(1)).then_some((sym, addr - sym.addr))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "{HEADER}").unwrap();
+        writeln!(out, "file {}", self.file).unwrap();
+        for sym in &self.symbols {
+            writeln!(out, "sym 0x{:08X} {} {}", sym.addr, sym.size, sym.name).unwrap();
+        }
+        for entry in &self.lines {
+            writeln!(out, "line 0x{:08X} {} {}", entry.addr, entry.line, entry.column).unwrap();
+        }
+        out
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+
+        match lines.next() {
+            Some((_, header)) if header.trim() == HEADER => (),
+            _ => return Err(format!("debug info must start with `{HEADER}`")),
+        }
+
+        let mut info = DebugInfo::default();
+        for (idx, line) in lines {
+            let err = |msg: &str| format!("error on line {} of debug info: {msg}", idx + 1);
+            let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match kind {
+                "file" => info.file = rest.to_string(),
+                "sym" => {
+                    let mut fields = rest.splitn(3, ' ');
+                    let addr = fields.next().and_then(parse_addr).ok_or_else(|| err("invalid address"))?;
+                    let size = fields.next().and_then(|s| s.parse().ok()).ok_or_else(|| err("invalid size"))?;
+                    let name = fields.next().filter(|s| !s.is_empty()).ok_or_else(|| err("missing name"))?;
+                    info.symbols.push(Symbol { name: name.to_string(), addr, size });
+                },
+                "line" => {
+                    let fields = rest.split_whitespace().collect::<Vec<_>>();
+                    let [addr, line, column] = fields[..] else {
+                        return Err(err("expected `line <address> <line> <column>`"));
+                    };
+                    info.lines.push(LineEntry {
+                        addr: parse_addr(addr).ok_or_else(|| err("invalid address"))?,
+                        line: line.parse().map_err(|_| err("invalid line number"))?,
+                        column: column.parse().map_err(|_| err("invalid column"))?,
+                    });
+                },
+                other => return Err(err(&format!("unknown record `{other}`"))),
+            }
+        }
+
+        info.symbols.sort_by_key(|sym| sym.addr);
+        info.lines.sort_by_key(|entry| entry.addr);
+        Ok(info)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        std::fs::write(path, self.to_text())
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())?;
+
+        Self::parse(&text)
+    }
+}
+
+fn parse_addr(s: &str) -> Option<u32> {
+    u32::from_str_radix(s.strip_prefix("0x")?, 16).ok()
+}
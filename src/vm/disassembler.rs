@@ -0,0 +1,140 @@
+use std::fmt::Write;
+use super::assembler;
+use super::isa::*;
+use super::float;
+use super::registers::{is_pair, REGISTER_NAMES};
+
+/// Decodes one instruction into assembler syntax.
+///
+/// Instructions the assembler produces disassemble to text that assembles
+/// back to the same bytes. Unassigned opcodes, and instructions naming an
+/// invalid register pair or bit field, execute as `nop` and are shown as one
+/// followed by their raw bytes in a comment; other encodings with stray bits
+/// are shown with the fields the machine actually reads. Use [`is_canonical`]
+/// to tell the cases apart.
+pub fn instruction(inst: u64) -> String {
+    operands(inst).unwrap_or_else(|| format!("nop ; encoded as {}", hex(&inst.to_le_bytes()[..6])))
+}
+
+/// The text of `inst`, or `None` if it executes as a `nop` without being one.
+fn operands(inst: u64) -> Option<String> {
+    let opcode = (inst & 0xFF) as u8;
+    let reg = |shift: u32| REGISTER_NAMES[((inst >> shift) & 0xF) as usize];
+    let imm = |shift: u32| ((inst >> shift) & 0xFFFF_FFFF) as u32;
+    // Loads and stores add the offset in bits 16..47 to their base register.
+    let memory = |shift: u32| match imm(16) {
+        0 => reg(shift).to_string(),
+        offset => format!("[{}{}]", reg(shift), displacement(offset)),
+    };
+    let rel = |shift: u32| if (inst >> shift) & 0x1 != 0 { "rel " } else { "" };
+    let name = mnemonic(opcode)?;
+    // Like unassigned opcodes, instructions naming an invalid pair or bit field are NOPs.
+    if !pair_fields(opcode).iter().all(|&shift| is_pair(((inst >> shift) & 0xF) as u8)) {
+        return None;
+    }
+    if matches!(opcode, BFX | BFI) && !is_bit_field(((inst >> 16) & 0xFF) as u32, ((inst >> 24) & 0xFF) as u32) {
+        return None;
+    }
+
+    Some(match opcode {
+        NOP | HLT | RET | FENCE | IRET | WFI => name.to_string(),
+        LDI => format!("{name} {} 0x{:X}", reg(8), imm(12)),
+        BNOT | LNOT | FSQRT | FABS | FNEG | DSQRT | DABS | DNEG | FTOD | ITOD | UTOD | LR
+            | CLZ | CTZ | POPC | BSWAP | SEXTB | SEXTH | ZEXTB | ZEXTH => format!("{name} {} {}", reg(8), reg(12)),
+        ITOF | UTOF | FTOI | FTOU | DTOF | DTOI | DTOU => {
+            let mode = float::rounding_name(((inst >> 16) & 0x7) as u8);
+            format!("{name} {} {} {mode}", reg(8), reg(12))
+        },
+        BFX | BFI => format!("{name} {} {} {} {}", reg(8), reg(12), (inst >> 16) & 0xFF, (inst >> 24) & 0xFF),
+        FMA | CAS | ADC | SBB | ADDO | SUBO | SEL => format!("{name} {} {} {} {}", reg(8), reg(12), reg(16), reg(20)),
+        SB | SW | SH => format!("{name} {} {}", memory(8), reg(12)),
+        LBS | LBU | LW | LHS | LHU => format!("{name} {} {}", reg(8), memory(12)),
+        INC | DEC | PB | PW | POBS | POBU | POW | FRCSR | FWCSR | CID | NCORES | IPI | SIV | IACK => format!("{name} {}", reg(8)),
+        ADDI | SUBI => format!("{name} {} {} 0x{:X}", reg(8), reg(12), imm(16)),
+        JMR | CAR => format!("{name} {}{}", rel(12), reg(8)),
+        JRI | CRI => format!("{name} {}{} {}", rel(16), reg(8), reg(12)),
+        JMI | CAI if rel(40).is_empty() => format!("{name} 0x{:X}", imm(8)),
+        JMI | CAI => format!("{name} rel {}", displacement(imm(8))),
+        JII | CII if rel(44).is_empty() => format!("{name} 0x{:X} {}", imm(8), reg(40)),
+        JII | CII => format!("{name} rel {} {}", displacement(imm(8)), reg(40)),
+        _ if has_immediate_form(opcode) && inst & IMMEDIATE_FLAG != 0 => {
+            let imm = ((((inst >> 16) as u32) << 1) as i32 >> 1) as u32;
+            format!("{name} {} {} 0x{imm:X}", reg(8), reg(12))
+        },
+        _ => format!("{name} {} {} {}", reg(8), reg(12), reg(16)),
+    })
+}
+
+/// Whether `bytes` is exactly what the assembler emits for its disassembly.
+pub fn is_canonical(bytes: &[u8; 6]) -> bool {
+    let text = instruction(decode(bytes));
+    assembler::assemble(&text, 0).is_ok_and(|assembly| assembly.bytes == bytes)
+}
+
+/// Disassembles a program loaded at `origin` into source that assembles back
+/// to the same bytes, one instruction per line with its address in a comment.
+///
+/// Zero padding that leaves the next instruction, or the end of the program,
+/// off the 6-byte grid, as a `.org` can, is turned back into a `.org`. Other instructions that would
+/// not round-trip are flagged with their raw bytes, as are trailing bytes
+/// that do not form a whole instruction.
+pub fn disassemble(bytes: &[u8], origin: u32) -> String {
+    let mut out = String::new();
+    writeln!(out, ".org 0x{origin:08X}").unwrap();
+
+    let chunk_at = |pos: usize| bytes.get(pos..pos + 6).map(|chunk| <&[u8; 6]>::try_from(chunk).unwrap());
+    let mut pos = 0;
+    while let Some(chunk) = chunk_at(pos) {
+        let addr = origin.wrapping_add(pos as u32);
+        let canonical = is_canonical(chunk);
+        if !canonical {
+            let zeros = bytes[pos..].iter().take_while(|&&byte| byte == 0).count();
+            let resume = (pos + 1..=pos + zeros.min(5))
+                .find(|&next| chunk_at(next).is_some_and(is_canonical));
+            if let Some(next) = resume {
+                writeln!(out, ".org 0x{:08X}", origin.wrapping_add(next as u32)).unwrap();
+                pos = next;
+                continue;
+            }
+        }
+
+        let text = operands(decode(chunk)).unwrap_or_else(|| "nop".to_string());
+        write!(out, "    {text:<32}; 0x{addr:08X}").unwrap();
+        if !canonical {
+            write!(out, " (encoded as {})", hex(chunk)).unwrap();
+        }
+        out.push('\n');
+        pos += 6;
+    }
+    if bytes[pos..].iter().all(|&byte| byte == 0) {
+        if pos < bytes.len() {
+            writeln!(out, ".org 0x{:08X}", origin.wrapping_add(bytes.len() as u32)).unwrap();
+        }
+    } else {
+        writeln!(out, "; trailing bytes: {}", hex(&bytes[pos..])).unwrap();
+    }
+    out
+}
+
+fn decode(bytes: &[u8; 6]) -> u64 {
+    let mut word = [0; 8];
+    word[..6].copy_from_slice(bytes);
+    u64::from_le_bytes(word)
+}
+
+/// Relative displacements are shown signed, as they are usually written.
+fn displacement(offset: u32) -> String {
+    let offset = offset as i32;
+    if offset.is_negative() {
+        format!("-0x{:X}", offset.unsigned_abs())
+    } else {
+        format!("+0x{offset:X}")
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,208 @@
+use std::fmt::Write;
+use std::path::Path;
+use super::{Engine, Helios32};
+use super::assembler;
+use super::config::MachineConfig;
+use super::registers::{REGISTER_NAMES, RPC};
+
+/// Cycle budget for tests without a `.expect halted within` line.
+pub const DEFAULT_CYCLE_LIMIT: u64 = 1_000_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expectation {
+    Register { reg: u8, value: u32 },
+    Memory { start: u32, bytes: Vec<u8> },
+}
+
+/// A guest program with `.expect` directives describing its final state.
+///
+/// ```text
+/// .expect gr0 = 42
+/// .expect gr1 = -1.5
+/// .expect mem[0x1000..0x1002] = "HI"
+/// .expect mem[0x2000..0x2004] = 0xDEADBEEF
+/// .expect halted within 1000 cycles
+/// ```
+///
+/// Register values are any `ldi` immediate. Memory ranges are half-open and
+/// match either a string literal of exactly that length or an integer stored
+/// little-endian in at most 4 bytes; `mem[addr]` is a single byte. Without a
+/// `halted within` line the program must halt within [`DEFAULT_CYCLE_LIMIT`]
+/// cycles. A fault always fails the test.
+#[derive(Clone, Debug)]
+pub struct GuestTest {
+    /// The program with every `.expect` line blanked, so line numbers still match.
+    pub source: String,
+    /// Expectations with the 1-based line they were declared on.
+    pub expectations: Vec<(usize, Expectation)>,
+    pub cycle_limit: u64,
+}
+
+impl GuestTest {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut source = String::with_capacity(text.len());
+        let mut expectations = Vec::new();
+        let mut cycle_limit = None;
+
+        for (idx, line) in text.lines().enumerate() {
+            let Some(directive) = line.trim_start().strip_prefix(".expect ") else {
+                source.push_str(line);
+                source.push('\n');
+                continue;
+            };
+            source.push('\n');
+
+            let err = |msg: &str| format!("error on line {}: {msg}", idx + 1);
+            let directive = strip_comment(directive).trim();
+            if let Some(rest) = directive.strip_prefix("halted within ") {
+                let cycles = rest.strip_suffix(" cycles")
+                    .and_then(|n| n.trim().replace('_', "").parse::<u64>().ok())
+                    .ok_or_else(|| err("expected `.expect halted within <n> cycles`"))?;
+                cycle_limit = Some(cycles);
+                continue;
+            }
+
+            let Some((target, value)) = directive.split_once('=') else {
+                return Err(err("expected `.expect <target> = <value>`"));
+            };
+            let (target, value) = (target.trim(), value.trim());
+            let expectation = if let Some(range) = target.strip_prefix("mem[").and_then(|t| t.strip_suffix(']')) {
+                let address = |s: &str| assembler::parse_address(s.trim()).map_err(|e| err(&e));
+                let (start, len) = match range.split_once("..") {
+                    Some((start, end)) => {
+                        let (start, end) = (address(start)?, address(end)?);
+                        if end <= start {
+                            return Err(err(&format!("empty memory range `{range}`")));
+                        }
+                        (start, (end - start) as usize)
+                    },
+                    None => (address(range)?, 1),
+                };
+                let bytes = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                    Some(string) => parse_string(string).ok_or_else(|| err(&format!("invalid string `{value}`")))?,
+                    None if len <= 4 => assembler::parse_signed_immediate(value).map_err(|e| err(&e))?.to_le_bytes()[..len].to_vec(),
+                    None => return Err(err("integers match at most 4 bytes; use a string for longer ranges")),
+                };
+                if bytes.len() != len {
+                    return Err(err(&format!("`{value}` is {} bytes but the range is {len}", bytes.len())));
+                }
+                Expectation::Memory { start, bytes }
+            } else {
+                let reg = assembler::parse_register(target).map_err(|e| err(&e))?;
+                let value = assembler::parse_signed_immediate(value).map_err(|e| err(&e))?;
+                Expectation::Register { reg, value }
+            };
+            expectations.push((idx + 1, expectation));
+        }
+
+        Ok(Self {
+            source,
+            expectations,
+            cycle_limit: cycle_limit.unwrap_or(DEFAULT_CYCLE_LIMIT),
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())?;
+
+        Self::parse(&text)
+    }
+
+    /// Assembles and runs the program on a fresh machine, returning one
+    /// message per unmet expectation.
+    pub fn run(&self, config: &MachineConfig, engine: Engine) -> Result<Vec<String>, String> {
+        let mut vm = Helios32::builder()
+            .config(config.clone())
+            .engine(engine)
+            .build()?;
+        let assembly = assembler::assemble(&self.source, vm.config.code_base)?;
+        vm.load_program(assembly.origin, &assembly.bytes)?;
+        if vm.config.reset_vector.is_none() {
+            vm.core.registers[RPC as usize] = assembly.origin;
+        }
+
+        let executed = vm.run_for(self.cycle_limit);
+        let mut failures = Vec::new();
+        if let Some(fault) = vm.core.fault {
+            failures.push(format!("fault: {fault}"));
+        } else if vm.core.is_running {
+            failures.push(format!("did not halt within {executed} cycles"));
+        }
+
+        for (line, expectation) in &self.expectations {
+            match expectation {
+                Expectation::Register { reg, value } => {
+                    let actual = vm.core.registers[*reg as usize];
+                    if actual != *value {
+                        failures.push(format!(
+                            "line {line}: {} = 0x{actual:08X} ({actual}), expected 0x{value:08X} ({value})",
+                            REGISTER_NAMES[*reg as usize]
+                        ));
+                    }
+                },
+                Expectation::Memory { start, bytes } => {
+                    let start_idx = *start as usize;
+                    let actual = &vm.mem[start_idx..start_idx + bytes.len()];
+                    if actual != &bytes[..] {
+                        failures.push(format!(
+                            "line {line}: mem[0x{start:08X}..0x{:08X}] = {}, expected {}",
+                            *start as u64 + bytes.len() as u64, hex(actual), hex(bytes)
+                        ));
+                    }
+                },
+            }
+        }
+        Ok(failures)
+    }
+}
+
+/// `directive` up to the first `;` outside a string or character literal.
+fn strip_comment(directive: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in directive.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, ';') => return &directive[..i],
+            _ => (),
+        }
+    }
+    directive
+}
+
+fn parse_string(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '\\' => match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                '0' => '\0',
+                '\\' => '\\',
+                '"' => '"',
+                _ => return None,
+            },
+            c => c,
+        };
+        let mut buf = [0; 4];
+        bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+    }
+    Some(bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        write!(out, "{byte:02X}").unwrap();
+    }
+    out
+}
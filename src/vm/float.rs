@@ -0,0 +1,502 @@
+use std::cmp::Ordering;
+
+/// Exception flags, in the bit positions they take in both the sticky flags
+/// and the trap-enable fields of FCSR.
+pub const INEXACT: u32 = 1 << 0;
+pub const UNDERFLOW: u32 = 1 << 1;
+pub const OVERFLOW: u32 = 1 << 2;
+pub const DIVIDE_BY_ZERO: u32 = 1 << 3;
+pub const INVALID: u32 = 1 << 4;
+pub const FLAGS: u32 = 0x1F;
+
+/// FCSR layout: `[5:sticky flags][3:rounding mode][5:trap enables]`, from
+/// bit 0 up. The remaining bits read as zero.
+pub const FCSR_ROUNDING_SHIFT: u32 = 5;
+pub const FCSR_TRAP_SHIFT: u32 = 8;
+pub const FCSR_MASK: u32 = 0x1FFF;
+
+/// The conversion instructions' rounding mode field value that defers to FCSR.
+pub const DYNAMIC_ROUNDING: u8 = 7;
+
+/// 2^128, the smallest magnitude that overflows an `f32` after rounding.
+const OVERFLOW_THRESHOLD: f64 = pow2(128);
+
+/// IEEE-754 rounding direction, as encoded in the conversion instructions and
+/// in FCSR.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round to nearest, ties to even.
+    #[default]
+    NearestEven = 0,
+    TowardZero = 1,
+    /// Toward negative infinity.
+    Down = 2,
+    /// Toward positive infinity.
+    Up = 3,
+    /// Round to nearest, ties away from zero.
+    NearestAway = 4,
+}
+
+impl RoundingMode {
+    const ALL: [Self; 5] = [Self::NearestEven, Self::TowardZero, Self::Down, Self::Up, Self::NearestAway];
+
+    /// Decodes a 3-bit mode field. Unassigned values round to nearest even.
+    pub fn from_bits(bits: u32) -> Self {
+        Self::ALL.get(bits as usize).copied().unwrap_or_default()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::NearestEven => "rne",
+            Self::TowardZero => "rtz",
+            Self::Down => "rdn",
+            Self::Up => "rup",
+            Self::NearestAway => "rmm",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name().eq_ignore_ascii_case(s))
+    }
+
+    /// Rounds `value` to an integral value.
+    pub fn round(self, value: f64) -> f64 {
+        match self {
+            Self::NearestEven => value.round_ties_even(),
+            Self::TowardZero => value.trunc(),
+            Self::Down => value.floor(),
+            Self::Up => value.ceil(),
+            Self::NearestAway => value.round(),
+        }
+    }
+
+}
+
+/// Parses a conversion instruction's rounding operand: a mode name, or `dyn`
+/// for the mode in FCSR.
+pub fn parse_rounding(s: &str) -> Option<u8> {
+    if s.eq_ignore_ascii_case("dyn") {
+        return Some(DYNAMIC_ROUNDING);
+    }
+    RoundingMode::parse(s).map(|mode| mode as u8)
+}
+
+/// The assembler name of a conversion instruction's rounding field.
+pub fn rounding_name(bits: u8) -> &'static str {
+    match bits {
+        DYNAMIC_ROUNDING => "dyn",
+        bits => RoundingMode::from_bits(bits as u32).name(),
+    }
+}
+
+/// IEEE-754 `minimumNumber`: a NaN operand is ignored unless both are NaN,
+/// and -0 is below +0.
+pub fn min(a: f32, b: f32) -> f32 {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => f32::NAN,
+        (true, false) => b,
+        (false, true) => a,
+        _ if a == b => if a.is_sign_negative() { a } else { b },
+        _ => a.min(b),
+    }
+}
+
+/// IEEE-754 `maximumNumber`, the counterpart of [`min`].
+pub fn max(a: f32, b: f32) -> f32 {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => f32::NAN,
+        (true, false) => b,
+        (false, true) => a,
+        _ if a == b => if a.is_sign_positive() { a } else { b },
+        _ => a.max(b),
+    }
+}
+
+/// Names the exceptions set in `flags`, most severe first.
+pub fn flag_names(flags: u32) -> String {
+    const NAMES: [(u32, &str); 5] = [
+        (INVALID, "invalid"),
+        (DIVIDE_BY_ZERO, "divide-by-zero"),
+        (OVERFLOW, "overflow"),
+        (UNDERFLOW, "underflow"),
+        (INEXACT, "inexact"),
+    ];
+    NAMES.iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn add(a: f32, b: f32, mode: RoundingMode) -> (f32, u32) {
+    if !a.is_finite() || !b.is_finite() {
+        return special(a + b, &[a, b]);
+    }
+    sum(a as f64, b as f64, mode)
+}
+
+pub fn sub(a: f32, b: f32, mode: RoundingMode) -> (f32, u32) {
+    if !a.is_finite() || !b.is_finite() {
+        return special(a - b, &[a, b]);
+    }
+    sum(a as f64, -(b as f64), mode)
+}
+
+pub fn mul(a: f32, b: f32, mode: RoundingMode) -> (f32, u32) {
+    if !a.is_finite() || !b.is_finite() {
+        return special(a * b, &[a, b]);
+    }
+    // The product of two 24-bit significands is exact in an f64.
+    round(a as f64 * b as f64, Ordering::Equal, mode)
+}
+
+pub fn div(a: f32, b: f32, mode: RoundingMode) -> (f32, u32) {
+    if !a.is_finite() || !b.is_finite() || b == 0.0 {
+        let (value, mut flags) = special(a / b, &[a, b]);
+        if b == 0.0 && a.is_finite() && a != 0.0 {
+            flags |= DIVIDE_BY_ZERO;
+        }
+        return (value, flags);
+    }
+    let (x, y) = (a as f64, b as f64);
+    let quotient = x / y;
+    // The remainder of a correctly rounded quotient is exact.
+    let remainder = (-quotient).mul_add(y, x);
+    let residual = if y > 0.0 { sign(remainder) } else { sign(remainder).reverse() };
+    round(quotient, residual, mode)
+}
+
+/// The remainder of truncating division, which is always exact.
+pub fn rem(a: f32, b: f32) -> (f32, u32) {
+    special(a % b, &[a, b])
+}
+
+pub fn sqrt(a: f32, mode: RoundingMode) -> (f32, u32) {
+    if !a.is_finite() || a < 0.0 {
+        return special(a.sqrt(), &[a]);
+    }
+    let x = a as f64;
+    let root = x.sqrt();
+    round(root, sign((-root).mul_add(root, x)), mode)
+}
+
+/// `a * b + c` with a single rounding.
+pub fn fma(a: f32, b: f32, c: f32, mode: RoundingMode) -> (f32, u32) {
+    if !a.is_finite() || !b.is_finite() || !c.is_finite() {
+        return special(a.mul_add(b, c), &[a, b, c]);
+    }
+    sum(a as f64 * b as f64, c as f64, mode)
+}
+
+/// Rounds an integer, exact in an `f64`, to an `f32`.
+pub fn from_int(value: f64, mode: RoundingMode) -> (f32, u32) {
+    round(value, Ordering::Equal, mode)
+}
+
+/// Converts to `i32`. Out-of-range values saturate and NaN gives 0, both
+/// raising the invalid exception.
+pub fn to_i32(value: f64, mode: RoundingMode) -> (u32, u32) {
+    to_int(value, mode, i32::MIN as f64, i32::MAX as f64, |rounded| rounded as i32 as u32)
+}
+
+/// Converts to `u32`, with the same out-of-range behavior as [`to_i32`].
+pub fn to_u32(value: f64, mode: RoundingMode) -> (u32, u32) {
+    to_int(value, mode, 0.0, u32::MAX as f64, |rounded| rounded as u32)
+}
+
+fn to_int(value: f64, mode: RoundingMode, min: f64, max: f64, convert: fn(f64) -> u32) -> (u32, u32) {
+    if value.is_nan() {
+        return (0, INVALID);
+    }
+    let rounded = mode.round(value);
+    if rounded < min || rounded > max {
+        (convert(rounded), INVALID)
+    } else {
+        (convert(rounded), if rounded != value { INEXACT } else { 0 })
+    }
+}
+
+/// The exceptions raised by comparing `a` with `b`. Equality and min/max
+/// only complain about signaling NaNs; ordered comparisons about any NaN.
+pub fn compare_flags(a: f32, b: f32, ordered: bool) -> u32 {
+    let invalid = is_signaling(a) || is_signaling(b) || (ordered && (a.is_nan() || b.is_nan()));
+    if invalid { INVALID } else { 0 }
+}
+
+/// [`compare_flags`] for doubles.
+pub fn compare_flags_f64(a: f64, b: f64, ordered: bool) -> u32 {
+    let invalid = is_signaling_f64(a) || is_signaling_f64(b) || (ordered && (a.is_nan() || b.is_nan()));
+    if invalid { INVALID } else { 0 }
+}
+
+fn is_signaling(value: f32) -> bool {
+    value.is_nan() && value.to_bits() & 0x0040_0000 == 0
+}
+
+fn is_signaling_f64(value: f64) -> bool {
+    value.is_nan() && value.to_bits() & 0x0008_0000_0000_0000 == 0
+}
+
+/// Flags for an operation with a non-finite operand, or one whose host result
+/// is already correctly rounded.
+fn special(result: f32, operands: &[f32]) -> (f32, u32) {
+    let invalid = operands.iter().any(|&x| is_signaling(x))
+        || (result.is_nan() && !operands.iter().any(|x| x.is_nan()));
+    (result, if invalid { INVALID } else { 0 })
+}
+
+/// Adds two values holding `f32`-derived numbers, tracking the error of the
+/// f64 addition so the result is rounded only once.
+fn sum(x: f64, y: f64, mode: RoundingMode) -> (f32, u32) {
+    let total = x + y;
+    if total == 0.0 {
+        // An exact zero sum is -0 if both addends are -0, or when rounding down.
+        let negative = if x == 0.0 && y == 0.0 && x.is_sign_negative() == y.is_sign_negative() {
+            x.is_sign_negative()
+        } else {
+            mode == RoundingMode::Down
+        };
+        return (if negative { -0.0 } else { 0.0 }, 0);
+    }
+    let rounded_y = total - x;
+    let error = (x - (total - rounded_y)) + (y - rounded_y);
+    round(total, sign(error), mode)
+}
+
+fn sign(value: f64) -> Ordering {
+    value.partial_cmp(&0.0).unwrap_or(Ordering::Equal)
+}
+
+const fn pow2(exponent: i32) -> f64 {
+    f64::from_bits(((exponent + 1023) as u64) << 52)
+}
+
+/// Rounds a true result to an `f32`. The result is given as `approx`, the
+/// nearest f64 to it, and `residual`, the sign of `true result - approx`.
+fn round(approx: f64, residual: Ordering, mode: RoundingMode) -> (f32, u32) {
+    if approx == 0.0 {
+        return (approx as f32, 0);
+    }
+    let magnitude = approx.abs();
+    let exponent = ((magnitude.to_bits() >> 52) as i32 - 1023).max(-126);
+    let mut ulp = pow2(exponent - 23);
+    let on_grid = (approx / ulp).fract() == 0.0;
+    let toward_zero = (residual == Ordering::Less) == (approx > 0.0);
+
+    let (value, inexact) = if on_grid && residual == Ordering::Equal {
+        (approx, false)
+    } else {
+        // Find the neighbouring f32 values `lo < true result < hi`.
+        if on_grid && toward_zero && magnitude == pow2(exponent) && exponent > -126 {
+            ulp /= 2.0;
+        }
+        let lo = match (on_grid, residual) {
+            (true, Ordering::Greater) => approx,
+            (true, _) => approx - ulp,
+            (false, _) => (approx / ulp).floor() * ulp,
+        };
+        let hi = lo + ulp;
+        let mid = lo + ulp / 2.0;
+        let nearest = match approx.partial_cmp(&mid).unwrap().then(residual) {
+            Ordering::Less => lo,
+            Ordering::Greater => hi,
+            Ordering::Equal => match mode {
+                RoundingMode::NearestAway if approx > 0.0 => hi,
+                RoundingMode::NearestAway => lo,
+                _ if (lo / ulp) % 2.0 == 0.0 => lo,
+                _ => hi,
+            },
+        };
+        let value = match mode {
+            RoundingMode::NearestEven | RoundingMode::NearestAway => nearest,
+            RoundingMode::TowardZero if approx > 0.0 => lo,
+            RoundingMode::TowardZero => hi,
+            RoundingMode::Down => lo,
+            RoundingMode::Up => hi,
+        };
+        (if value == 0.0 { 0.0f64.copysign(approx) } else { value }, true)
+    };
+
+    let mut flags = if inexact { INEXACT } else { 0 };
+    let tiny = magnitude < f32::MIN_POSITIVE as f64
+        || (magnitude == f32::MIN_POSITIVE as f64 && toward_zero && residual != Ordering::Equal);
+    if inexact && tiny {
+        flags |= UNDERFLOW;
+    }
+    if value.abs() >= OVERFLOW_THRESHOLD {
+        let positive = approx > 0.0;
+        let value = match mode {
+            RoundingMode::NearestEven | RoundingMode::NearestAway => f32::INFINITY,
+            RoundingMode::TowardZero => f32::MAX,
+            RoundingMode::Down => if positive { f32::MAX } else { f32::INFINITY },
+            RoundingMode::Up => if positive { f32::INFINITY } else { f32::MAX },
+        };
+        return (if positive { value } else { -value }, OVERFLOW | INEXACT);
+    }
+    (value as f32, flags)
+}
+
+pub fn add_f64(a: f64, b: f64, mode: RoundingMode) -> (f64, u32) {
+    if !a.is_finite() || !b.is_finite() {
+        return special_f64(a + b, &[a, b]);
+    }
+    if a == 0.0 || b == 0.0 || a == -b {
+        // Exact: one operand, or a zero whose sign follows the same rule as `sum`.
+        return (match (a == 0.0, b == 0.0) {
+            (true, true) if a.is_sign_negative() == b.is_sign_negative() => a,
+            (false, true) => a,
+            (true, false) => b,
+            _ => if mode == RoundingMode::Down { -0.0 } else { 0.0 },
+        }, 0);
+    }
+
+    let (a, b) = if a.abs() >= b.abs() { (a, b) } else { (b, a) };
+    let ((negative, ma, ea), (_, mb, eb)) = (decompose(a), decompose(b));
+    // Line both significands up 64 bits above the larger one's exponent, so
+    // the smaller one only loses bits when it is far below the larger.
+    let big = (ma as u128) << 64;
+    let shift = (ea - eb) as u32;
+    let small = if shift < 128 { ((mb as u128) << 64) >> shift } else { 0 };
+    let sticky = shift >= 128 || small << shift != (mb as u128) << 64;
+    let mantissa = match (a.is_sign_negative() == b.is_sign_negative(), sticky) {
+        (true, _) => big + small,
+        // `big - (small + fraction)` is `big - small - 1` plus a fraction.
+        (false, true) => big - small - 1,
+        (false, false) => big - small,
+    };
+    round_f64(negative, mantissa, ea - 64, sticky, mode)
+}
+
+pub fn sub_f64(a: f64, b: f64, mode: RoundingMode) -> (f64, u32) {
+    if !a.is_finite() || !b.is_finite() {
+        return special_f64(a - b, &[a, b]);
+    }
+    add_f64(a, -b, mode)
+}
+
+pub fn mul_f64(a: f64, b: f64, mode: RoundingMode) -> (f64, u32) {
+    if !a.is_finite() || !b.is_finite() || a == 0.0 || b == 0.0 {
+        return special_f64(a * b, &[a, b]);
+    }
+    let ((na, ma, ea), (nb, mb, eb)) = (decompose(a), decompose(b));
+    round_f64(na != nb, ma as u128 * mb as u128, ea + eb, false, mode)
+}
+
+pub fn div_f64(a: f64, b: f64, mode: RoundingMode) -> (f64, u32) {
+    if !a.is_finite() || !b.is_finite() || a == 0.0 || b == 0.0 {
+        let (value, mut flags) = special_f64(a / b, &[a, b]);
+        if b == 0.0 && a.is_finite() && a != 0.0 {
+            flags |= DIVIDE_BY_ZERO;
+        }
+        return (value, flags);
+    }
+    let ((na, ma, ea), (nb, mb, eb)) = (normalize(a), normalize(b));
+    // Both significands have 53 bits, so the quotient keeps at least 64.
+    let dividend = (ma as u128) << 64;
+    let (quotient, remainder) = (dividend / mb as u128, dividend % mb as u128);
+    round_f64(na != nb, quotient, ea - eb - 64, remainder != 0, mode)
+}
+
+pub fn sqrt_f64(a: f64, mode: RoundingMode) -> (f64, u32) {
+    if !a.is_finite() || a <= 0.0 {
+        return special_f64(a.sqrt(), &[a]);
+    }
+    let (_, mut ma, mut ea) = normalize(a);
+    if ea % 2 != 0 {
+        ma <<= 1;
+        ea -= 1;
+    }
+    let radicand = (ma as u128) << 64;
+    let root = radicand.isqrt();
+    round_f64(false, root, (ea - 64) / 2, root * root != radicand, mode)
+}
+
+/// Widens an `f32`, which is always exact.
+pub fn to_f64(value: f32) -> (f64, u32) {
+    (value as f64, if is_signaling(value) { INVALID } else { 0 })
+}
+
+/// Narrows an `f64` to an `f32`.
+pub fn from_f64(value: f64, mode: RoundingMode) -> (f32, u32) {
+    if !value.is_finite() {
+        return (value as f32, if is_signaling_f64(value) { INVALID } else { 0 });
+    }
+    round(value, Ordering::Equal, mode)
+}
+
+/// Splits a finite value into sign, significand and exponent, so that it is
+/// exactly `significand * 2^exponent`.
+fn decompose(value: f64) -> (bool, u64, i32) {
+    let bits = value.to_bits();
+    let biased = ((bits >> 52) & 0x7FF) as i32;
+    let fraction = bits & ((1 << 52) - 1);
+    match biased {
+        0 => (value.is_sign_negative(), fraction, -1074),
+        _ => (value.is_sign_negative(), fraction | (1 << 52), biased - 1075),
+    }
+}
+
+/// Like [`decompose`], with subnormals shifted up to a 53-bit significand.
+fn normalize(value: f64) -> (bool, u64, i32) {
+    let (negative, significand, exponent) = decompose(value);
+    let shift = significand.leading_zeros() - 11;
+    (negative, significand << shift, exponent - shift as i32)
+}
+
+fn special_f64(result: f64, operands: &[f64]) -> (f64, u32) {
+    let invalid = operands.iter().any(|&x| is_signaling_f64(x))
+        || (result.is_nan() && !operands.iter().any(|x| x.is_nan()));
+    (result, if invalid { INVALID } else { 0 })
+}
+
+/// Rounds the nonzero value `mantissa * 2^exponent`, plus a fraction of
+/// `2^exponent` when `sticky` is set, to an `f64`.
+fn round_f64(negative: bool, mantissa: u128, exponent: i32, sticky: bool, mode: RoundingMode) -> (f64, u32) {
+    // Normalize to 120 bits so there are always guard bits below the result.
+    let shift = mantissa.leading_zeros().saturating_sub(8);
+    let (mantissa, exponent) = (mantissa << shift, exponent - shift as i32);
+    let top = exponent + 127 - mantissa.leading_zeros() as i32;
+    let mut ulp = top.max(-1022) - 52;
+
+    let drop = (ulp - exponent) as u32;
+    let (mut kept, position) = if drop >= 128 {
+        (0, Ordering::Less)
+    } else {
+        let rest = mantissa & ((1 << drop) - 1);
+        let half = 1u128 << (drop - 1);
+        (mantissa >> drop, rest.cmp(&half).then(if sticky { Ordering::Greater } else { Ordering::Equal }))
+    };
+    let inexact = sticky || drop >= 128 || mantissa & ((1 << drop) - 1) != 0;
+
+    let up = match mode {
+        RoundingMode::NearestEven => position == Ordering::Greater || (position == Ordering::Equal && kept % 2 == 1),
+        RoundingMode::NearestAway => position != Ordering::Less,
+        RoundingMode::TowardZero => false,
+        RoundingMode::Down => negative && inexact,
+        RoundingMode::Up => !negative && inexact,
+    };
+    kept += up as u128;
+    if kept == 1 << 53 {
+        kept >>= 1;
+        ulp += 1;
+    }
+
+    let mut flags = if inexact { INEXACT } else { 0 };
+    if inexact && top < -1022 {
+        flags |= UNDERFLOW;
+    }
+    let value = if ulp > 1023 - 52 {
+        flags |= OVERFLOW | INEXACT;
+        match mode {
+            RoundingMode::NearestEven | RoundingMode::NearestAway => f64::INFINITY,
+            RoundingMode::TowardZero => f64::MAX,
+            RoundingMode::Down => if negative { f64::INFINITY } else { f64::MAX },
+            RoundingMode::Up => if negative { f64::MAX } else { f64::INFINITY },
+        }
+    } else if kept < 1 << 52 {
+        f64::from_bits(kept as u64)
+    } else {
+        f64::from_bits((((ulp + 1075) as u64) << 52) | (kept as u64 & ((1 << 52) - 1)))
+    };
+    (if negative { -value } else { value }, flags)
+}
@@ -0,0 +1,250 @@
+use std::panic::{self, AssertUnwindSafe};
+use super::{Engine, Helios32, CODE_BASE};
+use super::assembler;
+use super::disassembler;
+use super::registers::RPC;
+
+/// Instructions each engine runs per [`execute_target`] input.
+pub const EXECUTE_CYCLES: u64 = 10_000;
+
+/// Fuzz target for the assembler.
+///
+/// Any text must either assemble or fail with an error, and whatever
+/// assembles must disassemble to source that assembles to the same bytes.
+pub fn assemble_target(data: &[u8]) {
+    let source = String::from_utf8_lossy(data);
+    let Ok(assembly) = assembler::assemble(&source, CODE_BASE) else {
+        return;
+    };
+
+    let text = disassembler::disassemble(&assembly.bytes, assembly.origin);
+    let again = match assembler::assemble(&text, CODE_BASE) {
+        Ok(again) => again,
+        Err(err) => panic!("disassembly does not assemble: {err}\n{text}"),
+    };
+    assert_eq!(again.origin, assembly.origin, "round trip moved the program:\n{text}");
+    assert!(again.bytes == assembly.bytes, "round trip changed the program:\n{text}");
+}
+
+/// Fuzz target for the executor.
+///
+/// Arbitrary bytes are loaded at the code base and run for
+/// [`EXECUTE_CYCLES`] on both engines, which must end in the same state.
+/// Their disassembly must also be valid assembly.
+pub fn execute_target(data: &[u8]) {
+    let text = disassembler::disassemble(data, CODE_BASE);
+    if let Err(err) = assembler::assemble(&text, CODE_BASE) {
+        panic!("disassembly does not assemble: {err}\n{text}");
+    }
+
+    let [interpreter, threaded] = [Engine::Interpreter, Engine::Threaded].map(|engine| {
+        let mut vm = Helios32::builder().engine(engine).build().unwrap();
+        if vm.load_program(CODE_BASE, data).is_ok() {
+            vm.core.registers[RPC as usize] = CODE_BASE;
+            vm.run_for(EXECUTE_CYCLES);
+        }
+        vm
+    });
+    assert_eq!(interpreter.core.registers, threaded.core.registers, "engines disagree on registers");
+    assert_eq!(interpreter.core.fault, threaded.core.fault, "engines disagree on faults");
+    assert_eq!(interpreter.core.is_running, threaded.core.is_running, "engines disagree on halting");
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Assemble,
+    Execute,
+}
+
+impl Target {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "assemble" => Ok(Self::Assemble),
+            "execute" => Ok(Self::Execute),
+            _ => Err(format!("unknown fuzz target `{name}`, expected `assemble` or `execute`")),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Assemble => "assemble",
+            Self::Execute => "execute",
+        }
+    }
+
+    pub fn run(self, data: &[u8]) {
+        match self {
+            Self::Assemble => assemble_target(data),
+            Self::Execute => execute_target(data),
+        }
+    }
+
+    /// Generates an input likely to reach interesting code: assembly-shaped
+    /// text for the assembler, mostly-valid opcodes for the executor.
+    fn generate(self, rng: &mut Rng) -> Vec<u8> {
+        match self {
+            Self::Assemble => random_source(rng).into_bytes(),
+            Self::Execute => {
+                let len = rng.below(64) as usize * 6;
+                let mut bytes = (0..len).map(|_| rng.next() as u8).collect::<Vec<_>>();
+                for inst in bytes.chunks_mut(6) {
+                    if rng.below(8) != 0 {
+                        inst[0] = rng.below(0x80) as u8;
+                    }
+                }
+                bytes
+            },
+        }
+    }
+}
+
+/// Runs `target` on `runs` generated inputs without an external fuzzer,
+/// returning the first input that panics along with its run index.
+pub fn fuzz(target: Target, seed: u64, runs: u64) -> Option<(u64, Vec<u8>)> {
+    let mut rng = Rng::new(seed);
+    for run in 0..runs {
+        let input = target.generate(&mut rng);
+        if !survives(target, &input) {
+            return Some((run, input));
+        }
+    }
+    None
+}
+
+/// Runs `target` on one input, reporting whether it finished without panicking.
+pub fn survives(target: Target, input: &[u8]) -> bool {
+    panic::catch_unwind(AssertUnwindSafe(|| target.run(input))).is_ok()
+}
+
+/// xorshift64* generator, so runs are reproducible from a seed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+/// Mnemonics with their operand shapes: `R` register, `P` register pair, `I`
+/// immediate or label, `O` register or immediate, `A` load/store address, `D` double, `B` bit field
+/// position and width, `F` an optional relative flag, `M` an optional rounding mode.
+const SHAPES: &[(&str, &str)] = &[
+    ("nop", ""), ("hlt", ""), ("ret", ""), ("ldi", "R I"), ("LDI", "R I"),
+    ("add", "R R R"), ("sub", "R R R"), ("bor", "R R O"), ("band", "R R O"), ("bxor", "R R O"),
+    ("lor", "R R R"), ("land", "R R R"), ("lxor", "R R R"), ("eq", "R R O"), ("ne", "R R O"),
+    ("gt", "R R O"), ("lt", "R R O"), ("ge", "R R O"), ("le", "R R O"), ("shl", "R R O"),
+    ("lshr", "R R O"), ("ashr", "R R O"), ("rotl", "R R O"), ("rotr", "R R O"), ("mul", "R R O"),
+    ("div", "R R O"), ("rem", "R R O"), ("fadd", "R R R"), ("fsub", "R R R"), ("fmul", "R R R"),
+    ("fdiv", "R R R"), ("frem", "R R R"), ("muhs", "R R R"), ("muhu", "R R R"),
+    ("sgt", "R R O"), ("slt", "R R O"), ("sge", "R R O"), ("sle", "R R O"), ("sdiv", "R R O"),
+    ("srem", "R R O"), ("feq", "R R R"), ("flt", "R R R"), ("fle", "R R R"), ("fmin", "R R R"),
+    ("fmax", "R R R"), ("fsqrt", "R R"), ("fabs", "R R"), ("fneg", "R R"), ("fma", "R R R R"),
+    ("itof", "R R M"), ("utof", "R R M"), ("ftoi", "R R M"), ("ftou", "R R M"),
+    ("frcsr", "R"), ("fwcsr", "R"), ("ldd", "P D"), ("sh", "A R"), ("lhs", "R A"), ("lhu", "R A"), ("dadd", "P P P"), ("dsub", "P P P"),
+    ("dmul", "P P P"), ("ddiv", "P P P"), ("dsqrt", "P P"), ("dabs", "P P"), ("dneg", "P P"),
+    ("deq", "R P P"), ("dlt", "R P P"), ("dle", "R P P"), ("ftod", "P R"), ("dtof", "R P M"),
+    ("itod", "P R"), ("utod", "P R"), ("dtoi", "R P M"), ("dtou", "R P M"),
+    ("swap", "R R R"), ("cas", "R R R R"), ("xadd", "R R R"), ("lr", "R R"), ("sc", "R R R"), ("fence", ""),
+    ("cid", "R"), ("ncores", "R"), ("ipi", "R"), ("siv", "R"), ("iack", "R"), ("iret", ""), ("wfi", ""),
+    ("clz", "R R"), ("ctz", "R R"), ("popc", "R R"), ("bswap", "R R"), ("bfx", "R R B"), ("bfi", "R R B"),
+    ("sextb", "R R"), ("sexth", "R R"), ("zextb", "R R"), ("zexth", "R R"),
+    ("adc", "R R R R"), ("sbb", "R R R R"), ("addo", "R R R R"), ("subo", "R R R R"), ("sel", "R R R R"),
+    ("bnot", "R R"), ("lnot", "R R"), ("sb", "A R"), ("sw", "A R"), ("lbs", "R A"),
+    ("lbu", "R A"), ("lw", "R A"), ("inc", "R"), ("dec", "R"), ("pb", "R"), ("pw", "R"),
+    ("pobs", "R"), ("pobu", "R"), ("pow", "R"), ("addi", "R R I"), ("subi", "R R I"),
+    ("jmr", "F R"), ("car", "F R"), ("jri", "F R R"), ("cri", "F R R"), ("jmi", "F I"),
+    ("JMI", "F I"), ("cai", "F I"), ("jii", "F I R"), ("cii", "F I R"),
+];
+
+const REGISTERS: &[&str] = &["rds", "gr0", "gr1", "gr9", "gra", "grb", "gr11", "rsp", "csp", "rpc", "GR0"];
+
+const ADDRESSES: &[&str] = &["gr1", "[gr1]", "[gr0+8]", "[rsp-0x4]", "[gra+0xFFFFFFFF]", "[csp-2147483648]", "[gr2+]"];
+
+const BIT_FIELDS: &[&str] = &["0 1", "0 32", "31 1", "8 8", "0x10 16", "4 29", "0 0", "32 1"];
+
+const PAIRS: &[&str] = &["gr0", "gr2", "gr8", "gra", "GR4"];
+
+const DOUBLES: &[&str] = &["0", "-1", "2.5", "1e300", "-1e-310", "inf", "nan", "0x7FF4000000000000"];
+
+const IMMEDIATES: &[&str] = &[
+    "0", "1", "42", "-1", "+6", "-6", "0x0", "0xFFFFFFFF", "0x80000000", "-0x80000000",
+    "-2147483648", "0b101", "0o17", "1.5", "-1.5", "inf", "-inf", "nan", "'a'", "'\\n'",
+    "start", "loop", "end", "0xC0000000", "0xC0000006", "0xFFFFFFFA",
+];
+
+/// Tokens that are usually wrong, mixed in to reach error paths.
+const JUNK: &[&str] = &[
+    "gr12", "rel", "r", "4294967296", "0x", "0b", "-", "+", "1e40", "'", "''", "'\\q'", "'ab'",
+    "'\\'", "missing", "é", "\u{0}", ":", ".org", "bogus", "start:",
+];
+
+fn random_source(rng: &mut Rng) -> String {
+    // Most inputs are valid programs so the round trip gets exercised.
+    let junk_rate = if rng.below(2) == 0 { 0 } else { 1 + rng.below(4) };
+    let mut source = String::new();
+    for _ in 0..rng.below(24) {
+        let mut tokens = Vec::new();
+        if rng.below(4) == 0 {
+            tokens.push(rng.pick(&["start:", "loop:", "end:"]).to_string());
+        }
+        if rng.below(16) == 0 {
+            // Mostly short gaps; arbitrary addresses reach the `.org` range errors.
+            let addr = if rng.below(4) == 0 { rng.next() as u32 } else { 0xC000_0000 + rng.below(0x100) as u32 };
+            tokens.push(format!(".org 0x{addr:X}"));
+        } else {
+            let (mnemonic, shape) = SHAPES[rng.below(SHAPES.len() as u64) as usize];
+            tokens.push(mnemonic.to_string());
+            for kind in shape.split_whitespace() {
+                match kind {
+                    "R" => tokens.push(rng.pick(REGISTERS).to_string()),
+                    "P" => tokens.push(rng.pick(PAIRS).to_string()),
+                    "A" => tokens.push(rng.pick(ADDRESSES).to_string()),
+                    "O" => {
+                        let pool = if rng.below(2) == 0 { REGISTERS } else { IMMEDIATES };
+                        tokens.push(rng.pick(pool).to_string());
+                    },
+                    "D" => tokens.push(rng.pick(DOUBLES).to_string()),
+                    "B" => tokens.push(rng.pick(BIT_FIELDS).to_string()),
+                    "I" => tokens.push(rng.pick(IMMEDIATES).to_string()),
+                    "M" => if rng.below(2) == 0 {
+                        tokens.push(rng.pick(&["rne", "rtz", "rdn", "rup", "rmm", "dyn", "RNE"]).to_string());
+                    },
+                    _ => if rng.below(2) == 0 {
+                        tokens.push(rng.pick(&["rel", "relative", "R"]).to_string());
+                    },
+                }
+            }
+        }
+        for _ in 0..junk_rate {
+            if rng.below(8) == 0 {
+                let at = rng.below(tokens.len() as u64 + 1) as usize;
+                match rng.below(3) {
+                    0 if at < tokens.len() => { tokens.remove(at); },
+                    1 if at < tokens.len() => tokens[at] = rng.pick(JUNK).to_string(),
+                    _ => tokens.insert(at, rng.pick(JUNK).to_string()),
+                }
+            }
+        }
+        source.push_str(&tokens.join(" "));
+        if rng.below(8) == 0 {
+            source.push_str(" ; comment");
+        }
+        source.push_str(rng.pick(&["\n", "\n", "\n", "\r\n", "\n\n"]));
+    }
+    source
+}
@@ -14,13 +14,13 @@ pub const ADD: u8  = 0x03;
 /// `[8:opcode][4:dest][4:src1][4:src2]`
 pub const SUB: u8  = 0x04;
 /// Bitwise OR
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const BOR: u8  = 0x05;
 /// Bitwise AND
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const BAND: u8 = 0x06;
 /// Bitwise XOR
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const BXOR: u8 = 0x07;
 /// Bitwise NOT
 /// `[8:opcode][4:dest][4:src]`
@@ -37,20 +37,20 @@ pub const LXOR: u8 = 0x0B;
 /// Logical NOT
 /// `[8:opcode][4:dest][4:src]`
 pub const LNOT: u8 = 0x0C;
-/// Store byte
-/// `[8:opcode][4:dest][4:src]`
+/// Store byte at address `dest + offset`
+/// `[8:opcode][4:dest][4:src][32:offset]`
 pub const SB: u8   = 0x0D;
-/// Store word
-/// `[8:opcode][4:dest][4:src]`
+/// Store word at address `dest + offset`
+/// `[8:opcode][4:dest][4:src][32:offset]`
 pub const SW: u8   = 0x0E;
-/// Load byte signed
-/// `[8:opcode][4:dest][4:src]`
+/// Load byte signed from address `src + offset`
+/// `[8:opcode][4:dest][4:src][32:offset]`
 pub const LBS: u8  = 0x0F;
-/// Load byte unsigned
-/// `[8:opcode][4:dest][4:src]`
+/// Load byte unsigned from address `src + offset`
+/// `[8:opcode][4:dest][4:src][32:offset]`
 pub const LBU: u8  = 0x10;
-/// Load word
-/// `[8:opcode][4:dest][4:src]`
+/// Load word from address `src + offset`
+/// `[8:opcode][4:dest][4:src][32:offset]`
 pub const LW: u8   = 0x11;
 /// Jump register
 /// `[8:opcode][4:dest][1:rel/abs]`
@@ -80,22 +80,22 @@ pub const CII: u8  = 0x19;
 /// `[8:opcode]`
 pub const RET: u8  = 0x1A;
 /// Compare equal
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const EQ: u8   = 0x1B;
 /// Compare not-equal
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const NE: u8   = 0x1C;
-/// Compare greater-than
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// Compare greater-than (unsigned)
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const GT: u8   = 0x1D;
-/// Compare less-than
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// Compare less-than (unsigned)
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const LT: u8   = 0x1E;
-/// Compare greater-than-or-equal
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// Compare greater-than-or-equal (unsigned)
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const GE: u8   = 0x1F;
-/// Compare less-than-or-equal
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// Compare less-than-or-equal (unsigned)
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const LE: u8   = 0x20;
 /// Increment
 /// `[8:opcode][4:dest]`
@@ -109,20 +109,20 @@ pub const ADDI: u8 = 0x23;
 /// Subtract immediate
 /// `[8:opcode][4:dest][4:src][32:imm]`
 pub const SUBI: u8 = 0x24;
-/// Shift left
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// Shift left. Shift amounts are taken modulo 32, as for all shifts and rotates.
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const SHL: u8  = 0x25;
 /// Logical shift right
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const LSHR: u8 = 0x26;
-/// Arithmetic shift left
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// Arithmetic shift right
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const ASHR: u8 = 0x27;
 /// Rotate left
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const ROTL: u8 = 0x28;
 /// Rotate right
-/// `[8:opcode][4:dest][4:src1][4:src2]`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const ROTR: u8 = 0x29;
 /// Push byte
 /// `[8:opcode][4:src]`
@@ -140,32 +140,434 @@ pub const POBU: u8 = 0x2D;
 /// `[8:opcode][4:dest]`
 pub const POW: u8  = 0x2E;
 /// Multiply low
-/// `[8:opcode][4:dest]`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const MUL: u8  = 0x2F;
-/// Divide
-/// `[8:opcode][4:dest]`
+/// Divide (unsigned). Division by zero yields 0
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const DIV: u8  = 0x30;
-/// Remainder
-/// `[8:opcode][4:dest]`
+/// Remainder (unsigned). Division by zero yields 0
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
 pub const REM: u8  = 0x31;
-/// Float add
-/// `[8:opcode][4:dest]`
+/// Float add, rounded in the FCSR rounding mode
+/// `[8:opcode][4:dest][4:src1][4:src2]`
 pub const FADD: u8 = 0x32;
-/// Float subtract
-/// `[8:opcode][4:dest]`
+/// Float subtract, rounded in the FCSR rounding mode
+/// `[8:opcode][4:dest][4:src1][4:src2]`
 pub const FSUB: u8 = 0x33;
-/// Float multiply
-/// `[8:opcode][4:dest]`
+/// Float multiply, rounded in the FCSR rounding mode
+/// `[8:opcode][4:dest][4:src1][4:src2]`
 pub const FMUL: u8 = 0x34;
-/// Float divide
-/// `[8:opcode][4:dest]`
+/// Float divide, rounded in the FCSR rounding mode
+/// `[8:opcode][4:dest][4:src1][4:src2]`
 pub const FDIV: u8 = 0x35;
-/// Float remainder
-/// `[8:opcode][4:dest]`
+/// Float remainder, truncating the quotient. Always exact
+/// `[8:opcode][4:dest][4:src1][4:src2]`
 pub const FREM: u8 = 0x36;
 /// Multiply high signed
-/// `[8:opcode][4:dest]`
+/// `[8:opcode][4:dest][4:src1][4:src2]`
 pub const MUHS: u8 = 0x37;
 /// Multiply high unsigned
+/// `[8:opcode][4:dest][4:src1][4:src2]`
+pub const MUHU: u8 = 0x38;
+/// Compare greater-than (signed)
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
+pub const SGT: u8  = 0x39;
+/// Compare less-than (signed)
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
+pub const SLT: u8  = 0x3A;
+/// Compare greater-than-or-equal (signed)
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
+pub const SGE: u8  = 0x3B;
+/// Compare less-than-or-equal (signed)
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
+pub const SLE: u8  = 0x3C;
+/// Divide (signed), rounding toward zero. Division by zero yields 0 and
+/// `i32::MIN / -1` wraps to `i32::MIN`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
+pub const SDIV: u8 = 0x3D;
+/// Remainder (signed), with the sign of the dividend. Division by zero
+/// yields 0, as does `i32::MIN % -1`
+/// `[8:opcode][4:dest][4:src1][4:src2]` or `[8:opcode][4:dest][4:src1][31:imm][1:1]`
+pub const SREM: u8 = 0x3E;
+/// Float compare equal. False if either operand is NaN; only a signaling NaN
+/// raises the invalid exception
+/// `[8:opcode][4:dest][4:src1][4:src2]`
+pub const FEQ: u8  = 0x3F;
+/// Float compare less-than. False if either operand is NaN, which raises the
+/// invalid exception
+/// `[8:opcode][4:dest][4:src1][4:src2]`
+pub const FLT: u8  = 0x40;
+/// Float compare less-than-or-equal. False if either operand is NaN, which
+/// raises the invalid exception
+/// `[8:opcode][4:dest][4:src1][4:src2]`
+pub const FLE: u8  = 0x41;
+/// Float minimum. A NaN operand is ignored unless both are NaN; -0 is below +0
+/// `[8:opcode][4:dest][4:src1][4:src2]`
+pub const FMIN: u8 = 0x42;
+/// Float maximum. A NaN operand is ignored unless both are NaN; +0 is above -0
+/// `[8:opcode][4:dest][4:src1][4:src2]`
+pub const FMAX: u8 = 0x43;
+/// Float square root, rounded in the FCSR rounding mode. Negative operands
+/// other than -0 give NaN
+/// `[8:opcode][4:dest][4:src]`
+pub const FSQRT: u8 = 0x44;
+/// Float absolute value. Clears the sign bit, NaNs included
+/// `[8:opcode][4:dest][4:src]`
+pub const FABS: u8 = 0x45;
+/// Float negate. Flips the sign bit, NaNs included
+/// `[8:opcode][4:dest][4:src]`
+pub const FNEG: u8 = 0x46;
+/// Fused multiply-add, `src1 * src2 + src3` with a single rounding in the FCSR
+/// rounding mode
+/// `[8:opcode][4:dest][4:src1][4:src2][4:src3]`
+pub const FMA: u8  = 0x47;
+/// Convert signed integer to float. Rounding mode 7 (`dyn`) uses the FCSR mode
+/// `[8:opcode][4:dest][4:src][3:rounding mode]`
+pub const ITOF: u8 = 0x48;
+/// Convert unsigned integer to float. Rounding mode 7 (`dyn`) uses the FCSR mode
+/// `[8:opcode][4:dest][4:src][3:rounding mode]`
+pub const UTOF: u8 = 0x49;
+/// Convert float to signed integer. Out-of-range values saturate and NaN gives 0,
+/// raising the invalid exception. Rounding mode 7 (`dyn`) uses the FCSR mode
+/// `[8:opcode][4:dest][4:src][3:rounding mode]`
+pub const FTOI: u8 = 0x4A;
+/// Convert float to unsigned integer. Out-of-range values saturate and NaN gives
+/// 0, raising the invalid exception. Rounding mode 7 (`dyn`) uses the FCSR mode
+/// `[8:opcode][4:dest][4:src][3:rounding mode]`
+pub const FTOU: u8 = 0x4B;
+/// Read the floating-point control/status register
+/// `[8:opcode][4:dest]`
+pub const FRCSR: u8 = 0x4C;
+/// Write the floating-point control/status register. Unassigned bits are
+/// ignored
+/// `[8:opcode][4:src]`
+pub const FWCSR: u8 = 0x4D;
+/// Double add, rounded in the FCSR rounding mode
+/// `[8:opcode][4:dest pair][4:src1 pair][4:src2 pair]`
+pub const DADD: u8 = 0x4E;
+/// Double subtract, rounded in the FCSR rounding mode
+/// `[8:opcode][4:dest pair][4:src1 pair][4:src2 pair]`
+pub const DSUB: u8 = 0x4F;
+/// Double multiply, rounded in the FCSR rounding mode
+/// `[8:opcode][4:dest pair][4:src1 pair][4:src2 pair]`
+pub const DMUL: u8 = 0x50;
+/// Double divide, rounded in the FCSR rounding mode
+/// `[8:opcode][4:dest pair][4:src1 pair][4:src2 pair]`
+pub const DDIV: u8 = 0x51;
+/// Double square root, rounded in the FCSR rounding mode. Negative operands
+/// other than -0 give NaN
+/// `[8:opcode][4:dest pair][4:src pair]`
+pub const DSQRT: u8 = 0x52;
+/// Double absolute value. Clears the sign bit, NaNs included
+/// `[8:opcode][4:dest pair][4:src pair]`
+pub const DABS: u8 = 0x53;
+/// Double negate. Flips the sign bit, NaNs included
+/// `[8:opcode][4:dest pair][4:src pair]`
+pub const DNEG: u8 = 0x54;
+/// Double compare equal. False if either operand is NaN; only a signaling NaN
+/// raises the invalid exception
+/// `[8:opcode][4:dest][4:src1 pair][4:src2 pair]`
+pub const DEQ: u8  = 0x55;
+/// Double compare less-than. False if either operand is NaN, which raises the
+/// invalid exception
+/// `[8:opcode][4:dest][4:src1 pair][4:src2 pair]`
+pub const DLT: u8  = 0x56;
+/// Double compare less-than-or-equal. False if either operand is NaN, which
+/// raises the invalid exception
+/// `[8:opcode][4:dest][4:src1 pair][4:src2 pair]`
+pub const DLE: u8  = 0x57;
+/// Convert float to double. Always exact
+/// `[8:opcode][4:dest pair][4:src]`
+pub const FTOD: u8 = 0x58;
+/// Convert double to float. Rounding mode 7 (`dyn`) uses the FCSR mode
+/// `[8:opcode][4:dest][4:src pair][3:rounding mode]`
+pub const DTOF: u8 = 0x59;
+/// Convert signed integer to double. Always exact
+/// `[8:opcode][4:dest pair][4:src]`
+pub const ITOD: u8 = 0x5A;
+/// Convert unsigned integer to double. Always exact
+/// `[8:opcode][4:dest pair][4:src]`
+pub const UTOD: u8 = 0x5B;
+/// Convert double to signed integer. Out-of-range values saturate and NaN gives
+/// 0, raising the invalid exception. Rounding mode 7 (`dyn`) uses the FCSR mode
+/// `[8:opcode][4:dest][4:src pair][3:rounding mode]`
+pub const DTOI: u8 = 0x5C;
+/// Convert double to unsigned integer. Out-of-range values saturate and NaN
+/// gives 0, raising the invalid exception. Rounding mode 7 (`dyn`) uses the FCSR mode
+/// `[8:opcode][4:dest][4:src pair][3:rounding mode]`
+pub const DTOU: u8 = 0x5D;
+/// Store halfword at address `dest + offset`
+/// `[8:opcode][4:dest][4:src][32:offset]`
+pub const SH: u8   = 0x5E;
+/// Load halfword signed from address `src + offset`
+/// `[8:opcode][4:dest][4:src][32:offset]`
+pub const LHS: u8  = 0x5F;
+/// Load halfword unsigned from address `src + offset`
+/// `[8:opcode][4:dest][4:src][32:offset]`
+pub const LHU: u8  = 0x60;
+/// Atomically load the word at address `addr` into `dest` and store `src` there
+/// `[8:opcode][4:dest][4:addr][4:src]`
+pub const SWAP: u8 = 0x61;
+/// Atomically compare the word at address `addr` with `expected` and, if they
+/// are equal, replace it with `new`. `dest` receives the old word either way,
+/// so the swap happened exactly when `dest == expected`
+/// `[8:opcode][4:dest][4:addr][4:expected][4:new]`
+pub const CAS: u8  = 0x62;
+/// Atomically add `src` to the word at address `addr`, wrapping, and load the
+/// old word into `dest`
+/// `[8:opcode][4:dest][4:addr][4:src]`
+pub const XADD: u8 = 0x63;
+/// Load reserved: load the word at address `addr` into `dest` and reserve it.
+/// The reservation covers the four bytes at `addr` and is broken by any store
+/// that overlaps them, by another `lr`, by an `sc` and by reset
+/// `[8:opcode][4:dest][4:addr]`
+pub const LR: u8   = 0x64;
+/// Store conditional: if the reservation from the last `lr` is still held for
+/// exactly `addr`, store `src` there and set `dest` to 0; otherwise store
+/// nothing and set `dest` to 1. The reservation is released either way
+/// `[8:opcode][4:dest][4:addr][4:src]`
+pub const SC: u8   = 0x65;
+/// Memory fence: every load and store before it completes before any after it.
+/// Memory is sequentially consistent, so this only marks the ordering point
+/// `[8:opcode]`
+pub const FENCE: u8 = 0x66;
+/// Load the ID of the executing core, counting from 0
+/// `[8:opcode][4:dest]`
+pub const CID: u8  = 0x67;
+/// Load the number of cores in the system
+/// `[8:opcode][4:dest]`
+pub const NCORES: u8 = 0x68;
+/// Send an inter-processor interrupt to the core whose ID is in `src`,
+/// setting this core's bit in its pending mask. IDs past the last core are ignored
+/// `[8:opcode][4:src]`
+pub const IPI: u8  = 0x69;
+/// Set the interrupt handler address to `src`; 0 turns delivery off
+/// `[8:opcode][4:src]`
+pub const SIV: u8  = 0x6A;
+/// Load the pending IPI mask, bit `n` for core `n`, and clear it
 /// `[8:opcode][4:dest]`
-pub const MUHU: u8 = 0x38;
\ No newline at end of file
+pub const IACK: u8 = 0x6B;
+/// Return from an interrupt handler: pop RPC like `ret` and unmask delivery
+/// `[8:opcode]`
+pub const IRET: u8 = 0x6C;
+/// Wait for interrupt: unless an IPI is already pending, stop this core until
+/// one arrives. Waking does not need a handler; the IPI stays pending
+/// `[8:opcode]`
+pub const WFI: u8  = 0x6D;
+/// Count leading zeros: the number of zero bits above the highest set bit of `src`,
+/// 32 if it is zero
+/// `[8:opcode][4:dest][4:src]`
+pub const CLZ: u8  = 0x6E;
+/// Count trailing zeros: the number of zero bits below the lowest set bit of `src`,
+/// 32 if it is zero
+/// `[8:opcode][4:dest][4:src]`
+pub const CTZ: u8  = 0x6F;
+/// Population count: the number of set bits in `src`
+/// `[8:opcode][4:dest][4:src]`
+pub const POPC: u8 = 0x70;
+/// Reverse the byte order of `src`
+/// `[8:opcode][4:dest][4:src]`
+pub const BSWAP: u8 = 0x71;
+/// Bit-field extract: `width` bits of `src` starting at bit `lsb`, zero-extended.
+/// The field must pass [`is_bit_field`], or the instruction is a NOP
+/// `[8:opcode][4:dest][4:src][8:lsb][8:width]`
+pub const BFX: u8  = 0x72;
+/// Bit-field insert: replace `width` bits of `dest` starting at bit `lsb` with the
+/// low bits of `src`, keeping the rest of `dest`. The field is limited as for `bfx`
+/// `[8:opcode][4:dest][4:src][8:lsb][8:width]`
+pub const BFI: u8  = 0x73;
+/// Sign-extend the low byte of `src`
+/// `[8:opcode][4:dest][4:src]`
+pub const SEXTB: u8 = 0x74;
+/// Sign-extend the low halfword of `src`
+/// `[8:opcode][4:dest][4:src]`
+pub const SEXTH: u8 = 0x75;
+/// Zero-extend the low byte of `src`
+/// `[8:opcode][4:dest][4:src]`
+pub const ZEXTB: u8 = 0x76;
+/// Zero-extend the low halfword of `src`
+/// `[8:opcode][4:dest][4:src]`
+pub const ZEXTH: u8 = 0x77;
+/// Add with carry: `dest = src1 + src2 + (carry & 1)`, wrapping, then `carry` is set
+/// to the carry out, 0 or 1. If `dest` and `carry` are the same register it gets the sum
+/// `[8:opcode][4:dest][4:carry][4:src1][4:src2]`
+pub const ADC: u8  = 0x78;
+/// Subtract with borrow: `dest = src1 - src2 - (borrow & 1)`, wrapping, then `borrow`
+/// is set to the borrow out, 0 or 1. If `dest` and `borrow` are the same register it
+/// gets the difference
+/// `[8:opcode][4:dest][4:borrow][4:src1][4:src2]`
+pub const SBB: u8  = 0x79;
+/// Add, wrapping, and set `overflow` to 1 if the signed sum overflowed, else 0.
+/// If `dest` and `overflow` are the same register it gets the sum
+/// `[8:opcode][4:dest][4:overflow][4:src1][4:src2]`
+pub const ADDO: u8 = 0x7A;
+/// Subtract, wrapping, and set `overflow` to 1 if the signed difference overflowed,
+/// else 0. If `dest` and `overflow` are the same register it gets the difference
+/// `[8:opcode][4:dest][4:overflow][4:src1][4:src2]`
+pub const SUBO: u8 = 0x7B;
+/// Select: `dest = src1` if `cond` is nonzero, else `dest = src2`
+/// `[8:opcode][4:dest][4:cond][4:src1][4:src2]`
+pub const SEL: u8  = 0x7C;
+
+/// Set in the instructions of [`has_immediate_form`] when `src2` is replaced by
+/// a 31-bit immediate, sign-extended to 32 bits.
+pub const IMMEDIATE_FLAG: u64 = 1 << 47;
+
+/// Whether `opcode` accepts an immediate in place of `src2`.
+pub fn has_immediate_form(opcode: u8) -> bool {
+    matches!(
+        opcode,
+        BOR | BAND | BXOR | SHL | LSHR | ASHR | ROTL | ROTR | EQ | NE | GT | LT | GE | LE
+            | SGT | SLT | SGE | SLE | MUL | DIV | REM | SDIV | SREM
+    )
+}
+
+/// Bit offsets of the operand fields of `opcode` that name register pairs.
+pub fn pair_fields(opcode: u8) -> &'static [u32] {
+    match opcode {
+        DADD | DSUB | DMUL | DDIV => &[8, 12, 16],
+        DSQRT | DABS | DNEG => &[8, 12],
+        DEQ | DLT | DLE => &[12, 16],
+        FTOD | ITOD | UTOD => &[8],
+        DTOF | DTOI | DTOU => &[12],
+        _ => &[],
+    }
+}
+
+/// Whether a `bfx` or `bfi` field of `width` bits at bit `lsb` is 1 to 32
+/// bits wide and lies entirely within a word.
+pub fn is_bit_field(lsb: u32, width: u32) -> bool {
+    width != 0 && lsb.checked_add(width).is_some_and(|end| end <= 32)
+}
+
+/// Returns the assembler mnemonic for `opcode`, or `None` if it is unassigned.
+pub fn mnemonic(opcode: u8) -> Option<&'static str> {
+    Some(match opcode {
+        NOP => "nop",
+        HLT => "hlt",
+        LDI => "ldi",
+        ADD => "add",
+        SUB => "sub",
+        BOR => "bor",
+        BAND => "band",
+        BXOR => "bxor",
+        BNOT => "bnot",
+        LOR => "lor",
+        LAND => "land",
+        LXOR => "lxor",
+        LNOT => "lnot",
+        SB => "sb",
+        SW => "sw",
+        LBS => "lbs",
+        LBU => "lbu",
+        LW => "lw",
+        JMR => "jmr",
+        JRI => "jri",
+        CAR => "car",
+        CRI => "cri",
+        JMI => "jmi",
+        JII => "jii",
+        CAI => "cai",
+        CII => "cii",
+        RET => "ret",
+        EQ => "eq",
+        NE => "ne",
+        GT => "gt",
+        LT => "lt",
+        GE => "ge",
+        LE => "le",
+        INC => "inc",
+        DEC => "dec",
+        ADDI => "addi",
+        SUBI => "subi",
+        SHL => "shl",
+        LSHR => "lshr",
+        ASHR => "ashr",
+        ROTL => "rotl",
+        ROTR => "rotr",
+        PB => "pb",
+        PW => "pw",
+        POBS => "pobs",
+        POBU => "pobu",
+        POW => "pow",
+        MUL => "mul",
+        DIV => "div",
+        REM => "rem",
+        FADD => "fadd",
+        FSUB => "fsub",
+        FMUL => "fmul",
+        FDIV => "fdiv",
+        FREM => "frem",
+        MUHS => "muhs",
+        MUHU => "muhu",
+        SGT => "sgt",
+        SLT => "slt",
+        SGE => "sge",
+        SLE => "sle",
+        SDIV => "sdiv",
+        SREM => "srem",
+        FEQ => "feq",
+        FLT => "flt",
+        FLE => "fle",
+        FMIN => "fmin",
+        FMAX => "fmax",
+        FSQRT => "fsqrt",
+        FABS => "fabs",
+        FNEG => "fneg",
+        FMA => "fma",
+        ITOF => "itof",
+        UTOF => "utof",
+        FTOI => "ftoi",
+        FTOU => "ftou",
+        FRCSR => "frcsr",
+        FWCSR => "fwcsr",
+        DADD => "dadd",
+        DSUB => "dsub",
+        DMUL => "dmul",
+        DDIV => "ddiv",
+        DSQRT => "dsqrt",
+        DABS => "dabs",
+        DNEG => "dneg",
+        DEQ => "deq",
+        DLT => "dlt",
+        DLE => "dle",
+        FTOD => "ftod",
+        DTOF => "dtof",
+        ITOD => "itod",
+        UTOD => "utod",
+        DTOI => "dtoi",
+        DTOU => "dtou",
+        SH => "sh",
+        LHS => "lhs",
+        LHU => "lhu",
+        SWAP => "swap",
+        CAS => "cas",
+        XADD => "xadd",
+        LR => "lr",
+        SC => "sc",
+        FENCE => "fence",
+        CID => "cid",
+        NCORES => "ncores",
+        IPI => "ipi",
+        SIV => "siv",
+        IACK => "iack",
+        IRET => "iret",
+        WFI => "wfi",
+        CLZ => "clz",
+        CTZ => "ctz",
+        POPC => "popc",
+        BSWAP => "bswap",
+        BFX => "bfx",
+        BFI => "bfi",
+        SEXTB => "sextb",
+        SEXTH => "sexth",
+        ZEXTB => "zextb",
+        ZEXTH => "zexth",
+        ADC => "adc",
+        SBB => "sbb",
+        ADDO => "addo",
+        SUBO => "subo",
+        SEL => "sel",
+        _ => return None,
+    })
+}
@@ -1,182 +1,734 @@
 pub mod registers;
 pub mod isa;
 pub mod assembler;
+pub mod disassembler;
+pub mod threaded;
+pub mod snapshot;
+pub mod debugger;
+pub mod profiler;
+pub mod coverage;
+pub mod debuginfo;
+pub mod symbols;
+pub mod config;
+pub mod float;
+pub mod expect;
+pub mod fuzz;
+pub mod system;
+#[cfg(test)]
+mod tests;
 
-use std::path::Path;
 use registers::*;
+use threaded::BlockCache;
+use config::{MachineBuilder, MachineConfig};
+use float::RoundingMode;
+
+/// Where programs are loaded and execution starts on the default board.
+pub const CODE_BASE: u32 = 0xC000_0000;
+
+pub const PAGE_SHIFT: u32 = 12;
+pub const PAGE_SIZE: usize = 1 << PAGE_SHIFT;
+const PAGE_COUNT: usize = 1 << (32 - PAGE_SHIFT);
+
+fn zeroed_memory() -> Box<[u8; 4_294_967_296]> {
+    vec![0u8; 4_294_967_296]
+        .into_boxed_slice()
+        .try_into()
+        .unwrap()
+}
+
+/// The register pairs named by the fields of `inst` at `shifts`, or `None`
+/// if any of them is not a pair, in which case the instruction is a NOP.
+fn pairs<const N: usize>(inst: u64, shifts: [u32; N]) -> Option<[usize; N]> {
+    let regs = shifts.map(|shift| ((inst >> shift) & 0xF) as u8);
+    regs.into_iter().all(is_pair).then(|| regs.map(|reg| reg as usize))
+}
+
+/// The `(lsb, mask)` of the bit field named by a `bfx` or `bfi`, or `None`
+/// if it is not an [`isa::is_bit_field`], in which case the instruction is a NOP.
+fn bit_field(inst: u64) -> Option<(u32, u32)> {
+    let lsb = ((inst >> 16) & 0xFF) as u32;
+    let width = ((inst >> 24) & 0xFF) as u32;
+    isa::is_bit_field(lsb, width).then(|| (lsb, u32::MAX >> (32 - width)))
+}
+
+/// Selects how [`Helios32::run`] executes guest code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// Fetch, decode and execute one instruction per [`Helios32::cycle`].
+    #[default]
+    Interpreter,
+    /// Translate basic blocks into pre-decoded micro-ops and execute whole blocks.
+    Threaded,
+}
+
+/// One of the two hardware stacks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stack {
+    /// Grows down from RSP, used by `PB`/`PW` and the pops.
+    Data,
+    /// Grows down from CSP, used by calls and `RET`.
+    Call,
+}
+
+/// An error that stops the machine. The faulting instruction has no effect
+/// and RPC is left pointing at it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// A push would store below the stack's limit.
+    StackOverflow { stack: Stack, pc: u32, sp: u32 },
+    /// A pop would read above the stack's top.
+    StackUnderflow { stack: Stack, pc: u32, sp: u32 },
+    /// A float instruction raised an exception whose trap is enabled in FCSR.
+    /// `flags` holds every exception the instruction raised.
+    FloatingPoint { pc: u32, flags: u32 },
+}
+
+impl std::fmt::Display for Fault {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (kind, stack, pc, sp) = match *self {
+            Fault::StackOverflow { stack, pc, sp } => ("overflow", stack, pc, sp),
+            Fault::StackUnderflow { stack, pc, sp } => ("underflow", stack, pc, sp),
+            Fault::FloatingPoint { pc, flags } => {
+                return write!(f, "floating-point exception ({}) at pc 0x{pc:08X}", float::flag_names(flags));
+            },
+        };
+        let stack = match stack {
+            Stack::Data => "data",
+            Stack::Call => "call",
+        };
+        write!(f, "{stack} stack {kind} at pc 0x{pc:08X} (sp 0x{sp:08X})")
+    }
+}
+
+/// Inter-processor interrupt state of a core.
+///
+/// An interrupt is delivered before the next instruction while one is
+/// pending, a handler is set and no handler is already running: the address
+/// of that next instruction is pushed on the call stack like a call and
+/// execution continues at the handler.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Interrupts {
+    /// Handler address set by `siv`; 0 turns delivery off.
+    pub vector: u32,
+    /// Bit `n` is set while an IPI from core `n` is unacknowledged.
+    pub pending: u32,
+    /// Set from delivery until `iret`, masking further delivery.
+    pub in_handler: bool,
+    /// Set by a `wfi` that found nothing pending, until an IPI arrives.
+    pub waiting: bool,
+}
+
+impl Interrupts {
+    fn is_deliverable(&self) -> bool {
+        self.pending != 0 && self.vector != 0 && !self.in_handler
+    }
+}
+
+/// The state a core keeps to itself: everything but memory and devices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Core {
+    pub registers: [u32; 16],
+    /// Floating-point control/status register; see [`float::FCSR_MASK`].
+    pub fcsr: u32,
+    /// Whether the core is running. Halting, faulting and waiting in `wfi`
+    /// all clear it.
+    pub is_running: bool,
+    /// Why the core stopped, if the last instruction faulted.
+    pub fault: Option<Fault>,
+    pub interrupts: Interrupts,
+    /// Word address held by the last `lr`, until a store overlaps it.
+    reservation: Option<u32>,
+    /// What `cid` reads.
+    id: u32,
+    /// Deepest use of the data and call stacks in bytes since reset.
+    stack_high_water: [u32; 2],
+    /// Top and optional limit of the core's data and call stacks.
+    stacks: [(u32, Option<u32>); 2],
+}
+
+impl Core {
+    /// A stopped core with zeroed registers except for the stack pointers,
+    /// which start at the tops of `stacks`.
+    fn new(id: u32, stacks: [(u32, Option<u32>); 2]) -> Self {
+        let mut registers = [0; 16];
+        registers[RSP as usize] = stacks[Stack::Data as usize].0;
+        registers[CSP as usize] = stacks[Stack::Call as usize].0;
+        Self {
+            registers,
+            fcsr: 0,
+            is_running: false,
+            fault: None,
+            interrupts: Interrupts::default(),
+            reservation: None,
+            id,
+            stack_high_water: [0; 2],
+            stacks,
+        }
+    }
+
+    /// Deepest use of `stack` in bytes since the last reset.
+    pub fn stack_high_water(&self, stack: Stack) -> u32 {
+        self.stack_high_water[stack as usize]
+    }
+}
 
 #[derive(Clone)]
 pub struct Helios32 {
-    pub registers: [u32; 16],
+    /// The machine's own core. A [`system::System`] runs its cores against
+    /// the memory and devices here instead.
+    pub core: Core,
     pub mem: Box<[u8; 4_294_967_296]>,
-    pub is_running: bool,
+    pub engine: Engine,
+    pub config: MachineConfig,
+    /// The read-only `(base, size)` window once a boot ROM is loaded.
+    rom: Option<(u32, u32)>,
+    /// What `ncores` reads. A lone machine is core 0 of 1.
+    core_count: u32,
+    /// Cores sent an IPI since the owning [`system::System`] last collected them.
+    outbox: u32,
+    translations: BlockCache,
+    written_pages: Box<[u64]>,
+    /// When set, every store appends `(addr, previous byte)` here, so the
+    /// debugger can undo it.
+    pub(crate) write_log: Option<Vec<(u32, u8)>>,
+    /// When set, every store appends its address here, so a
+    /// [`system::System`] can break the other cores' reservations.
+    store_log: Option<Vec<u32>>,
+}
+
+/// A core executing against a machine's memory and devices.
+struct Cpu<'a> {
+    core: &'a mut Core,
+    mem: &'a mut [u8; 4_294_967_296],
+    engine: Engine,
+    config: &'a MachineConfig,
+    rom: Option<(u32, u32)>,
+    core_count: u32,
+    outbox: &'a mut u32,
+    translations: &'a mut BlockCache,
+    written_pages: &'a mut [u64],
+    write_log: &'a mut Option<Vec<(u32, u8)>>,
+    store_log: &'a mut Option<Vec<u32>>,
+}
+
+/// Reads the 6-byte instruction at `pc`, zero-extended to a `u64`.
+fn fetch(mem: &[u8; 4_294_967_296], pc: u32) -> u64 {
+    u64::from_le_bytes([
+        mem[pc as usize],
+        mem[pc.wrapping_add(1) as usize],
+        mem[pc.wrapping_add(2) as usize],
+        mem[pc.wrapping_add(3) as usize],
+        mem[pc.wrapping_add(4) as usize],
+        mem[pc.wrapping_add(5) as usize],
+        0u8,
+        0u8
+    ])
+}
+
+/// Whether stores to `addr` take effect: it is mapped and outside `rom`.
+fn is_writable(config: &MachineConfig, rom: Option<(u32, u32)>, addr: u32) -> bool {
+    config.is_mapped(addr) && rom.is_none_or(|(base, size)| addr.wrapping_sub(base) >= size)
 }
 
 impl Helios32 {
+    /// A machine with the default [`MachineConfig`].
     pub fn new() -> Self {
-        let mut registers = [0u32; 16];
-        registers[RPC as usize] = 3_221_225_472u32; // 1GB Instruction Memory
-        registers[CSP as usize] = 3_221_225_471u32; // Call stack starting at 0xBFFFFFFF
+        let mut vm = Self {
+            core: Core::new(0, [(0, None); 2]),
+            mem: zeroed_memory(),
+            engine: Engine::default(),
+            config: MachineConfig::default(),
+            rom: None,
+            core_count: 1,
+            outbox: 0,
+            translations: BlockCache::new(),
+            written_pages: vec![0u64; PAGE_COUNT / 64].into_boxed_slice(),
+            write_log: None,
+            store_log: None,
+        };
+        vm.reset();
+        vm
+    }
 
-        Self {
-            registers,
-            mem: vec![0u8; 4_294_967_296]
-                .into_boxed_slice()
-                .try_into()
-                .unwrap(),
-            is_running: false,
+    /// Returns registers and device windows to their power-on state. RAM and
+    /// the boot ROM keep their contents.
+    pub fn reset(&mut self) {
+        self.core = Core::new(0, self.config.stacks());
+        self.core.registers[RPC as usize] = self.reset_vector();
+        self.outbox = 0;
+
+        let devices = self.config.devices.iter()
+            .map(|dev| (dev.base, dev.size))
+            .collect::<Vec<_>>();
+        for (base, size) in devices {
+            let first = base as usize >> PAGE_SHIFT;
+            let last = (base as usize + size as usize - 1) >> PAGE_SHIFT;
+            for page in first..=last {
+                if self.written_pages[page / 64] & (1 << (page % 64)) == 0 {
+                    continue;
+                }
+                let start = (page << PAGE_SHIFT).max(base as usize);
+                let end = ((page + 1) << PAGE_SHIFT).min(base as usize + size as usize);
+                self.mem[start..end].fill(0);
+            }
+        }
+        self.flush_translations();
+    }
+
+    /// Where execution starts after [`Helios32::reset`]: the configured reset
+    /// vector, else the boot ROM, else the code base.
+    pub fn reset_vector(&self) -> u32 {
+        match (self.config.reset_vector, self.rom) {
+            (Some(addr), _) => addr,
+            (None, Some((base, _))) => base,
+            (None, None) => self.config.code_base,
         }
     }
 
-    pub fn load_program(&mut self, program: &[u8]) {
-        assert!(program.len() <= 1_073_741_824, "program length exceeds limit of 1GB");
+    pub fn builder() -> MachineBuilder {
+        MachineBuilder::default()
+    }
+
+    /// Copies `program` into memory starting at `origin`.
+    pub fn load_program(&mut self, origin: u32, program: &[u8]) -> Result<(), String> {
+        if program.len() as u64 > config::ADDRESS_SPACE || !self.config.is_mapped_range(origin, program.len() as u32) {
+            return Err(format!(
+                "program of {} bytes at 0x{origin:08X} does not fit in mapped memory",
+                program.len()
+            ));
+        }
 
-        self.mem[3_221_225_472..3_221_225_472 + program.len()].copy_from_slice(program);
+        let base = origin as usize;
+        self.mem[base..base + program.len()].copy_from_slice(program);
+        self.mark_written(origin, program.len() as u32);
+        Ok(())
     }
 
-    pub fn load_program_from_path<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
-        use std::fs;
+    /// Deepest use of `stack` in bytes since the last reset.
+    pub fn stack_high_water(&self, stack: Stack) -> u32 {
+        self.core.stack_high_water(stack)
+    }
 
-        let bytes = fs::read(path)
-            .map_err(|err| err.to_string())?;
+    /// Loads a boot ROM image into the configured ROM window and makes the
+    /// window read-only. Takes effect for execution on the next [`Helios32::reset`].
+    pub fn load_rom(&mut self, image: &[u8]) -> Result<(), String> {
+        let (base, size) = (self.config.rom_base, self.config.rom_size);
+        if image.len() as u64 > size as u64 {
+            return Err(format!(
+                "ROM image of {} bytes does not fit in the {size} byte ROM window",
+                image.len()
+            ));
+        }
 
-        self.load_program(&bytes);
+        self.rom = None;
+        self.load_program(base, image)?;
+        self.rom = Some((base, size));
         Ok(())
     }
 
+    /// Discards every translated block.
+    pub fn flush_translations(&mut self) {
+        self.translations.clear();
+    }
+
+    /// Records that `len` bytes starting at `start` were written through `mem`
+    /// directly, so translations are dropped and snapshots include the pages.
+    pub fn mark_written(&mut self, start: u32, len: u32) {
+        if len == 0 {
+            return;
+        }
+
+        let first = start as usize >> PAGE_SHIFT;
+        let last = start.wrapping_add(len - 1) as usize >> PAGE_SHIFT;
+        let mut page = first;
+        loop {
+            self.written_pages[page / 64] |= 1 << (page % 64);
+            if page == last {
+                break;
+            }
+            page = (page + 1) % PAGE_COUNT;
+        }
+        self.flush_translations();
+    }
+
+    /// Execution state for `core`, or the machine's own core if `None`,
+    /// against this machine's memory and devices.
+    fn cpu<'a>(&'a mut self, core: Option<&'a mut Core>) -> Cpu<'a> {
+        let Self {
+            core: own, mem, engine, config, rom, core_count, outbox,
+            translations, written_pages, write_log, store_log,
+        } = self;
+        Cpu {
+            core: core.unwrap_or(own),
+            mem,
+            engine: *engine,
+            config,
+            rom: *rom,
+            core_count: *core_count,
+            outbox,
+            translations,
+            written_pages: &mut written_pages[..],
+            write_log,
+            store_log,
+        }
+    }
+
     pub fn run(&mut self) {
-        self.is_running = true;
+        self.run_for(u64::MAX);
+    }
+
+    /// Runs until the machine halts or `budget` instructions have executed,
+    /// returning the number of instructions executed.
+    pub fn run_for(&mut self, budget: u64) -> u64 {
+        self.core.is_running = true;
+        self.core.interrupts.waiting = false;
+        self.cpu(None).run_for(budget)
+    }
+
+    /// Runs like [`Helios32::run_for`] on the interpreter, calling
+    /// `observe(vm, pc, inst, registers_before)` after every instruction.
+    pub fn run_observed(&mut self, budget: u64, mut observe: impl FnMut(&Helios32, u32, u64, &[u32; 16])) -> u64 {
+        self.core.is_running = true;
+
+        let mut executed = 0;
+        while self.core.is_running && executed < budget {
+            let before = self.core.registers;
+            let pc = before[RPC as usize];
+            let inst = self.fetch(pc);
 
-        while self.is_running {
             self.cycle();
+            executed += 1;
+
+            observe(self, pc, inst, &before);
         }
+        executed
     }
 
     pub fn cycle(&mut self) {
-        self.registers[0] = 0u32;
-        
-        let pc = self.registers[RPC as usize];
-        let inst = u64::from_le_bytes([
-            self.mem[pc as usize],
-            self.mem[pc.wrapping_add(1) as usize],
-            self.mem[pc.wrapping_add(2) as usize],
-            self.mem[pc.wrapping_add(3) as usize],
-            self.mem[pc.wrapping_add(4) as usize],
-            self.mem[pc.wrapping_add(5) as usize],
-            0u8,
-            0u8
-        ].try_into().unwrap());
-        self.registers[RPC as usize] = pc.wrapping_add(6);
+        self.cpu(None).cycle();
+    }
+
+    /// Reads the 6-byte instruction at `pc`, zero-extended to a `u64`.
+    pub fn fetch(&self, pc: u32) -> u64 {
+        fetch(&self.mem, pc)
+    }
+
+    /// Whether stores to `addr` take effect: it is mapped and not in a loaded
+    /// boot ROM.
+    pub fn is_writable(&self, addr: u32) -> bool {
+        is_writable(&self.config, self.rom, addr)
+    }
+
+    /// Stores a byte, invalidating any translated block that covers `addr`.
+    /// Stores to unmapped addresses and to a loaded boot ROM are dropped.
+    pub fn write_u8(&mut self, addr: u32, value: u8) {
+        self.cpu(None).write_u8(addr, value);
+    }
+}
+
+impl Cpu<'_> {
+    /// The stack pointer register, top and optional limit of `stack`.
+    fn stack_bounds(&self, stack: Stack) -> (u8, u32, Option<u32>) {
+        match stack {
+            Stack::Data => (RSP, self.core.stacks[0].0, self.core.stacks[0].1),
+            Stack::Call => (CSP, self.core.stacks[1].0, self.core.stacks[1].1),
+        }
+    }
+
+    /// Checks that `len` bytes can be pushed onto `stack` by the instruction at
+    /// `pc`, raising a fault if not.
+    fn check_push(&mut self, stack: Stack, pc: u32, len: u32) -> bool {
+        let (reg, top, limit) = self.stack_bounds(stack);
+        let sp = self.core.registers[reg as usize];
+        if limit.is_some_and(|limit| (sp as u64) < limit as u64 + len as u64 - 1) {
+            self.raise(Fault::StackOverflow { stack, pc, sp });
+            return false;
+        }
+        let depth = top.saturating_sub(sp).saturating_add(len);
+        let high_water = &mut self.core.stack_high_water[stack as usize];
+        *high_water = (*high_water).max(depth);
+        true
+    }
+
+    /// Checks that `len` bytes can be popped from `stack` by the instruction at
+    /// `pc`, raising a fault if not.
+    fn check_pop(&mut self, stack: Stack, pc: u32, len: u32) -> bool {
+        let (reg, top, limit) = self.stack_bounds(stack);
+        let sp = self.core.registers[reg as usize];
+        if limit.is_some() && sp as u64 + len as u64 > top as u64 {
+            self.raise(Fault::StackUnderflow { stack, pc, sp });
+            return false;
+        }
+        true
+    }
+
+    fn raise(&mut self, fault: Fault) {
+        self.core.fault = Some(fault);
+        self.core.is_running = false;
+        let pc = match fault {
+            Fault::StackOverflow { pc, .. } | Fault::StackUnderflow { pc, .. } => pc,
+            Fault::FloatingPoint { pc, .. } => pc,
+        };
+        self.core.registers[RPC as usize] = pc;
+    }
+
+    /// The second operand of an instruction with an [`isa::has_immediate_form`]:
+    /// register `src2`, or with bit 47 set, the sign-extended 31-bit immediate
+    /// in bits 16..46.
+    fn operand(&self, inst: u64) -> u32 {
+        if inst & isa::IMMEDIATE_FLAG != 0 {
+            ((((inst >> 16) as u32) << 1) as i32 >> 1) as u32
+        } else {
+            self.core.registers[((inst >> 16) & 0xF) as usize]
+        }
+    }
+
+    /// The rounding mode selected in FCSR.
+    fn rounding_mode(&self) -> RoundingMode {
+        RoundingMode::from_bits((self.core.fcsr >> float::FCSR_ROUNDING_SHIFT) & 0x7)
+    }
+
+    /// Decodes the rounding mode field of a conversion instruction.
+    fn conversion_rounding(&self, inst: u64) -> RoundingMode {
+        match ((inst >> 16) & 0x7) as u8 {
+            float::DYNAMIC_ROUNDING => self.rounding_mode(),
+            bits => RoundingMode::from_bits(bits as u32),
+        }
+    }
+
+    /// Completes a float instruction at `pc`: writes `value` to `dest` and
+    /// accrues `flags` in FCSR, or faults if any of them is enabled to trap.
+    fn float_result(&mut self, pc: u32, dest: usize, value: u32, flags: u32) {
+        if self.accrue(pc, flags) {
+            self.core.registers[dest] = value;
+        }
+    }
+
+    /// Like [`Cpu::float_result`], for a double in the pair at `dest`.
+    fn double_result(&mut self, pc: u32, dest: usize, value: f64, flags: u32) {
+        if self.accrue(pc, flags) {
+            self.core.registers[dest] = value.to_bits() as u32;
+            self.core.registers[dest + 1] = (value.to_bits() >> 32) as u32;
+        }
+    }
+
+    /// Accrues `flags` in FCSR, or faults and returns false if any of them is
+    /// enabled to trap.
+    fn accrue(&mut self, pc: u32, flags: u32) -> bool {
+        if flags & (self.core.fcsr >> float::FCSR_TRAP_SHIFT) & float::FLAGS != 0 {
+            self.raise(Fault::FloatingPoint { pc, flags });
+            return false;
+        }
+        self.core.fcsr |= flags;
+        true
+    }
+
+    /// The double in the pair at `low`.
+    fn double(&self, low: usize) -> f64 {
+        f64::from_bits(self.core.registers[low] as u64 | (self.core.registers[low + 1] as u64) << 32)
+    }
+
+    fn cycle(&mut self) {
+        if self.core.interrupts.is_deliverable() && !self.enter_interrupt() {
+            return;
+        }
+        let pc = self.core.registers[RPC as usize];
+        let inst = self.fetch(pc);
+        self.core.registers[RPC as usize] = pc.wrapping_add(6);
+
+        self.execute(pc, inst);
+    }
+
+    /// Delivers a pending IPI: pushes RPC on the call stack and jumps to the
+    /// handler. Returns false if the push faulted.
+    fn enter_interrupt(&mut self) -> bool {
+        let pc = self.core.registers[RPC as usize];
+        if !self.check_push(Stack::Call, pc, 4) {
+            return false;
+        }
+        let ret_addr = pc.to_le_bytes();
+        let sp = self.core.registers[CSP as usize];
+        for i in 0..4 {
+            self.write_u8(sp.wrapping_sub(i), ret_addr[3 - i as usize]);
+        }
+        self.core.registers[CSP as usize] = sp.wrapping_sub(4);
+        self.core.registers[RPC as usize] = self.core.interrupts.vector;
+        self.core.interrupts.in_handler = true;
+        true
+    }
+
+    /// Runs until the core stops or `budget` instructions have executed,
+    /// returning the number of instructions executed.
+    fn run_for(&mut self, budget: u64) -> u64 {
+        let mut executed = 0;
+        while self.core.is_running && executed < budget {
+            executed += match self.engine {
+                Engine::Interpreter => {
+                    self.cycle();
+                    1
+                },
+                Engine::Threaded => self.step_block(budget - executed),
+            };
+        }
+        executed
+    }
+
+    fn fetch(&self, pc: u32) -> u64 {
+        fetch(self.mem, pc)
+    }
+
+    /// Stores a byte, invalidating any translated block that covers `addr`.
+    /// Stores to unmapped addresses and to a loaded boot ROM are dropped.
+    fn write_u8(&mut self, addr: u32, value: u8) {
+        if !is_writable(self.config, self.rom, addr) {
+            return;
+        }
+        if let Some(log) = self.write_log {
+            log.push((addr, self.mem[addr as usize]));
+        }
+        if let Some(log) = self.store_log {
+            log.push(addr);
+        }
+        if self.core.reservation.is_some_and(|reserved| addr.wrapping_sub(reserved) < 4) {
+            self.core.reservation = None;
+        }
+        self.mem[addr as usize] = value;
+        let page = addr as usize >> PAGE_SHIFT;
+        self.written_pages[page / 64] |= 1 << (page % 64);
+        self.translations.note_write(addr);
+    }
+
+    /// Reads the little-endian word at `addr`, wrapping at the top of memory.
+    fn read_word(&self, addr: u32) -> u32 {
+        u32::from_le_bytes([
+            self.mem[addr as usize],
+            self.mem[addr.wrapping_add(1) as usize],
+            self.mem[addr.wrapping_add(2) as usize],
+            self.mem[addr.wrapping_add(3) as usize],
+        ])
+    }
+
+    /// Stores the little-endian word `value` at `addr` through [`Self::write_u8`].
+    fn write_word(&mut self, addr: u32, value: u32) {
+        for (i, byte) in value.to_le_bytes().into_iter().enumerate() {
+            self.write_u8(addr.wrapping_add(i as u32), byte);
+        }
+    }
+
+    /// Executes an already fetched instruction. `RPC` must already point past it.
+    fn execute(&mut self, pc: u32, inst: u64) {
+        self.core.registers[0] = 0u32;
+        self.core.fault = None;
 
         let opcode = (inst & 0xFF) as u8;
         match opcode {
             isa::NOP => (),
-            isa::HLT => self.is_running = false,
+            isa::HLT => self.core.is_running = false,
             isa::LDI => {
                 let dest = ((inst >> 8) & 0xF) as usize;
 
                 let imm = ((inst >> 12) & 0xFFFFFFFF) as u32;
-                self.registers[dest] = imm;
+                self.core.registers[dest] = imm;
             },
             isa::ADD => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = self.registers[src1].wrapping_add(self.registers[src2]);
+                self.core.registers[dest] = self.core.registers[src1].wrapping_add(self.core.registers[src2]);
             },
             isa::SUB => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = self.registers[src1].wrapping_sub(self.registers[src2]);
+                self.core.registers[dest] = self.core.registers[src1].wrapping_sub(self.core.registers[src2]);
             },
             isa::BOR => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = self.registers[src1] | self.registers[src2];
+                self.core.registers[dest] = self.core.registers[src1] | src2;
             },
             isa::BAND => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = self.registers[src1] & self.registers[src2];
+                self.core.registers[dest] = self.core.registers[src1] & src2;
             },
             isa::BXOR => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = self.registers[src1] ^ self.registers[src2];
+                self.core.registers[dest] = self.core.registers[src1] ^ src2;
             },
             isa::BNOT => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src = ((inst >> 12) & 0xF) as usize;
 
-                self.registers[dest] = !self.registers[src];
+                self.core.registers[dest] = !self.core.registers[src];
             },
             isa::LOR => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = (self.registers[src1] != 0 || self.registers[src2] != 0) as u32;
+                self.core.registers[dest] = (self.core.registers[src1] != 0 || self.core.registers[src2] != 0) as u32;
             },
             isa::LAND => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = (self.registers[src1] != 0 && self.registers[src2] != 0) as u32;
+                self.core.registers[dest] = (self.core.registers[src1] != 0 && self.core.registers[src2] != 0) as u32;
             },
             isa::LXOR => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = ((self.registers[src1] != 0) != (self.registers[src2] != 0)) as u32;
+                self.core.registers[dest] = ((self.core.registers[src1] != 0) != (self.core.registers[src2] != 0)) as u32;
             },
             isa::LNOT => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src = ((inst >> 12) & 0xF) as usize;
 
-                self.registers[dest] = (self.registers[src] == 0) as u32;
+                self.core.registers[dest] = (self.core.registers[src] == 0) as u32;
             },
             isa::SB => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src = ((inst >> 12) & 0xF) as usize;
+                let offset = (inst >> 16) as u32;
 
-                self.mem[self.registers[dest] as usize]
-                    = (self.registers[src] & 0xFF) as u8;
+                self.write_u8(self.core.registers[dest].wrapping_add(offset), (self.core.registers[src] & 0xFF) as u8);
             },
             isa::SW => {
-                let dest = self.registers[((inst >> 8) & 0xF) as usize];
+                let dest = self.core.registers[((inst >> 8) & 0xF) as usize].wrapping_add((inst >> 16) as u32);
                 let src = ((inst >> 12) & 0xF) as usize;
-                let bytes = self.registers[src].to_le_bytes();
+                let bytes = self.core.registers[src].to_le_bytes();
                 
                 for i in 0..4 {
-                    self.mem[dest.wrapping_add(i) as usize] = bytes[i as usize];
+                    self.write_u8(dest.wrapping_add(i), bytes[i as usize]);
                 }
             },
             isa::LBS => {
                 let dest = ((inst >> 8) & 0xF) as usize;
-                let src = ((inst >> 12) & 0xF) as usize;
+                let src = self.core.registers[((inst >> 12) & 0xF) as usize].wrapping_add((inst >> 16) as u32);
 
-                self.registers[dest] = self.mem[src] as i8 as i32 as u32;
+                self.core.registers[dest] = self.mem[src as usize] as i8 as i32 as u32;
             },
             isa::LBU => {
                 let dest = ((inst >> 8) & 0xF) as usize;
-                let src = ((inst >> 12) & 0xF) as usize;
+                let src = self.core.registers[((inst >> 12) & 0xF) as usize].wrapping_add((inst >> 16) as u32);
 
-                self.registers[dest] = self.mem[src] as u32;
+                self.core.registers[dest] = self.mem[src as usize] as u32;
             },
             isa::LW => {
                 let dest = ((inst >> 8) & 0xF) as usize;
-                let src = self.registers[((inst >> 12) & 0xF) as usize];
+                let src = self.core.registers[((inst >> 12) & 0xF) as usize].wrapping_add((inst >> 16) as u32);
 
                 let bytes = [
                     self.mem[src as usize],
@@ -185,21 +737,21 @@ impl Helios32 {
                     self.mem[src.wrapping_add(3) as usize],
                 ];
 
-                self.registers[dest] = u32::from_le_bytes(bytes);
+                self.core.registers[dest] = u32::from_le_bytes(bytes);
             },
             isa::JMR => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let is_relative = ((inst >> 12) & 0x1) as u8;
 
                 if is_relative != 0 {
-                    let jmp = self.registers[dest] as i32;
+                    let jmp = self.core.registers[dest] as i32;
                     if jmp.is_negative() {
-                        self.registers[RPC as usize] = pc.saturating_sub(jmp.abs() as u32);
+                        self.core.registers[RPC as usize] = pc.saturating_sub(jmp.unsigned_abs());
                     } else {
-                        self.registers[RPC as usize] = pc.saturating_add(jmp as u32);
+                        self.core.registers[RPC as usize] = pc.saturating_add(jmp as u32);
                     }
                 } else {
-                    self.registers[RPC as usize] = self.registers[dest];
+                    self.core.registers[RPC as usize] = self.core.registers[dest];
                 }
             },
             isa::JRI => {
@@ -207,16 +759,16 @@ impl Helios32 {
                 let src = ((inst >> 12) & 0xF) as usize;
                 let is_relative = ((inst >> 16) & 0x1) as u8;
 
-                if self.registers[src] != 0 {
+                if self.core.registers[src] != 0 {
                     if is_relative != 0 {
-                        let jmp = self.registers[dest] as i32;
+                        let jmp = self.core.registers[dest] as i32;
                         if jmp.is_negative() {
-                            self.registers[RPC as usize] = pc.saturating_sub(jmp.abs() as u32);
+                            self.core.registers[RPC as usize] = pc.saturating_sub(jmp.unsigned_abs());
                         } else {
-                            self.registers[RPC as usize] = pc.saturating_add(jmp as u32);
+                            self.core.registers[RPC as usize] = pc.saturating_add(jmp as u32);
                         }
                     } else {
-                        self.registers[RPC as usize] = self.registers[dest];
+                        self.core.registers[RPC as usize] = self.core.registers[dest];
                     }
                 }
             },
@@ -224,21 +776,24 @@ impl Helios32 {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let is_relative = ((inst >> 12) & 0x1) as u8;
 
+                if !self.check_push(Stack::Call, pc, 4) {
+                    return;
+                }
                 let ret_addr = pc.wrapping_add(6).to_le_bytes();
-                let sp = self.registers[CSP as usize];
+                let sp = self.core.registers[CSP as usize];
                 for i in 0..4 {
-                    self.mem[sp.wrapping_sub(i) as usize] = ret_addr[3 - i as usize];
+                    self.write_u8(sp.wrapping_sub(i), ret_addr[3 - i as usize]);
                 }
-                self.registers[CSP as usize] = sp.wrapping_sub(4);
+                self.core.registers[CSP as usize] = sp.wrapping_sub(4);
                 if is_relative != 0 {
-                    let jmp = self.registers[dest] as i32;
+                    let jmp = self.core.registers[dest] as i32;
                     if jmp.is_negative() {
-                        self.registers[RPC as usize] = pc.saturating_sub(jmp.abs() as u32);
+                        self.core.registers[RPC as usize] = pc.saturating_sub(jmp.unsigned_abs());
                     } else {
-                        self.registers[RPC as usize] = pc.saturating_add(jmp as u32);
+                        self.core.registers[RPC as usize] = pc.saturating_add(jmp as u32);
                     }
                 } else {
-                    self.registers[RPC as usize] = self.registers[dest];
+                    self.core.registers[RPC as usize] = self.core.registers[dest];
                 }
             },
             isa::CRI => {
@@ -246,22 +801,25 @@ impl Helios32 {
                 let src = ((inst >> 12) & 0xF) as usize;
                 let is_relative = ((inst >> 16) & 0x1) as u8;
 
-                if self.registers[src] != 0 {
+                if self.core.registers[src] != 0 {
+                    if !self.check_push(Stack::Call, pc, 4) {
+                        return;
+                    }
                     let ret_addr = pc.wrapping_add(6).to_le_bytes();
-                    let sp = self.registers[CSP as usize];
+                    let sp = self.core.registers[CSP as usize];
                     for i in 0..4 {
-                        self.mem[sp.wrapping_sub(i) as usize] = ret_addr[3 - i as usize];
+                        self.write_u8(sp.wrapping_sub(i), ret_addr[3 - i as usize]);
                     }
-                    self.registers[CSP as usize] = sp.wrapping_sub(4);
+                    self.core.registers[CSP as usize] = sp.wrapping_sub(4);
                     if is_relative != 0 {
-                        let jmp = self.registers[dest] as i32;
+                        let jmp = self.core.registers[dest] as i32;
                         if jmp.is_negative() {
-                            self.registers[RPC as usize] = pc.saturating_sub(jmp.abs() as u32);
+                            self.core.registers[RPC as usize] = pc.saturating_sub(jmp.unsigned_abs());
                         } else {
-                            self.registers[RPC as usize] = pc.saturating_add(jmp as u32);
+                            self.core.registers[RPC as usize] = pc.saturating_add(jmp as u32);
                         }
                     } else {
-                        self.registers[RPC as usize] = self.registers[dest];
+                        self.core.registers[RPC as usize] = self.core.registers[dest];
                     }
                 }
             },
@@ -272,12 +830,12 @@ impl Helios32 {
                 if is_relative != 0 {
                     let jmp = dest as i32;
                     if jmp.is_negative() {
-                        self.registers[RPC as usize] = pc.saturating_sub(jmp.abs() as u32);
+                        self.core.registers[RPC as usize] = pc.saturating_sub(jmp.unsigned_abs());
                     } else {
-                        self.registers[RPC as usize] = pc.saturating_add(jmp as u32);
+                        self.core.registers[RPC as usize] = pc.saturating_add(jmp as u32);
                     }
                 } else {
-                    self.registers[RPC as usize] = dest;
+                    self.core.registers[RPC as usize] = dest;
                 }
             },
             isa::JII => {
@@ -285,16 +843,16 @@ impl Helios32 {
                 let src = ((inst >> 40) & 0xF) as usize;
                 let is_relative = ((inst >> 44) & 0x1) as u8;
 
-                if self.registers[src] != 0 {
+                if self.core.registers[src] != 0 {
                     if is_relative != 0 {
                         let jmp = dest as i32;
                         if jmp.is_negative() {
-                            self.registers[RPC as usize] = pc.saturating_sub(jmp.abs() as u32);
+                            self.core.registers[RPC as usize] = pc.saturating_sub(jmp.unsigned_abs());
                         } else {
-                            self.registers[RPC as usize] = pc.saturating_add(jmp as u32);
+                            self.core.registers[RPC as usize] = pc.saturating_add(jmp as u32);
                         }
                     } else {
-                        self.registers[RPC as usize] = dest;
+                        self.core.registers[RPC as usize] = dest;
                     }
                 }
             },
@@ -302,21 +860,24 @@ impl Helios32 {
                 let dest = ((inst >> 8) & 0xFFFFFFFF) as u32;
                 let is_relative = ((inst >> 40) & 0x1) as u8;
 
+                if !self.check_push(Stack::Call, pc, 4) {
+                    return;
+                }
                 let ret_addr = pc.wrapping_add(6).to_le_bytes();
-                let sp = self.registers[CSP as usize];
+                let sp = self.core.registers[CSP as usize];
                 for i in 0..4 {
-                    self.mem[sp.wrapping_sub(i) as usize] = ret_addr[3 - i as usize];
+                    self.write_u8(sp.wrapping_sub(i), ret_addr[3 - i as usize]);
                 }
-                self.registers[CSP as usize] = sp.wrapping_sub(4);
+                self.core.registers[CSP as usize] = sp.wrapping_sub(4);
                 if is_relative != 0 {
                     let jmp = dest as i32;
                     if jmp.is_negative() {
-                        self.registers[RPC as usize] = pc.saturating_sub(jmp.abs() as u32);
+                        self.core.registers[RPC as usize] = pc.saturating_sub(jmp.unsigned_abs());
                     } else {
-                        self.registers[RPC as usize] = pc.saturating_add(jmp as u32);
+                        self.core.registers[RPC as usize] = pc.saturating_add(jmp as u32);
                     }
                 } else {
-                    self.registers[RPC as usize] = dest;
+                    self.core.registers[RPC as usize] = dest;
                 }
             },
             isa::CII => {
@@ -324,176 +885,196 @@ impl Helios32 {
                 let src = ((inst >> 40) & 0xF) as usize;
                 let is_relative = ((inst >> 44) & 0x1) as u8;
 
-                if self.registers[src] != 0 {
+                if self.core.registers[src] != 0 {
+                    if !self.check_push(Stack::Call, pc, 4) {
+                        return;
+                    }
                     let ret_addr = pc.wrapping_add(6).to_le_bytes();
-                    let sp = self.registers[CSP as usize];
+                    let sp = self.core.registers[CSP as usize];
                     for i in 0..4 {
-                        self.mem[sp.wrapping_sub(i) as usize] = ret_addr[3 - i as usize];
+                        self.write_u8(sp.wrapping_sub(i), ret_addr[3 - i as usize]);
                     }
-                    self.registers[CSP as usize] = sp.wrapping_sub(4);
+                    self.core.registers[CSP as usize] = sp.wrapping_sub(4);
                     if is_relative != 0 {
                         let jmp = dest as i32;
                         if jmp.is_negative() {
-                            self.registers[RPC as usize] = pc.saturating_sub(jmp.abs() as u32);
+                            self.core.registers[RPC as usize] = pc.saturating_sub(jmp.unsigned_abs());
                         } else {
-                            self.registers[RPC as usize] = pc.saturating_add(jmp as u32);
+                            self.core.registers[RPC as usize] = pc.saturating_add(jmp as u32);
                         }
                     } else {
-                        self.registers[RPC as usize] = dest;
+                        self.core.registers[RPC as usize] = dest;
                     }
                 }
             },
             isa::RET => {
+                if !self.check_pop(Stack::Call, pc, 4) {
+                    return;
+                }
                 let mut bytes = [0; 4];
-                let sp = self.registers[CSP as usize];
+                let sp = self.core.registers[CSP as usize];
                 for i in 1..5 {
                     bytes[i - 1] = self.mem[sp.wrapping_add(i as u32) as usize];
                 }
-                self.registers[CSP as usize] = sp.wrapping_add(4);
+                self.core.registers[CSP as usize] = sp.wrapping_add(4);
 
-                self.registers[RPC as usize] = u32::from_le_bytes(bytes);
+                self.core.registers[RPC as usize] = u32::from_le_bytes(bytes);
             },
             isa::EQ => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = (self.registers[src1] == self.registers[src2]) as u32;
+                self.core.registers[dest] = (self.core.registers[src1] == src2) as u32;
             },
             isa::NE => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = (self.registers[src1] != self.registers[src2]) as u32;
+                self.core.registers[dest] = (self.core.registers[src1] != src2) as u32;
             },
             isa::GT => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = (self.registers[src1] > self.registers[src2]) as u32;
+                self.core.registers[dest] = (self.core.registers[src1] > src2) as u32;
             },
             isa::LT => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = (self.registers[src1] < self.registers[src2]) as u32;
+                self.core.registers[dest] = (self.core.registers[src1] < src2) as u32;
             },
             isa::GE => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = (self.registers[src1] >= self.registers[src2]) as u32;
+                self.core.registers[dest] = (self.core.registers[src1] >= src2) as u32;
             },
             isa::LE => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = (self.registers[src1] <= self.registers[src2]) as u32;
+                self.core.registers[dest] = (self.core.registers[src1] <= src2) as u32;
             },
             isa::INC => {
                 let dest = ((inst >> 8) & 0xF) as usize;
 
-                self.registers[dest] = self.registers[dest].wrapping_add(1);
+                self.core.registers[dest] = self.core.registers[dest].wrapping_add(1);
             },
             isa::DEC => {
                 let dest = ((inst >> 8) & 0xF) as usize;
 
-                self.registers[dest] = self.registers[dest].wrapping_sub(1);
+                self.core.registers[dest] = self.core.registers[dest].wrapping_sub(1);
             },
             isa::ADDI => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xFFFFFFFF) as u32;
 
-                self.registers[dest] = self.registers[src1].wrapping_add(src2);
+                self.core.registers[dest] = self.core.registers[src1].wrapping_add(src2);
             },
             isa::SUBI => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xFFFFFFFF) as u32;
 
-                self.registers[dest] = self.registers[src1].wrapping_sub(src2);
+                self.core.registers[dest] = self.core.registers[src1].wrapping_sub(src2);
             },
             isa::SHL => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = self.registers[src1].wrapping_shl(self.registers[src2]);
+                self.core.registers[dest] = self.core.registers[src1].wrapping_shl(src2);
             },
             isa::LSHR => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = self.registers[src1].wrapping_shr(self.registers[src2]);
+                self.core.registers[dest] = self.core.registers[src1].wrapping_shr(src2);
             },
             isa::ASHR => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = (self.registers[src1] as i32).wrapping_shr(self.registers[src2]) as u32;
+                self.core.registers[dest] = (self.core.registers[src1] as i32).wrapping_shr(src2) as u32;
             },
             isa::ROTL => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = self.registers[src1].rotate_left(self.registers[src2]);
+                self.core.registers[dest] = self.core.registers[src1].rotate_left(src2);
             },
             isa::ROTR => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = self.registers[src1].rotate_right(self.registers[src2]);
+                self.core.registers[dest] = self.core.registers[src1].rotate_right(src2);
             },
             isa::PB => {
+                if !self.check_push(Stack::Data, pc, 1) {
+                    return;
+                }
                 let src = ((inst >> 8) & 0xF) as usize;
 
-                self.mem[self.registers[RSP as usize] as usize]
-                    = (self.registers[src] & 0xFF) as u8;
+                self.write_u8(self.core.registers[RSP as usize], (self.core.registers[src] & 0xFF) as u8);
 
-                self.registers[RSP as usize] = self.registers[RSP as usize].wrapping_sub(1);
+                self.core.registers[RSP as usize] = self.core.registers[RSP as usize].wrapping_sub(1);
             },
             isa::PW => {
-                let sp = self.registers[RSP as usize];
+                if !self.check_push(Stack::Data, pc, 4) {
+                    return;
+                }
+                let sp = self.core.registers[RSP as usize];
                 let src = ((inst >> 8) & 0xF) as usize;
-                let bytes = self.registers[src].to_le_bytes();
+                let bytes = self.core.registers[src].to_le_bytes();
                 
                 for i in 0..4 {
-                    self.mem[sp.wrapping_sub(i) as usize] = bytes[3 - i as usize];
+                    self.write_u8(sp.wrapping_sub(i), bytes[3 - i as usize]);
                 }
 
-                self.registers[RSP as usize] = self.registers[RSP as usize].wrapping_sub(4);
+                self.core.registers[RSP as usize] = self.core.registers[RSP as usize].wrapping_sub(4);
             },
             isa::POBS => {
-                self.registers[RSP as usize] = self.registers[RSP as usize].wrapping_add(1);
+                if !self.check_pop(Stack::Data, pc, 1) {
+                    return;
+                }
+                self.core.registers[RSP as usize] = self.core.registers[RSP as usize].wrapping_add(1);
                 
-                let sp = self.registers[RSP as usize];
+                let sp = self.core.registers[RSP as usize];
                 let dest = ((inst >> 8) & 0xF) as usize;
 
-                self.registers[dest] = self.mem[sp as usize] as i8 as i32 as u32;
+                self.core.registers[dest] = self.mem[sp as usize] as i8 as i32 as u32;
             },
             isa::POBU => {
-                self.registers[RSP as usize] = self.registers[RSP as usize].wrapping_add(1);
+                if !self.check_pop(Stack::Data, pc, 1) {
+                    return;
+                }
+                self.core.registers[RSP as usize] = self.core.registers[RSP as usize].wrapping_add(1);
 
-                let sp = self.registers[RSP as usize];
+                let sp = self.core.registers[RSP as usize];
                 let dest = ((inst >> 8) & 0xF) as usize;
 
-                self.registers[dest] = self.mem[sp as usize] as u32;
+                self.core.registers[dest] = self.mem[sp as usize] as u32;
             },
             isa::POW => {
-                self.registers[RSP as usize] = self.registers[RSP as usize].wrapping_add(4);
+                if !self.check_pop(Stack::Data, pc, 4) {
+                    return;
+                }
+                self.core.registers[RSP as usize] = self.core.registers[RSP as usize].wrapping_add(4);
 
                 let dest = ((inst >> 8) & 0xF) as usize;
-                let sp = self.registers[RSP as usize];
+                let sp = self.core.registers[RSP as usize];
 
                 let bytes = [
                     self.mem[sp.wrapping_sub(3) as usize],
@@ -502,77 +1083,582 @@ impl Helios32 {
                     self.mem[sp as usize],
                 ];
 
-                self.registers[dest] = u32::from_le_bytes(bytes);
+                self.core.registers[dest] = u32::from_le_bytes(bytes);
             },
             isa::MUL => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = ((self.registers[src1] as i64 * self.registers[src2] as i64) & 0xFFFFFFFF) as u32;
+                self.core.registers[dest] = self.core.registers[src1].wrapping_mul(src2);
             },
             isa::DIV => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = self.registers[src1].checked_div(self.registers[src2]).unwrap_or(0);
+                self.core.registers[dest] = self.core.registers[src1].checked_div(src2).unwrap_or(0);
             },
             isa::REM => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
-                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src2 = self.operand(inst);
 
-                self.registers[dest] = self.registers[src1].checked_rem(self.registers[src2]).unwrap_or(0);
+                self.core.registers[dest] = self.core.registers[src1].checked_rem(src2).unwrap_or(0);
             },
             isa::MUHS => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = ((self.registers[src1] as i64 * self.registers[src2] as i64) >> 32) as i32 as u32;
+                self.core.registers[dest] = ((self.core.registers[src1] as i32 as i64 * self.core.registers[src2] as i32 as i64) >> 32) as u32;
             },
             isa::MUHU => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = ((self.registers[src1] as i64 * self.registers[src2] as i64) >> 32) as u32;
+                self.core.registers[dest] = ((self.core.registers[src1] as u64 * self.core.registers[src2] as u64) >> 32) as u32;
             },
             isa::FADD => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = (f32::from_bits(self.registers[src1]) + f32::from_bits(self.registers[src2])).to_bits();
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                let (value, flags) = float::add(a, b, self.rounding_mode());
+                self.float_result(pc, dest, value.to_bits(), flags);
             },
             isa::FSUB => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = (f32::from_bits(self.registers[src1]) - f32::from_bits(self.registers[src2])).to_bits();
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                let (value, flags) = float::sub(a, b, self.rounding_mode());
+                self.float_result(pc, dest, value.to_bits(), flags);
             },
             isa::FMUL => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = (f32::from_bits(self.registers[src1]) + f32::from_bits(self.registers[src2])).to_bits();
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                let (value, flags) = float::mul(a, b, self.rounding_mode());
+                self.float_result(pc, dest, value.to_bits(), flags);
             },
             isa::FDIV => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = (f32::from_bits(self.registers[src1]) / f32::from_bits(self.registers[src2])).to_bits();
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                let (value, flags) = float::div(a, b, self.rounding_mode());
+                self.float_result(pc, dest, value.to_bits(), flags);
             },
             isa::FREM => {
                 let dest = ((inst >> 8) & 0xF) as usize;
                 let src1 = ((inst >> 12) & 0xF) as usize;
                 let src2 = ((inst >> 16) & 0xF) as usize;
 
-                self.registers[dest] = (f32::from_bits(self.registers[src1]) % f32::from_bits(self.registers[src2])).to_bits();
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                let (value, flags) = float::rem(a, b);
+                self.float_result(pc, dest, value.to_bits(), flags);
+            },
+            isa::SGT => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = self.operand(inst);
+
+                self.core.registers[dest] = ((self.core.registers[src1] as i32) > (src2 as i32)) as u32;
+            },
+            isa::SLT => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = self.operand(inst);
+
+                self.core.registers[dest] = ((self.core.registers[src1] as i32) < (src2 as i32)) as u32;
+            },
+            isa::SGE => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = self.operand(inst);
+
+                self.core.registers[dest] = ((self.core.registers[src1] as i32) >= (src2 as i32)) as u32;
+            },
+            isa::SLE => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = self.operand(inst);
+
+                self.core.registers[dest] = ((self.core.registers[src1] as i32) <= (src2 as i32)) as u32;
+            },
+            isa::SDIV => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = self.operand(inst);
+
+                let (dividend, divisor) = (self.core.registers[src1] as i32, src2 as i32);
+                self.core.registers[dest] = if divisor == 0 { 0 } else { dividend.wrapping_div(divisor) as u32 };
+            },
+            isa::SREM => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = self.operand(inst);
+
+                let (dividend, divisor) = (self.core.registers[src1] as i32, src2 as i32);
+                self.core.registers[dest] = if divisor == 0 { 0 } else { dividend.wrapping_rem(divisor) as u32 };
+            },
+            isa::FEQ => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = ((inst >> 16) & 0xF) as usize;
+
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                self.float_result(pc, dest, (a == b) as u32, float::compare_flags(a, b, false));
+            },
+            isa::FLT => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = ((inst >> 16) & 0xF) as usize;
+
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                self.float_result(pc, dest, (a < b) as u32, float::compare_flags(a, b, true));
+            },
+            isa::FLE => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = ((inst >> 16) & 0xF) as usize;
+
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                self.float_result(pc, dest, (a <= b) as u32, float::compare_flags(a, b, true));
+            },
+            isa::FMIN => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = ((inst >> 16) & 0xF) as usize;
+
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                self.float_result(pc, dest, float::min(a, b).to_bits(), float::compare_flags(a, b, false));
+            },
+            isa::FMAX => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = ((inst >> 16) & 0xF) as usize;
+
+                let (a, b) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]));
+                self.float_result(pc, dest, float::max(a, b).to_bits(), float::compare_flags(a, b, false));
+            },
+            isa::FSQRT => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                let (value, flags) = float::sqrt(f32::from_bits(self.core.registers[src]), self.rounding_mode());
+                self.float_result(pc, dest, value.to_bits(), flags);
+            },
+            isa::FABS => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src] & 0x7FFF_FFFF;
+            },
+            isa::FNEG => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src] ^ 0x8000_0000;
+            },
+            isa::FMA => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src1 = ((inst >> 12) & 0xF) as usize;
+                let src2 = ((inst >> 16) & 0xF) as usize;
+                let src3 = ((inst >> 20) & 0xF) as usize;
+
+                let (a, b, c) = (f32::from_bits(self.core.registers[src1]), f32::from_bits(self.core.registers[src2]), f32::from_bits(self.core.registers[src3]));
+                let (value, flags) = float::fma(a, b, c, self.rounding_mode());
+                self.float_result(pc, dest, value.to_bits(), flags);
+            },
+            isa::ITOF => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+                let mode = self.conversion_rounding(inst);
+
+                let (value, flags) = float::from_int(self.core.registers[src] as i32 as f64, mode);
+                self.float_result(pc, dest, value.to_bits(), flags);
+            },
+            isa::UTOF => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+                let mode = self.conversion_rounding(inst);
+
+                let (value, flags) = float::from_int(self.core.registers[src] as f64, mode);
+                self.float_result(pc, dest, value.to_bits(), flags);
+            },
+            isa::FTOI => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+                let mode = self.conversion_rounding(inst);
+
+                let (value, flags) = float::to_i32(f32::from_bits(self.core.registers[src]) as f64, mode);
+                self.float_result(pc, dest, value, flags);
+            },
+            isa::FTOU => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+                let mode = self.conversion_rounding(inst);
+
+                let (value, flags) = float::to_u32(f32::from_bits(self.core.registers[src]) as f64, mode);
+                self.float_result(pc, dest, value, flags);
+            },
+            isa::FRCSR => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.fcsr;
+            },
+            isa::FWCSR => {
+                let src = ((inst >> 8) & 0xF) as usize;
+
+                self.core.fcsr = self.core.registers[src] & float::FCSR_MASK;
+            },
+            isa::DADD => {
+                let Some([dest, src1, src2]) = pairs(inst, [8, 12, 16]) else { return };
+
+                let (a, b) = (self.double(src1), self.double(src2));
+                let (value, flags) = float::add_f64(a, b, self.rounding_mode());
+                self.double_result(pc, dest, value, flags);
+            },
+            isa::DSUB => {
+                let Some([dest, src1, src2]) = pairs(inst, [8, 12, 16]) else { return };
+
+                let (a, b) = (self.double(src1), self.double(src2));
+                let (value, flags) = float::sub_f64(a, b, self.rounding_mode());
+                self.double_result(pc, dest, value, flags);
+            },
+            isa::DMUL => {
+                let Some([dest, src1, src2]) = pairs(inst, [8, 12, 16]) else { return };
+
+                let (a, b) = (self.double(src1), self.double(src2));
+                let (value, flags) = float::mul_f64(a, b, self.rounding_mode());
+                self.double_result(pc, dest, value, flags);
+            },
+            isa::DDIV => {
+                let Some([dest, src1, src2]) = pairs(inst, [8, 12, 16]) else { return };
+
+                let (a, b) = (self.double(src1), self.double(src2));
+                let (value, flags) = float::div_f64(a, b, self.rounding_mode());
+                self.double_result(pc, dest, value, flags);
+            },
+            isa::DSQRT => {
+                let Some([dest, src]) = pairs(inst, [8, 12]) else { return };
+
+                let (value, flags) = float::sqrt_f64(self.double(src), self.rounding_mode());
+                self.double_result(pc, dest, value, flags);
+            },
+            isa::DABS => {
+                let Some([dest, src]) = pairs(inst, [8, 12]) else { return };
+
+                self.core.registers[dest] = self.core.registers[src];
+                self.core.registers[dest + 1] = self.core.registers[src + 1] & 0x7FFF_FFFF;
+            },
+            isa::DNEG => {
+                let Some([dest, src]) = pairs(inst, [8, 12]) else { return };
+
+                self.core.registers[dest] = self.core.registers[src];
+                self.core.registers[dest + 1] = self.core.registers[src + 1] ^ 0x8000_0000;
+            },
+            isa::DEQ => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let Some([src1, src2]) = pairs(inst, [12, 16]) else { return };
+
+                let (a, b) = (self.double(src1), self.double(src2));
+                self.float_result(pc, dest, (a == b) as u32, float::compare_flags_f64(a, b, false));
+            },
+            isa::DLT => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let Some([src1, src2]) = pairs(inst, [12, 16]) else { return };
+
+                let (a, b) = (self.double(src1), self.double(src2));
+                self.float_result(pc, dest, (a < b) as u32, float::compare_flags_f64(a, b, true));
+            },
+            isa::DLE => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let Some([src1, src2]) = pairs(inst, [12, 16]) else { return };
+
+                let (a, b) = (self.double(src1), self.double(src2));
+                self.float_result(pc, dest, (a <= b) as u32, float::compare_flags_f64(a, b, true));
+            },
+            isa::FTOD => {
+                let Some([dest]) = pairs(inst, [8]) else { return };
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                let (value, flags) = float::to_f64(f32::from_bits(self.core.registers[src]));
+                self.double_result(pc, dest, value, flags);
+            },
+            isa::DTOF => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let Some([src]) = pairs(inst, [12]) else { return };
+                let mode = self.conversion_rounding(inst);
+
+                let (value, flags) = float::from_f64(self.double(src), mode);
+                self.float_result(pc, dest, value.to_bits(), flags);
+            },
+            isa::ITOD => {
+                let Some([dest]) = pairs(inst, [8]) else { return };
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.double_result(pc, dest, self.core.registers[src] as i32 as f64, 0);
+            },
+            isa::UTOD => {
+                let Some([dest]) = pairs(inst, [8]) else { return };
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.double_result(pc, dest, self.core.registers[src] as f64, 0);
+            },
+            isa::DTOI => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let Some([src]) = pairs(inst, [12]) else { return };
+                let mode = self.conversion_rounding(inst);
+
+                let (value, flags) = float::to_i32(self.double(src), mode);
+                self.float_result(pc, dest, value, flags);
+            },
+            isa::DTOU => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let Some([src]) = pairs(inst, [12]) else { return };
+                let mode = self.conversion_rounding(inst);
+
+                let (value, flags) = float::to_u32(self.double(src), mode);
+                self.float_result(pc, dest, value, flags);
+            },
+            isa::SH => {
+                let dest = self.core.registers[((inst >> 8) & 0xF) as usize].wrapping_add((inst >> 16) as u32);
+                let src = ((inst >> 12) & 0xF) as usize;
+                let bytes = (self.core.registers[src] as u16).to_le_bytes();
+
+                self.write_u8(dest, bytes[0]);
+                self.write_u8(dest.wrapping_add(1), bytes[1]);
+            },
+            isa::LHS => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = self.core.registers[((inst >> 12) & 0xF) as usize].wrapping_add((inst >> 16) as u32);
+
+                let bytes = [self.mem[src as usize], self.mem[src.wrapping_add(1) as usize]];
+                self.core.registers[dest] = i16::from_le_bytes(bytes) as i32 as u32;
+            },
+            isa::LHU => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = self.core.registers[((inst >> 12) & 0xF) as usize].wrapping_add((inst >> 16) as u32);
+
+                let bytes = [self.mem[src as usize], self.mem[src.wrapping_add(1) as usize]];
+                self.core.registers[dest] = u16::from_le_bytes(bytes) as u32;
+            },
+            isa::SWAP => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let addr = self.core.registers[((inst >> 12) & 0xF) as usize];
+                let src = self.core.registers[((inst >> 16) & 0xF) as usize];
+
+                self.core.registers[dest] = self.read_word(addr);
+                self.write_word(addr, src);
+            },
+            isa::CAS => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let addr = self.core.registers[((inst >> 12) & 0xF) as usize];
+                let expected = self.core.registers[((inst >> 16) & 0xF) as usize];
+                let new = self.core.registers[((inst >> 20) & 0xF) as usize];
+
+                let old = self.read_word(addr);
+                if old == expected {
+                    self.write_word(addr, new);
+                }
+                self.core.registers[dest] = old;
+            },
+            isa::XADD => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let addr = self.core.registers[((inst >> 12) & 0xF) as usize];
+                let src = self.core.registers[((inst >> 16) & 0xF) as usize];
+
+                let old = self.read_word(addr);
+                self.write_word(addr, old.wrapping_add(src));
+                self.core.registers[dest] = old;
+            },
+            isa::LR => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let addr = self.core.registers[((inst >> 12) & 0xF) as usize];
+
+                self.core.registers[dest] = self.read_word(addr);
+                self.core.reservation = Some(addr);
+            },
+            isa::SC => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let addr = self.core.registers[((inst >> 12) & 0xF) as usize];
+                let src = self.core.registers[((inst >> 16) & 0xF) as usize];
+
+                let held = self.core.reservation.take() == Some(addr);
+                if held {
+                    self.write_word(addr, src);
+                }
+                self.core.registers[dest] = !held as u32;
+            },
+            isa::FENCE => {
+                // Accesses already complete in program order.
+            },
+            isa::CID => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                self.core.registers[dest] = self.core.id;
+            },
+            isa::NCORES => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                self.core.registers[dest] = self.core_count;
+            },
+            isa::IPI => {
+                let src = ((inst >> 8) & 0xF) as usize;
+                let target = self.core.registers[src];
+
+                if target == self.core.id {
+                    self.core.interrupts.pending |= 1 << target;
+                } else if target < self.core_count {
+                    *self.outbox |= 1 << target;
+                }
+            },
+            isa::SIV => {
+                let src = ((inst >> 8) & 0xF) as usize;
+                self.core.interrupts.vector = self.core.registers[src];
+            },
+            isa::IACK => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                self.core.registers[dest] = std::mem::take(&mut self.core.interrupts.pending);
+            },
+            isa::IRET => {
+                if !self.check_pop(Stack::Call, pc, 4) {
+                    return;
+                }
+                let mut bytes = [0; 4];
+                let sp = self.core.registers[CSP as usize];
+                for i in 1..5 {
+                    bytes[i - 1] = self.mem[sp.wrapping_add(i as u32) as usize];
+                }
+                self.core.registers[CSP as usize] = sp.wrapping_add(4);
+
+                self.core.registers[RPC as usize] = u32::from_le_bytes(bytes);
+                self.core.interrupts.in_handler = false;
+            },
+            isa::WFI if self.core.interrupts.pending == 0 => {
+                self.core.interrupts.waiting = true;
+                self.core.is_running = false;
+            },
+            isa::CLZ => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src].leading_zeros();
+            },
+            isa::CTZ => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src].trailing_zeros();
+            },
+            isa::POPC => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src].count_ones();
+            },
+            isa::BSWAP => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src].swap_bytes();
+            },
+            isa::BFX => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+                let Some((lsb, mask)) = bit_field(inst) else { return };
+
+                self.core.registers[dest] = (self.core.registers[src] >> lsb) & mask;
+            },
+            isa::BFI => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+                let Some((lsb, mask)) = bit_field(inst) else { return };
+
+                let field = mask << lsb;
+                let bits = (self.core.registers[src] << lsb) & field;
+                self.core.registers[dest] = (self.core.registers[dest] & !field) | bits;
+            },
+            isa::SEXTB => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src] as i8 as i32 as u32;
+            },
+            isa::SEXTH => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src] as i16 as i32 as u32;
+            },
+            isa::ZEXTB => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src] as u8 as u32;
+            },
+            isa::ZEXTH => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let src = ((inst >> 12) & 0xF) as usize;
+
+                self.core.registers[dest] = self.core.registers[src] as u16 as u32;
+            },
+            isa::ADC => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let carry = ((inst >> 12) & 0xF) as usize;
+                let src1 = self.core.registers[((inst >> 16) & 0xF) as usize];
+                let src2 = self.core.registers[((inst >> 20) & 0xF) as usize];
+
+                let (sum, out1) = src1.overflowing_add(src2);
+                let (sum, out2) = sum.overflowing_add(self.core.registers[carry] & 1);
+                self.core.registers[carry] = (out1 | out2) as u32;
+                self.core.registers[dest] = sum;
+            },
+            isa::SBB => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let borrow = ((inst >> 12) & 0xF) as usize;
+                let src1 = self.core.registers[((inst >> 16) & 0xF) as usize];
+                let src2 = self.core.registers[((inst >> 20) & 0xF) as usize];
+
+                let (diff, out1) = src1.overflowing_sub(src2);
+                let (diff, out2) = diff.overflowing_sub(self.core.registers[borrow] & 1);
+                self.core.registers[borrow] = (out1 | out2) as u32;
+                self.core.registers[dest] = diff;
+            },
+            isa::ADDO => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let overflow = ((inst >> 12) & 0xF) as usize;
+                let src1 = self.core.registers[((inst >> 16) & 0xF) as usize];
+                let src2 = self.core.registers[((inst >> 20) & 0xF) as usize];
+
+                let (sum, overflowed) = (src1 as i32).overflowing_add(src2 as i32);
+                self.core.registers[overflow] = overflowed as u32;
+                self.core.registers[dest] = sum as u32;
+            },
+            isa::SUBO => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let overflow = ((inst >> 12) & 0xF) as usize;
+                let src1 = self.core.registers[((inst >> 16) & 0xF) as usize];
+                let src2 = self.core.registers[((inst >> 20) & 0xF) as usize];
+
+                let (diff, overflowed) = (src1 as i32).overflowing_sub(src2 as i32);
+                self.core.registers[overflow] = overflowed as u32;
+                self.core.registers[dest] = diff as u32;
+            },
+            isa::SEL => {
+                let dest = ((inst >> 8) & 0xF) as usize;
+                let cond = self.core.registers[((inst >> 12) & 0xF) as usize];
+                let src1 = self.core.registers[((inst >> 16) & 0xF) as usize];
+                let src2 = self.core.registers[((inst >> 20) & 0xF) as usize];
+
+                self.core.registers[dest] = if cond != 0 { src1 } else { src2 };
             },
             _ => (),
         }
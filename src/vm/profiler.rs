@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use super::{Helios32, isa};
+use super::registers::*;
+
+/// Index into [`Profiler::symbols`], or `None` for code before the first label.
+type Symbol = Option<usize>;
+
+/// Instruction-level profile of a run.
+///
+/// Every instruction counts as one cycle. Cycles are attributed to the nearest
+/// label at or below the PC, and a shadow call stack is kept from taken calls
+/// (`CAR`/`CRI`/`CAI`/`CII`) and `RET` to build the call graph and folded stacks.
+pub struct Profiler {
+    symbols: Vec<(u32, String)>,
+    total: u64,
+    pc_counts: HashMap<u32, u64>,
+    opcode_counts: [u64; 256],
+    calls: HashMap<(Symbol, Symbol), u64>,
+    stacks: HashMap<Vec<Symbol>, u64>,
+    stack: Vec<Symbol>,
+}
+
+impl Profiler {
+    /// `symbols` are `(name, absolute address)` pairs, typically assembler labels.
+    pub fn new(symbols: Vec<(String, u32)>) -> Self {
+        let mut symbols = symbols.into_iter()
+            .map(|(name, addr)| (addr, name))
+            .collect::<Vec<_>>();
+        symbols.sort();
+
+        Self {
+            symbols,
+            total: 0,
+            pc_counts: HashMap::new(),
+            opcode_counts: [0; 256],
+            calls: HashMap::new(),
+            stacks: HashMap::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    fn symbol(&self, addr: u32) -> Symbol {
+        self.symbols.partition_point(|(start, _)| *start <= addr).checked_sub(1)
+    }
+
+    fn name(&self, symbol: Symbol) -> &str {
+        symbol.map_or("[unknown]", |i| &self.symbols[i].1)
+    }
+
+    /// Formats `addr` as `label+0xoffset`.
+    fn location(&self, addr: u32) -> String {
+        match self.symbol(addr) {
+            Some(i) if self.symbols[i].0 == addr => self.symbols[i].1.clone(),
+            Some(i) => format!("{}+0x{:X}", self.symbols[i].1, addr - self.symbols[i].0),
+            None => "[unknown]".to_string(),
+        }
+    }
+
+    /// Records one executed instruction, see [`Helios32::run_observed`].
+    pub fn observe(&mut self, vm: &Helios32, pc: u32, inst: u64, before: &[u32; 16]) {
+        let opcode = (inst & 0xFF) as u8;
+        let is_call = matches!(opcode, isa::CAR | isa::CRI | isa::CAI | isa::CII);
+        let call_target = (is_call && vm.core.registers[CSP as usize] == before[CSP as usize].wrapping_sub(4))
+            .then_some(vm.core.registers[RPC as usize]);
+
+        self.record(pc, opcode, call_target, opcode == isa::RET);
+    }
+
+    /// Records one executed instruction.
+    ///
+    /// `call_target` is the destination of a taken call and `returned` is set
+    /// for `RET`.
+    fn record(&mut self, pc: u32, opcode: u8, call_target: Option<u32>, returned: bool) {
+        self.total += 1;
+        *self.pc_counts.entry(pc).or_default() += 1;
+        self.opcode_counts[opcode as usize] += 1;
+
+        let current = self.symbol(pc);
+        if self.stack.is_empty() {
+            self.stack.push(current);
+        }
+        let pushed = self.stack.last() != Some(&current);
+        if pushed {
+            self.stack.push(current);
+        }
+        match self.stacks.get_mut(&self.stack[..]) {
+            Some(count) => *count += 1,
+            None => { self.stacks.insert(self.stack.clone(), 1); },
+        }
+        if pushed {
+            self.stack.pop();
+        }
+
+        if let Some(target) = call_target {
+            let callee = self.symbol(target);
+            *self.calls.entry((current, callee)).or_default() += 1;
+            self.stack.push(callee);
+        } else if returned && self.stack.len() > 1 {
+            self.stack.pop();
+        }
+    }
+
+    /// Human-readable report: hot spots, opcode mix, per-function cycles and call graph.
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        let percent = |count: u64| count as f64 * 100.0 / self.total.max(1) as f64;
+
+        writeln!(out, "total instructions: {}", self.total).unwrap();
+
+        writeln!(out, "\nhot spots:").unwrap();
+        writeln!(out, "{:>12} {:>7}  {:<10}  location", "count", "%", "address").unwrap();
+        let mut pcs = self.pc_counts.iter().collect::<Vec<_>>();
+        pcs.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (&pc, &count) in pcs.iter().take(20) {
+            writeln!(
+                out, "{count:>12} {:>6.2}%  0x{pc:08X}  {}",
+                percent(count), self.location(pc)
+            ).unwrap();
+        }
+
+        writeln!(out, "\nopcodes:").unwrap();
+        writeln!(out, "{:>12} {:>7}  mnemonic", "count", "%").unwrap();
+        let mut opcodes = (0..=255u8)
+            .filter(|&op| self.opcode_counts[op as usize] != 0)
+            .collect::<Vec<_>>();
+        opcodes.sort_by_key(|&op| std::cmp::Reverse(self.opcode_counts[op as usize]));
+        for op in opcodes {
+            let count = self.opcode_counts[op as usize];
+            let name = isa::mnemonic(op).map_or_else(|| format!("0x{op:02X}"), str::to_string);
+            writeln!(out, "{count:>12} {:>6.2}%  {name}", percent(count)).unwrap();
+        }
+
+        let mut self_counts = HashMap::<Symbol, u64>::new();
+        let mut inclusive = HashMap::<Symbol, u64>::new();
+        for (stack, &count) in &self.stacks {
+            *self_counts.entry(*stack.last().unwrap()).or_default() += count;
+            let mut seen = Vec::new();
+            for frame in stack {
+                if !seen.contains(frame) {
+                    seen.push(*frame);
+                    *inclusive.entry(*frame).or_default() += count;
+                }
+            }
+        }
+        writeln!(out, "\nfunctions:").unwrap();
+        writeln!(out, "{:>12} {:>7} {:>12} {:>7}  name", "self", "%", "inclusive", "%").unwrap();
+        let mut functions = inclusive.into_iter().collect::<Vec<_>>();
+        functions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (symbol, incl) in functions {
+            let own = self_counts.get(&symbol).copied().unwrap_or(0);
+            writeln!(
+                out, "{own:>12} {:>6.2}% {incl:>12} {:>6.2}%  {}",
+                percent(own), percent(incl), self.name(symbol)
+            ).unwrap();
+        }
+
+        writeln!(out, "\ncall graph:").unwrap();
+        writeln!(out, "{:>12}  caller -> callee", "calls").unwrap();
+        let mut calls = self.calls.iter().collect::<Vec<_>>();
+        calls.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (&(caller, callee), &count) in calls {
+            writeln!(out, "{count:>12}  {} -> {}", self.name(caller), self.name(callee)).unwrap();
+        }
+
+        out
+    }
+
+    /// Folded stacks (`outer;inner count` per line) for flame-graph tools.
+    pub fn folded_stacks(&self) -> String {
+        let mut lines = self.stacks.iter()
+            .map(|(stack, count)| {
+                let frames = stack.iter()
+                    .map(|&frame| self.name(frame))
+                    .collect::<Vec<_>>();
+                format!("{} {count}", frames.join(";"))
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+
+        let mut out = lines.join("\n");
+        out.push('\n');
+        out
+    }
+}
@@ -13,4 +13,14 @@ pub const GRA: u8 = 0xB;
 pub const GRB: u8 = 0xC;
 pub const RSP: u8 = 0xD; // stack pointer
 pub const CSP: u8 = 0xE; // call stack pointer
-pub const RPC: u8 = 0xF; // program counter
\ No newline at end of file
+pub const RPC: u8 = 0xF; // program counter
+pub const REGISTER_NAMES: [&str; 16] = [
+    "rds", "gr0", "gr1", "gr2", "gr3", "gr4", "gr5", "gr6",
+    "gr7", "gr8", "gr9", "gra", "grb", "rsp", "csp", "rpc",
+];
+
+/// Whether `reg` names a register pair, which holds a double with its low word
+/// in `reg` and its high word in the next register: gr0, gr2, ..., gra.
+pub fn is_pair(reg: u8) -> bool {
+    (GR0..=GRA).contains(&reg) && (reg - GR0).is_multiple_of(2)
+}
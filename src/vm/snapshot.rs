@@ -0,0 +1,328 @@
+use std::path::Path;
+use super::{float, Helios32, Interrupts, zeroed_memory, PAGE_COUNT, PAGE_SHIFT, PAGE_SIZE};
+use super::config::{DeviceWindow, MachineConfig};
+
+const MAGIC: &[u8; 4] = b"H32S";
+/// Bumped whenever a section is added or its layout changes; snapshots from
+/// any other version are rejected.
+///
+/// - 1: `CPU` and `MEM`.
+/// - 2: `ROM`.
+/// - 3: `FPU`.
+/// - 4: `RESERVATION`.
+/// - 5: `INTERRUPTS`.
+/// - 6: `CONFIG`.
+pub const SNAPSHOT_VERSION: u16 = 6;
+
+const SECTION_END: u8 = 0x00;
+const SECTION_CPU: u8 = 0x01;
+const SECTION_MEM: u8 = 0x02;
+const SECTION_ROM: u8 = 0x03;
+const SECTION_FPU: u8 = 0x04;
+const SECTION_RESERVATION: u8 = 0x05;
+const SECTION_INTERRUPTS: u8 = 0x06;
+const SECTION_CONFIG: u8 = 0x07;
+
+/// Memory is split across several `MEM` sections so lengths fit in a `u32`.
+const PAGES_PER_SECTION: usize = 256;
+
+impl Helios32 {
+    /// Serializes the machine state.
+    ///
+    /// Layout (all integers little-endian):
+    /// `[4:"H32S"][2:version]` followed by sections `[1:tag][4:length][length:payload]`
+    /// and terminated by an empty `END` section.
+    ///
+    /// - `CPU` (`0x01`): the 16 registers as `u32`, then `is_running` as one byte.
+    /// - `MEM` (`0x02`, repeatable): non-zero 4KiB pages as `[4:page index][4096:bytes]`.
+    /// - `ROM` (`0x03`, optional): the read-only boot ROM window as `[4:base][4:size]`.
+    ///   Its contents are saved in `MEM` like any other memory.
+    /// - `FPU` (`0x04`, optional): FCSR as a `u32`. Omitted while it is zero.
+    /// - `RESERVATION` (`0x05`, optional): the address held by the last `lr` as a
+    ///   `u32`. Omitted when no reservation is held.
+    /// - `INTERRUPTS` (`0x06`, optional): the IPI handler address and pending
+    ///   mask as `u32`s, then `in_handler` and `waiting` as one byte each.
+    ///   Omitted while all are clear.
+    /// - `CONFIG` (`0x07`): the [`MachineConfig`] as
+    ///   `[8:ram_size][4:code_base][4:rsp][4:csp]`, then `rsp_limit`,
+    ///   `csp_limit` and `reset_vector` as `[1:present][4:value]`, then
+    ///   `[4:rom_base][4:rom_size]`, then each device window as
+    ///   `[4:base][4:size][2:name length][name]`.
+    ///
+    /// Translated blocks and the selected [`Engine`](super::Engine) are host
+    /// state and are not saved.
+    pub fn save_snapshot(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(MAGIC);
+        out.extend(SNAPSHOT_VERSION.to_le_bytes());
+
+        let mut cpu = Vec::with_capacity(16 * 4 + 1);
+        for reg in self.core.registers {
+            cpu.extend(reg.to_le_bytes());
+        }
+        cpu.push(self.core.is_running as u8);
+        write_section(&mut out, SECTION_CPU, &cpu);
+        write_section(&mut out, SECTION_CONFIG, &encode_config(&self.config));
+
+        if self.core.fcsr != 0 {
+            write_section(&mut out, SECTION_FPU, &self.core.fcsr.to_le_bytes());
+        }
+
+        if let Some(addr) = self.core.reservation {
+            write_section(&mut out, SECTION_RESERVATION, &addr.to_le_bytes());
+        }
+
+        if self.core.interrupts != Interrupts::default() {
+            let mut interrupts = Vec::with_capacity(10);
+            interrupts.extend(self.core.interrupts.vector.to_le_bytes());
+            interrupts.extend(self.core.interrupts.pending.to_le_bytes());
+            interrupts.push(self.core.interrupts.in_handler as u8);
+            interrupts.push(self.core.interrupts.waiting as u8);
+            write_section(&mut out, SECTION_INTERRUPTS, &interrupts);
+        }
+
+        if let Some((base, size)) = self.rom {
+            let mut rom = Vec::with_capacity(8);
+            rom.extend(base.to_le_bytes());
+            rom.extend(size.to_le_bytes());
+            write_section(&mut out, SECTION_ROM, &rom);
+        }
+
+        let mut mem = Vec::new();
+        for page in 0..PAGE_COUNT {
+            if self.written_pages[page / 64] & (1 << (page % 64)) == 0 {
+                continue;
+            }
+            let bytes = &self.mem[page * PAGE_SIZE..(page + 1) * PAGE_SIZE];
+            if bytes.iter().all(|&b| b == 0) {
+                continue;
+            }
+            mem.extend((page as u32).to_le_bytes());
+            mem.extend(bytes);
+            if mem.len() == PAGES_PER_SECTION * (4 + PAGE_SIZE) {
+                write_section(&mut out, SECTION_MEM, &mem);
+                mem.clear();
+            }
+        }
+        if !mem.is_empty() {
+            write_section(&mut out, SECTION_MEM, &mem);
+        }
+
+        write_section(&mut out, SECTION_END, &[]);
+        out
+    }
+
+    /// Restores state produced by [`Helios32::save_snapshot`]. The machine is
+    /// left untouched if the snapshot is malformed.
+    ///
+    /// The saved [`MachineConfig`] replaces the machine's own. Faults and
+    /// stack high-water marks are not saved: the restored machine has no
+    /// fault and its marks start again from zero, as after a reset.
+    pub fn load_snapshot(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(4)? != MAGIC {
+            return Err("not a Helios-32 snapshot".to_string());
+        }
+        let version = u16::from_le_bytes(reader.take(2)?.try_into().unwrap());
+        if version != SNAPSHOT_VERSION {
+            return Err(format!("unsupported snapshot version {version}"));
+        }
+
+        let mut cpu = None;
+        let mut config = None;
+        let mut rom = None;
+        let mut fcsr = 0;
+        let mut reservation = None;
+        let mut interrupts = Interrupts::default();
+        let mut pages = Vec::new();
+        loop {
+            let tag = reader.take(1)?[0];
+            let len = u32::from_le_bytes(reader.take(4)?.try_into().unwrap()) as usize;
+            let payload = reader.take(len)?;
+
+            match tag {
+                SECTION_END => break,
+                SECTION_CPU => {
+                    if len != 16 * 4 + 1 {
+                        return Err("malformed CPU section in snapshot".to_string());
+                    }
+                    let mut registers = [0u32; 16];
+                    for (i, reg) in registers.iter_mut().enumerate() {
+                        *reg = u32::from_le_bytes(payload[i * 4..i * 4 + 4].try_into().unwrap());
+                    }
+                    cpu = Some((registers, payload[64] != 0));
+                },
+                SECTION_CONFIG => {
+                    let saved = decode_config(payload)
+                        .map_err(|_| "malformed config section in snapshot".to_string())?;
+                    saved.validate().map_err(|err| format!("invalid machine config in snapshot: {err}"))?;
+                    config = Some(saved);
+                },
+                SECTION_MEM => {
+                    if !len.is_multiple_of(4 + PAGE_SIZE) {
+                        return Err("malformed memory section in snapshot".to_string());
+                    }
+                    for chunk in payload.chunks_exact(4 + PAGE_SIZE) {
+                        let page = u32::from_le_bytes(chunk[..4].try_into().unwrap());
+                        if page as usize >= PAGE_COUNT {
+                            return Err(format!("invalid page index {page} in snapshot"));
+                        }
+                        pages.push((page, &chunk[4..]));
+                    }
+                },
+                SECTION_ROM => {
+                    if len != 8 {
+                        return Err("malformed ROM section in snapshot".to_string());
+                    }
+                    let base = u32::from_le_bytes(payload[..4].try_into().unwrap());
+                    let size = u32::from_le_bytes(payload[4..].try_into().unwrap());
+                    rom = Some((base, size));
+                },
+                SECTION_FPU => {
+                    if len != 4 {
+                        return Err("malformed FPU section in snapshot".to_string());
+                    }
+                    fcsr = u32::from_le_bytes(payload.try_into().unwrap());
+                },
+                SECTION_RESERVATION => {
+                    if len != 4 {
+                        return Err("malformed reservation section in snapshot".to_string());
+                    }
+                    reservation = Some(u32::from_le_bytes(payload.try_into().unwrap()));
+                },
+                SECTION_INTERRUPTS => {
+                    if len != 10 {
+                        return Err("malformed interrupts section in snapshot".to_string());
+                    }
+                    interrupts = Interrupts {
+                        vector: u32::from_le_bytes(payload[..4].try_into().unwrap()),
+                        pending: u32::from_le_bytes(payload[4..8].try_into().unwrap()),
+                        in_handler: payload[8] != 0,
+                        waiting: payload[9] != 0,
+                    };
+                },
+                other => return Err(format!("unknown snapshot section 0x{other:02X}")),
+            }
+        }
+
+        let Some((registers, is_running)) = cpu else {
+            return Err("snapshot has no CPU section".to_string());
+        };
+        let Some(config) = config else {
+            return Err("snapshot has no config section".to_string());
+        };
+
+        self.mem = zeroed_memory();
+        self.written_pages.fill(0);
+        for (page, bytes) in pages {
+            let start = (page as usize) << PAGE_SHIFT;
+            self.mem[start..start + PAGE_SIZE].copy_from_slice(bytes);
+            self.mark_written(start as u32, PAGE_SIZE as u32);
+        }
+        self.core.registers = registers;
+        self.core.stacks = config.stacks();
+        self.config = config;
+        self.core.fcsr = fcsr & float::FCSR_MASK;
+        self.core.is_running = is_running;
+        self.rom = rom;
+        self.core.reservation = reservation;
+        self.core.interrupts = interrupts;
+        self.core.fault = None;
+        self.core.stack_high_water = [0; 2];
+        self.flush_translations();
+
+        Ok(())
+    }
+
+    pub fn save_snapshot_to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        std::fs::write(path, self.save_snapshot())
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn load_snapshot_from_path<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        let bytes = std::fs::read(path)
+            .map_err(|err| err.to_string())?;
+
+        self.load_snapshot(&bytes)
+    }
+}
+
+fn write_section(out: &mut Vec<u8>, tag: u8, payload: &[u8]) {
+    out.push(tag);
+    out.extend((payload.len() as u32).to_le_bytes());
+    out.extend(payload);
+}
+
+fn encode_config(config: &MachineConfig) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend(config.ram_size.to_le_bytes());
+    for value in [config.code_base, config.rsp, config.csp] {
+        out.extend(value.to_le_bytes());
+    }
+    for value in [config.rsp_limit, config.csp_limit, config.reset_vector] {
+        out.push(value.is_some() as u8);
+        out.extend(value.unwrap_or(0).to_le_bytes());
+    }
+    out.extend(config.rom_base.to_le_bytes());
+    out.extend(config.rom_size.to_le_bytes());
+    for dev in &config.devices {
+        out.extend(dev.base.to_le_bytes());
+        out.extend(dev.size.to_le_bytes());
+        out.extend((dev.name.len() as u16).to_le_bytes());
+        out.extend(dev.name.as_bytes());
+    }
+    out
+}
+
+fn decode_config(payload: &[u8]) -> Result<MachineConfig, String> {
+    let mut reader = Reader { bytes: payload, pos: 0 };
+    let mut config = MachineConfig {
+        ram_size: u64::from_le_bytes(reader.take(8)?.try_into().unwrap()),
+        code_base: reader.u32()?,
+        rsp: reader.u32()?,
+        csp: reader.u32()?,
+        rsp_limit: reader.optional_u32()?,
+        csp_limit: reader.optional_u32()?,
+        reset_vector: reader.optional_u32()?,
+        rom_base: reader.u32()?,
+        rom_size: reader.u32()?,
+        devices: Vec::new(),
+    };
+    while reader.pos < payload.len() {
+        let base = reader.u32()?;
+        let size = reader.u32()?;
+        let len = u16::from_le_bytes(reader.take(2)?.try_into().unwrap());
+        let name = String::from_utf8(reader.take(len as usize)?.to_vec())
+            .map_err(|err| err.to_string())?;
+        config.devices.push(DeviceWindow { name, base, size });
+    }
+    Ok(config)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or("truncated snapshot")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// A `[1:present][4:value]` field.
+    fn optional_u32(&mut self) -> Result<Option<u32>, String> {
+        let present = self.take(1)?[0] != 0;
+        let value = self.u32()?;
+        Ok(present.then_some(value))
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt::Write;
+use std::path::Path;
+use super::assembler::Assembly;
+
+/// Section holding everything the assembler emits. The assembler has no data
+/// directives, so this is currently the only one.
+pub const TEXT_SECTION: &str = ".text";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MapEntry {
+    pub addr: u32,
+    pub size: u32,
+    pub section: String,
+    pub name: String,
+}
+
+/// Label table of an assembled program at its final load address.
+///
+/// The text form has one label per line, sorted by address then name:
+///
+/// ```text
+/// # address    size  section  name
+/// 0xC0000000      6  .text    main
+/// ```
+///
+/// Addresses are `0x`-prefixed hexadecimal, sizes are decimal bytes up to the
+/// next label or the end of the program. Fields are separated by whitespace;
+/// blank lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolMap {
+    pub entries: Vec<MapEntry>,
+}
+
+impl SymbolMap {
+    pub fn from_assembly(assembly: &Assembly) -> Self {
+        let mut entries = assembly.sized_labels()
+            .map(|(name, offset, size)| MapEntry {
+                addr: assembly.origin.wrapping_add(offset),
+                size,
+                section: TEXT_SECTION.to_string(),
+                name: name.to_string(),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.addr.cmp(&b.addr).then_with(|| a.name.cmp(&b.name)));
+
+        Self { entries }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "# {:<10} {:>5}  {:<7}  name", "address", "size", "section").unwrap();
+        for entry in &self.entries {
+            writeln!(
+                out, "0x{:08X} {:>7}  {:<7}  {}",
+                entry.addr, entry.size, entry.section, entry.name
+            ).unwrap();
+        }
+        out
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let err = |msg: &str| format!("error on line {} of symbol map: {msg}", idx + 1);
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [addr, size, section, name] = fields[..] else {
+                return Err(err("expected `<address> <size> <section> <name>`"));
+            };
+            entries.push(MapEntry {
+                addr: addr.strip_prefix("0x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| err("invalid address"))?,
+                size: size.parse().map_err(|_| err("invalid size"))?,
+                section: section.to_string(),
+                name: name.to_string(),
+            });
+        }
+        entries.sort_by(|a, b| a.addr.cmp(&b.addr).then_with(|| a.name.cmp(&b.name)));
+
+        Ok(Self { entries })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        std::fs::write(path, self.to_text())
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())?;
+
+        Self::parse(&text)
+    }
+}
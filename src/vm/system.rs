@@ -0,0 +1,143 @@
+use std::mem;
+use super::{Core, Helios32, Stack};
+use super::registers::*;
+
+/// Most cores a [`System`] can host; IPI masks have one bit per core.
+pub const MAX_CORES: u32 = 32;
+
+/// Several cores sharing one machine's memory, devices and boot ROM.
+///
+/// Cores run in turn, each for up to `quantum` instructions, always in ID
+/// order, so a run is fully determined by the program and the quantum. Every
+/// core starts from the machine's registers with its own slice of the data
+/// and call stacks: the configured stack regions are split evenly, core 0
+/// taking the top slice.
+///
+/// A core's stores break other cores' `lr` reservations and the IPIs it sent
+/// are delivered at the end of its turn. A core waiting in `wfi` is woken by
+/// the next IPI sent to it. The system stops once no core is running, which
+/// includes every remaining core waiting for an IPI that cannot come.
+pub struct System {
+    /// Shared memory, devices and ROM. Its own core only seeds the cores'
+    /// registers; use [`System::cores`] for their state.
+    pub machine: Helios32,
+    cores: Vec<Core>,
+    quantum: u64,
+}
+
+impl System {
+    /// Builds a system of `cores` cores around `machine`, which should already
+    /// hold the program and point RPC at it.
+    pub fn new(machine: Helios32, cores: u32, quantum: u64) -> Result<Self, String> {
+        if cores == 0 || cores > MAX_CORES {
+            return Err(format!("a system has 1 to {MAX_CORES} cores, not {cores}"));
+        }
+        if quantum == 0 {
+            return Err("the scheduling quantum must be at least 1 instruction".to_string());
+        }
+        let stacks = machine.config.stacks();
+        for (name, (top, limit)) in ["rsp", "csp"].into_iter().zip(stacks) {
+            match limit {
+                None if cores > 1 => {
+                    return Err(format!("{name}_limit must be set to split the stack between {cores} cores"));
+                },
+                Some(limit) if ((top - limit) as u64 + 1) / (cores as u64) < 8 => {
+                    return Err(format!("the {name} stack is too small to split between {cores} cores"));
+                },
+                _ => (),
+            }
+        }
+
+        let mut system = Self { machine, cores: Vec::new(), quantum };
+        system.machine.core_count = cores;
+        system.machine.store_log = Some(Vec::new());
+        system.boot(stacks, cores);
+        Ok(system)
+    }
+
+    pub fn cores(&self) -> &[Core] {
+        &self.cores
+    }
+
+    /// Whether any core is still running.
+    pub fn is_running(&self) -> bool {
+        self.cores.iter().any(|core| core.is_running)
+    }
+
+    /// Runs until every core has stopped.
+    pub fn run(&mut self) {
+        while self.is_running() {
+            self.run_for(u64::MAX);
+        }
+    }
+
+    /// Runs until every core has stopped or `budget` instructions have
+    /// executed across all cores, returning the number executed.
+    pub fn run_for(&mut self, budget: u64) -> u64 {
+        let mut executed = 0;
+        while self.is_running() && executed < budget {
+            for id in 0..self.cores.len() {
+                if executed == budget {
+                    break;
+                }
+                if !self.cores[id].is_running {
+                    continue;
+                }
+
+                let quantum = self.quantum.min(budget - executed);
+                executed += self.machine.cpu(Some(&mut self.cores[id])).run_for(quantum);
+                self.exchange(id);
+            }
+        }
+        executed
+    }
+
+    /// Carves each core's slice out of `stacks` and starts it from the
+    /// machine's registers.
+    fn boot(&mut self, stacks: [(u32, Option<u32>); 2], count: u32) {
+        let base = &self.machine.core;
+        self.cores = (0..count).map(|id| {
+            let stacks = stacks.map(|(top, limit)| match limit {
+                Some(limit) => {
+                    let slice = ((top - limit) as u64 + 1) / count as u64;
+                    let top = (top as u64 - id as u64 * slice) as u32;
+                    (top, Some((top as u64 + 1 - slice) as u32))
+                },
+                None => (top, None),
+            });
+            let mut core = Core::new(id, stacks);
+            core.registers = base.registers;
+            core.registers[RSP as usize] = stacks[Stack::Data as usize].0;
+            core.registers[CSP as usize] = stacks[Stack::Call as usize].0;
+            core.fcsr = base.fcsr;
+            core.is_running = true;
+            core
+        }).collect();
+    }
+
+    /// Publishes the end of core `sender`'s turn to the others: its stores
+    /// break their reservations and its IPIs become pending, waking waiters.
+    fn exchange(&mut self, sender: usize) {
+        let outbox = mem::take(&mut self.machine.outbox);
+        let stores = self.machine.store_log.get_or_insert_with(Vec::new);
+
+        for (id, core) in self.cores.iter_mut().enumerate() {
+            if id == sender {
+                continue;
+            }
+            if core.reservation.is_some_and(|reserved| {
+                stores.iter().any(|&addr| addr.wrapping_sub(reserved) < 4)
+            }) {
+                core.reservation = None;
+            }
+            if outbox & (1 << id) != 0 {
+                core.interrupts.pending |= 1 << sender;
+                if core.interrupts.waiting {
+                    core.interrupts.waiting = false;
+                    core.is_running = true;
+                }
+            }
+        }
+        stores.clear();
+    }
+}
@@ -0,0 +1,2057 @@
+//! Conformance tests: each case is a small assembly program run to `HLT` on
+//! both engines, followed by checks of the final register and memory state.
+
+use super::*;
+use super::assembler;
+use super::config::DeviceWindow;
+use super::debugger::{Debugger, LastWrite, StopReason};
+use super::coverage::Coverage;
+use super::debuginfo::{DebugInfo, LineEntry};
+use super::profiler::Profiler;
+use super::symbols::SymbolMap;
+use super::registers::*;
+
+/// Assembles `source` at the code base and runs it to completion on the
+/// interpreter and the threaded engine, checking that both agree.
+fn run(source: &str) -> Helios32 {
+    run_on(MachineConfig::default(), source)
+}
+
+fn run_on(config: MachineConfig, source: &str) -> Helios32 {
+    let assembly = assembler::assemble(source, config.code_base).unwrap();
+    let [vm, threaded] = [Engine::Interpreter, Engine::Threaded].map(|engine| {
+        let mut vm = Helios32::builder().config(config.clone()).engine(engine).build().unwrap();
+        vm.load_program(assembly.origin, &assembly.bytes).unwrap();
+        let executed = vm.run_for(100_000);
+        assert!(!vm.core.is_running, "program still running after {executed} instructions");
+        vm
+    });
+
+    assert_eq!(vm.core.registers, threaded.core.registers, "engines disagree on registers");
+    assert_eq!(vm.core.fault, threaded.core.fault, "engines disagree on faults");
+    assert_eq!(vm.core.fcsr, threaded.core.fcsr, "engines disagree on FCSR");
+    assert_same_memory(&vm, &threaded);
+    vm
+}
+
+/// Checks that two machines hold the same bytes in every page either wrote.
+fn assert_same_memory(a: &Helios32, b: &Helios32) {
+    for (i, (&left, &right)) in a.written_pages.iter().zip(b.written_pages.iter()).enumerate() {
+        for bit in 0..64 {
+            if (left | right) & (1 << bit) == 0 {
+                continue;
+            }
+            let start = (i * 64 + bit) * PAGE_SIZE;
+            let range = start..start + PAGE_SIZE;
+            assert!(a.mem[range.clone()] == b.mem[range], "engines disagree on memory at 0x{start:08X}");
+        }
+    }
+}
+
+fn reg(vm: &Helios32, reg: u8) -> u32 {
+    vm.core.registers[reg as usize]
+}
+
+fn word(vm: &Helios32, addr: u32) -> u32 {
+    let addr = addr as usize;
+    u32::from_le_bytes(vm.mem[addr..addr + 4].try_into().unwrap())
+}
+
+fn float(vm: &Helios32, reg: u8) -> f32 {
+    f32::from_bits(vm.core.registers[reg as usize])
+}
+
+#[test]
+fn nop_and_hlt() {
+    let vm = run("
+        nop
+        nop
+        hlt
+        ldi gr0 0x1
+    ");
+    assert_eq!(reg(&vm, RPC), CODE_BASE + 18);
+    assert_eq!(reg(&vm, GR0), 0);
+    assert_eq!(vm.core.fault, None);
+}
+
+#[test]
+fn ldi_forms() {
+    let vm = run("
+        ldi gr0 42
+        ldi gr1 0x2A
+        ldi gr2 0b101010
+        ldi gr3 0o52
+        ldi gr4 'A'
+        ldi gr5 '\\n'
+        ldi gr6 -1
+        ldi gr7 1.5
+        ldi gr8 0xFFFFFFFF
+        ldi gr9 42.0
+        ldi gra -1.5
+        ldi grb -42
+        hlt
+    ");
+    // Plain decimal integers load as integers, not as the bits of a float.
+    assert_eq!(reg(&vm, GR0), 42);
+    assert_eq!(reg(&vm, GR1), 42);
+    assert_eq!(reg(&vm, GR2), 42);
+    assert_eq!(reg(&vm, GR3), 42);
+    assert_eq!(reg(&vm, GR4), 'A' as u32);
+    assert_eq!(reg(&vm, GR5), '\n' as u32);
+    assert_eq!(reg(&vm, GR6), 0xFFFF_FFFF);
+    assert_eq!(reg(&vm, GR7), 1.5f32.to_bits());
+    assert_eq!(reg(&vm, GR8), 0xFFFF_FFFF);
+    assert_eq!(reg(&vm, GR9), 42.0f32.to_bits());
+    assert_eq!(reg(&vm, GRA), (-1.5f32).to_bits());
+    assert_eq!(reg(&vm, GRB), 42u32.wrapping_neg());
+
+    // `.expect` values and immediate operands share `ldi`'s parsing.
+    for (text, value) in [("42", 42), ("+6", 6), ("-42", 42u32.wrapping_neg()), ("-1.5", (-1.5f32).to_bits()), ("-inf", f32::NEG_INFINITY.to_bits())] {
+        assert_eq!(assembler::parse_signed_immediate(text), Ok(value), "{text}");
+    }
+}
+
+#[test]
+fn rds_reads_as_zero() {
+    let vm = run("
+        ldi gr0 0x5
+        ldi rds 0x7
+        add gr1 rds gr0
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR1), 5);
+}
+
+#[test]
+fn add_sub_wrap() {
+    let vm = run("
+        ldi gr0 0xFFFFFFFF
+        ldi gr1 0x1
+        add gr2 gr0 gr1
+        sub gr3 rds gr1
+        add gr4 gr0 gr0
+        sub gr5 gr1 gr1
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR2), 0);
+    assert_eq!(reg(&vm, GR3), 0xFFFF_FFFF);
+    assert_eq!(reg(&vm, GR4), 0xFFFF_FFFE);
+    assert_eq!(reg(&vm, GR5), 0);
+}
+
+#[test]
+fn addi_subi_inc_dec() {
+    let vm = run("
+        ldi gr0 0xFFFFFFFF
+        addi gr1 gr0 0x2
+        subi gr2 rds 0x1
+        addi gr3 gr1 -3
+        ldi gr4 0xFFFFFFFF
+        inc gr4
+        dec gr5
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR1), 1);
+    assert_eq!(reg(&vm, GR2), 0xFFFF_FFFF);
+    assert_eq!(reg(&vm, GR3), 0xFFFF_FFFE);
+    assert_eq!(reg(&vm, GR4), 0);
+    assert_eq!(reg(&vm, GR5), 0xFFFF_FFFF);
+}
+
+#[test]
+fn bitwise() {
+    let vm = run("
+        ldi gr0 0xF0F0F0F0
+        ldi gr1 0xFF00FF00
+        bor gr2 gr0 gr1
+        band gr3 gr0 gr1
+        bxor gr4 gr0 gr1
+        bnot gr5 gr0
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR2), 0xFFF0_FFF0);
+    assert_eq!(reg(&vm, GR3), 0xF000_F000);
+    assert_eq!(reg(&vm, GR4), 0x0FF0_0FF0);
+    assert_eq!(reg(&vm, GR5), 0x0F0F_0F0F);
+}
+
+#[test]
+fn logical() {
+    let vm = run("
+        ldi gr0 0x2
+        ldi gr1 0x80000000
+        lor gr2 gr0 rds
+        lor gr3 rds rds
+        land gr4 gr0 gr1
+        land gr5 gr0 rds
+        lxor gr6 gr0 gr1
+        lxor gr7 gr0 rds
+        lnot gr8 gr0
+        lnot gr9 rds
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR2), 1);
+    assert_eq!(reg(&vm, GR3), 0);
+    assert_eq!(reg(&vm, GR4), 1);
+    assert_eq!(reg(&vm, GR5), 0);
+    assert_eq!(reg(&vm, GR6), 0);
+    assert_eq!(reg(&vm, GR7), 1);
+    assert_eq!(reg(&vm, GR8), 0);
+    assert_eq!(reg(&vm, GR9), 1);
+}
+
+#[test]
+fn compares_are_unsigned() {
+    let vm = run("
+        ldi gr0 0xFFFFFFFF
+        ldi gr1 0x1
+        eq gr2 gr0 gr0
+        ne gr3 gr0 gr1
+        gt gr4 gr0 gr1
+        lt gr5 gr0 gr1
+        ge gr6 gr1 gr1
+        le gr7 gr0 gr1
+        eq gr8 gr0 gr1
+        ne gr9 gr1 gr1
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR2), 1);
+    assert_eq!(reg(&vm, GR3), 1);
+    // -1 compares as 0xFFFFFFFF, above 1.
+    assert_eq!(reg(&vm, GR4), 1);
+    assert_eq!(reg(&vm, GR5), 0);
+    assert_eq!(reg(&vm, GR6), 1);
+    assert_eq!(reg(&vm, GR7), 0);
+    assert_eq!(reg(&vm, GR8), 0);
+    assert_eq!(reg(&vm, GR9), 0);
+}
+
+#[test]
+fn signed_compares() {
+    let vm = run("
+        ldi gr0 -1
+        ldi gr1 0x1
+        ldi gr2 0x80000000
+        sgt gr3 gr0 gr1
+        slt gr4 gr0 gr1
+        sge gr5 gr0 gr0
+        sle gr6 gr1 gr0
+        slt gr7 gr2 gr0
+        sgt gr8 gr0 gr2
+        sge gr9 gr2 gr1
+        sle gra gr2 gr2
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR3), 0);
+    assert_eq!(reg(&vm, GR4), 1);
+    assert_eq!(reg(&vm, GR5), 1);
+    assert_eq!(reg(&vm, GR6), 0);
+    assert_eq!(reg(&vm, GR7), 1);
+    assert_eq!(reg(&vm, GR8), 1);
+    assert_eq!(reg(&vm, GR9), 0);
+    assert_eq!(reg(&vm, GRA), 1);
+}
+
+#[test]
+fn shifts_and_rotates() {
+    let vm = run("
+        ldi gr0 0x80000001
+        ldi gr1 0x4
+        ldi gr2 0x21
+        shl gr3 gr0 gr1
+        lshr gr4 gr0 gr1
+        ashr gr5 gr0 gr1
+        rotl gr6 gr0 gr1
+        rotr gr7 gr0 gr1
+        shl gr8 gr0 gr2
+        ashr gr9 gr0 gr2
+        rotl gra gr0 gr2
+        ldi grb 0x20
+        lshr grb gr0 grb
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR3), 0x0000_0010);
+    assert_eq!(reg(&vm, GR4), 0x0800_0000);
+    assert_eq!(reg(&vm, GR5), 0xF800_0000);
+    assert_eq!(reg(&vm, GR6), 0x0000_0018);
+    assert_eq!(reg(&vm, GR7), 0x1800_0000);
+    // Shift amounts are taken modulo 32.
+    assert_eq!(reg(&vm, GR8), 0x0000_0002);
+    assert_eq!(reg(&vm, GR9), 0xC000_0000);
+    assert_eq!(reg(&vm, GRA), 0x0000_0003);
+    assert_eq!(reg(&vm, GRB), 0x8000_0001);
+}
+
+#[test]
+fn multiply() {
+    let vm = run("
+        ldi gr0 0xFFFFFFFF
+        ldi gr1 0x3
+        ldi gr2 -2
+        mul gr3 gr0 gr0
+        mul gr4 gr1 gr2
+        muhu gr5 gr0 gr0
+        muhs gr6 gr0 gr0
+        muhs gr7 gr2 gr1
+        muhu gr8 gr2 gr1
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR3), 1);
+    assert_eq!(reg(&vm, GR4), -6i32 as u32);
+    assert_eq!(reg(&vm, GR5), 0xFFFF_FFFE);
+    // -1 * -1 = 1, high word 0.
+    assert_eq!(reg(&vm, GR6), 0);
+    // -2 * 3 = -6, high word all ones.
+    assert_eq!(reg(&vm, GR7), 0xFFFF_FFFF);
+    assert_eq!(reg(&vm, GR8), 2);
+}
+
+#[test]
+fn divide() {
+    let vm = run("
+        ldi gr0 0x11
+        ldi gr1 0x5
+        ldi gr2 0xFFFFFFFF
+        div gr3 gr0 gr1
+        rem gr4 gr0 gr1
+        div gr5 gr2 gr1
+        div gr6 gr0 rds
+        rem gr7 gr0 rds
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR3), 3);
+    assert_eq!(reg(&vm, GR4), 2);
+    assert_eq!(reg(&vm, GR5), 0x3333_3333);
+    assert_eq!(reg(&vm, GR6), 0);
+    assert_eq!(reg(&vm, GR7), 0);
+}
+
+#[test]
+fn signed_divide() {
+    let vm = run("
+        ldi gr0 -7
+        ldi gr1 0x2
+        ldi gr2 0x80000000
+        ldi gr3 -1
+        sdiv gr4 gr0 gr1
+        srem gr5 gr0 gr1
+        sdiv gr6 gr2 gr3
+        srem gr7 gr2 gr3
+        sdiv gr8 gr0 rds
+        srem gr9 gr0 rds
+        sdiv gra gr1 gr0
+        srem grb gr1 gr0
+        hlt
+    ");
+    // Rounds toward zero; the remainder takes the dividend's sign.
+    assert_eq!(reg(&vm, GR4), -3i32 as u32);
+    assert_eq!(reg(&vm, GR5), -1i32 as u32);
+    assert_eq!(reg(&vm, GR6), 0x8000_0000);
+    assert_eq!(reg(&vm, GR7), 0);
+    assert_eq!(reg(&vm, GR8), 0);
+    assert_eq!(reg(&vm, GR9), 0);
+    assert_eq!(reg(&vm, GRA), 0);
+    assert_eq!(reg(&vm, GRB), 2);
+}
+
+#[test]
+fn register_immediate_forms() {
+    let vm = run("
+        ldi gr0 0x12345678
+        band gr1 gr0 0xFF
+        bor gr2 gr0 -16
+        bxor gr3 gr0 0xFFFFFFFF
+        shl gr4 gr0 4
+        lshr gr5 gr3 28
+        ashr gr6 gr3 28
+        rotr gr7 gr0 8
+        rotl gr8 gr0 0x24
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR1), 0x78);
+    assert_eq!(reg(&vm, GR2), 0xFFFF_FFF8);
+    assert_eq!(reg(&vm, GR3), 0xEDCB_A987);
+    assert_eq!(reg(&vm, GR4), 0x2345_6780);
+    assert_eq!(reg(&vm, GR5), 0xE);
+    assert_eq!(reg(&vm, GR6), 0xFFFF_FFFE);
+    assert_eq!(reg(&vm, GR7), 0x7812_3456);
+    assert_eq!(reg(&vm, GR8), 0x2345_6781);
+
+    let vm = run("
+        ldi gr0 -3
+        lt gr1 gr0 -1
+        slt gr2 gr0 -1
+        sgt gr3 gr0 -4
+        eq gr4 gr0 0xFFFFFFFD
+        ne gr5 gr0 3
+        mul gr6 gr0 -1000
+        div gr7 gr0 2
+        sdiv gr8 gr0 2
+        srem gr9 gr0 2
+        rem gra gr0 0x3FFFFFFF
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR1), 1);
+    assert_eq!(reg(&vm, GR2), 1);
+    assert_eq!(reg(&vm, GR3), 1);
+    assert_eq!(reg(&vm, GR4), 1);
+    assert_eq!(reg(&vm, GR5), 1);
+    assert_eq!(reg(&vm, GR6), 3000);
+    assert_eq!(reg(&vm, GR7), 0x7FFF_FFFE);
+    assert_eq!(reg(&vm, GR8), -1i32 as u32);
+    assert_eq!(reg(&vm, GR9), -1i32 as u32);
+    assert_eq!(reg(&vm, GRA), 0xFFFF_FFFD % 0x3FFF_FFFF);
+
+    // Immediates must survive sign extension from 31 bits.
+    for source in ["band gr0 gr0 0x80000000", "band gr0 gr0 0x7FFFFFFF", "band gr0 gr0 -0x40000001", "mul gr0 gr0 x"] {
+        assert!(assembler::assemble(source, CODE_BASE).is_err(), "`{source}` assembled");
+    }
+    let assembly = assembler::assemble("band gr0 gr1 -0x40000000
+band gr0 gr1 0x3FFFFFFF", CODE_BASE).unwrap();
+    assert_eq!(&assembly.bytes[..6], &[isa::BAND, GR0 | (GR1 << 4), 0, 0, 0, 0xC0]);
+    assert_eq!(&assembly.bytes[6..], &[isa::BAND, GR0 | (GR1 << 4), 0xFF, 0xFF, 0xFF, 0xBF]);
+}
+
+#[test]
+fn float_arithmetic() {
+    let vm = run("
+        ldi gr0 3.0
+        ldi gr1 2.0
+        fadd gr2 gr0 gr1
+        fsub gr3 gr0 gr1
+        fmul gr4 gr0 gr1
+        fdiv gr5 gr0 gr1
+        frem gr6 gr0 gr1
+        ldi gr7 -5.5
+        fmul gr7 gr7 gr1
+        hlt
+    ");
+    assert_eq!(float(&vm, GR2), 5.0);
+    assert_eq!(float(&vm, GR3), 1.0);
+    assert_eq!(float(&vm, GR4), 6.0);
+    assert_eq!(float(&vm, GR5), 1.5);
+    assert_eq!(float(&vm, GR6), 1.0);
+    assert_eq!(float(&vm, GR7), -11.0);
+}
+
+#[test]
+fn float_special_values() {
+    let vm = run("
+        ldi gr0 1.0
+        ldi gr1 inf
+        ldi gr2 nan
+        fdiv gr3 gr0 rds
+        fdiv gr4 rds rds
+        fsub gr5 gr1 gr1
+        fadd gr6 gr2 gr0
+        fmul gr7 gr1 rds
+        frem gr8 gr0 rds
+        fadd gr9 gr1 gr0
+        hlt
+    ");
+    assert_eq!(float(&vm, GR3), f32::INFINITY);
+    assert!(float(&vm, GR4).is_nan());
+    assert!(float(&vm, GR5).is_nan());
+    assert!(float(&vm, GR6).is_nan());
+    assert!(float(&vm, GR7).is_nan());
+    assert!(float(&vm, GR8).is_nan());
+    assert_eq!(float(&vm, GR9), f32::INFINITY);
+}
+
+#[test]
+fn float_compares() {
+    let vm = run("
+        ldi gr0 1.0
+        ldi gr1 2.0
+        ldi gr2 nan
+        ldi gr3 -0.0
+        feq gr4 gr0 gr0
+        flt gr5 gr0 gr1
+        fle gr6 gr1 gr0
+        feq gr7 gr2 gr2
+        flt gr8 gr2 gr0
+        fle gr9 gr0 gr2
+        feq gra gr3 rds
+        fle grb gr3 rds
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR4), 1);
+    assert_eq!(reg(&vm, GR5), 1);
+    assert_eq!(reg(&vm, GR6), 0);
+    // Every comparison with NaN is false, NaN == NaN included.
+    assert_eq!(reg(&vm, GR7), 0);
+    assert_eq!(reg(&vm, GR8), 0);
+    assert_eq!(reg(&vm, GR9), 0);
+    assert_eq!(reg(&vm, GRA), 1);
+    assert_eq!(reg(&vm, GRB), 1);
+}
+
+#[test]
+fn float_math() {
+    let vm = run("
+        ldi gr0 2.0
+        ldi gr1 -1.0
+        ldi gr2 nan
+        ldi gr3 -0.0
+        fsqrt gr4 gr0
+        fsqrt gr5 gr1
+        fabs gr6 gr1
+        fneg gr7 gr0
+        fmin gr8 gr2 gr1
+        fmax gr9 gr1 gr2
+        fmin gra gr3 rds
+        fmax grb rds gr3
+        hlt
+    ");
+    assert_eq!(float(&vm, GR4), 2f32.sqrt());
+    assert!(float(&vm, GR5).is_nan());
+    assert_eq!(float(&vm, GR6), 1.0);
+    assert_eq!(float(&vm, GR7), -2.0);
+    assert_eq!(float(&vm, GR8), -1.0);
+    assert_eq!(float(&vm, GR9), -1.0);
+    assert_eq!(reg(&vm, GRA), (-0f32).to_bits());
+    assert_eq!(reg(&vm, GRB), 0);
+
+    let vm = run("
+        ldi gr0 nan
+        fabs gr1 gr0
+        fneg gr2 gr1
+        fmin gr3 gr0 gr0
+        ldi gr4 0x3F800800
+        ldi gr5 0xBF801000
+        fma gr6 gr4 gr4 gr5
+        fmul gr7 gr4 gr4
+        fadd gr7 gr7 gr5
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR1), f32::NAN.to_bits() & 0x7FFF_FFFF);
+    assert_eq!(reg(&vm, GR2), f32::NAN.to_bits() | 0x8000_0000);
+    assert!(float(&vm, GR3).is_nan());
+    // (1 + 2^-12)^2 - (1 + 2^-11) is 2^-24, lost when the product is rounded first.
+    assert_eq!(float(&vm, GR6), 2f32.powi(-24));
+    assert_eq!(float(&vm, GR7), 0.0);
+}
+
+#[test]
+fn float_conversions() {
+    let vm = run("
+        ldi gr0 2.5
+        ftoi gr1 gr0
+        ftoi gr2 gr0 rne
+        ftoi gr3 gr0 rmm
+        ftoi gr4 gr0 rdn
+        ftoi gr5 gr0 rup
+        ldi gr0 -2.5
+        ftoi gr6 gr0 rne
+        ftoi gr7 gr0 rmm
+        ftoi gr8 gr0 rdn
+        ftoi gr9 gr0 rup
+        ftoi gra gr0 rtz
+        ftou grb gr0
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR1), 2);
+    assert_eq!(reg(&vm, GR2), 2);
+    assert_eq!(reg(&vm, GR3), 3);
+    assert_eq!(reg(&vm, GR4), 2);
+    assert_eq!(reg(&vm, GR5), 3);
+    assert_eq!(reg(&vm, GR6), -2i32 as u32);
+    assert_eq!(reg(&vm, GR7), -3i32 as u32);
+    assert_eq!(reg(&vm, GR8), -3i32 as u32);
+    assert_eq!(reg(&vm, GR9), -2i32 as u32);
+    assert_eq!(reg(&vm, GRA), -2i32 as u32);
+    assert_eq!(reg(&vm, GRB), 0);
+
+    let vm = run("
+        ldi gr0 1e10
+        ftoi gr1 gr0
+        ftou gr2 gr0
+        ldi gr0 nan
+        ftoi gr3 gr0
+        ldi gr0 16777217
+        itof gr4 gr0
+        itof gr5 gr0 rup
+        itof gr6 gr0 rdn
+        ldi gr0 0xFFFFFFFF
+        utof gr7 gr0
+        itof gr8 gr0
+        ldi gr0 -16777217
+        itof gr9 gr0 rtz
+        itof gra gr0 rmm
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR1), i32::MAX as u32);
+    assert_eq!(reg(&vm, GR2), u32::MAX);
+    assert_eq!(reg(&vm, GR3), 0);
+    assert_eq!(float(&vm, GR4), 16777216.0);
+    assert_eq!(float(&vm, GR5), 16777218.0);
+    assert_eq!(float(&vm, GR6), 16777216.0);
+    assert_eq!(float(&vm, GR7), 4294967296.0);
+    assert_eq!(float(&vm, GR8), -1.0);
+    assert_eq!(float(&vm, GR9), -16777216.0);
+    assert_eq!(float(&vm, GRA), -16777218.0);
+}
+
+#[test]
+fn float_exception_flags() {
+    let vm = run("
+        ldi gr0 1.0
+        ldi gr1 0.0
+        ldi gr2 3.0
+        fdiv gr3 gr0 gr1
+        frcsr gr4
+        fwcsr rds
+        fdiv gr3 gr1 gr1
+        frcsr gr5
+        fwcsr rds
+        fdiv gr3 gr0 gr2
+        frcsr gr6
+        fwcsr rds
+        ldi gr3 3.4e38
+        fmul gr3 gr3 gr3
+        frcsr gr7
+        fwcsr rds
+        ldi gr3 1e-30
+        fmul gr3 gr3 gr3
+        frcsr gr8
+        fwcsr rds
+        fadd gr3 gr0 gr2
+        fsqrt gr3 gr3
+        flt gr9 gr0 gr2
+        ldi gr3 1e10
+        ftou gr3 gr3
+        frcsr gra
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR4), float::DIVIDE_BY_ZERO);
+    assert_eq!(reg(&vm, GR5), float::INVALID);
+    assert_eq!(reg(&vm, GR6), float::INEXACT);
+    assert_eq!(reg(&vm, GR7), float::OVERFLOW | float::INEXACT);
+    assert_eq!(reg(&vm, GR8), float::UNDERFLOW | float::INEXACT);
+    // 1 + 3 and its square root are exact; only the out-of-range conversion raises.
+    assert_eq!(reg(&vm, GRA), float::INVALID);
+    assert_eq!(vm.core.fcsr, float::INVALID);
+}
+
+#[test]
+fn float_rounding_modes() {
+    // Rounding mode in bits 5..7: rdn = 2, rup = 3, rtz = 1.
+    let vm = run("
+        ldi gr0 1.0
+        ldi gr1 3.0
+        ldi gr9 0x40
+        fwcsr gr9
+        fdiv gr2 gr0 gr1
+        ldi gr9 0x60
+        fwcsr gr9
+        fdiv gr3 gr0 gr1
+        ldi gr4 -1.0
+        fdiv gr4 gr4 gr1
+        ldi gr5 16777217
+        itof gr5 gr5 dyn
+        ldi gr6 2.5
+        ftoi gr6 gr6 dyn
+        ldi gr7 3.4e38
+        fadd gr7 gr7 gr7
+        ldi gr9 0x20
+        fwcsr gr9
+        ldi gr8 3.4e38
+        fadd gr8 gr8 gr8
+        fsub gra gr0 gr0
+        ldi gr9 0x40
+        fwcsr gr9
+        fsub grb gr0 gr0
+        hlt
+    ");
+    assert_eq!(float(&vm, GR3).to_bits(), float(&vm, GR2).to_bits() + 1);
+    assert_eq!(float(&vm, GR3), 1.0 / 3.0);
+    assert_eq!(float(&vm, GR4), -float(&vm, GR2));
+    assert_eq!(float(&vm, GR5), 16777218.0);
+    assert_eq!(reg(&vm, GR6), 3);
+    assert_eq!(float(&vm, GR7), f32::INFINITY);
+    assert_eq!(float(&vm, GR8), f32::MAX);
+    assert!(float(&vm, GRA) == 0.0 && float(&vm, GRA).is_sign_positive());
+    assert!(float(&vm, GRB) == 0.0 && float(&vm, GRB).is_sign_negative());
+}
+
+#[test]
+fn float_traps() {
+    // Trap enables in bits 8..12: divide-by-zero = 0x800.
+    let vm = run("
+        ldi gr0 1.0
+        ldi gr1 3.0
+        ldi gr9 0x800
+        fwcsr gr9
+        fdiv gr2 gr0 gr1
+        ldi gr1 0.0
+        ldi gr3 0x7
+        fdiv gr3 gr0 gr1
+        hlt
+    ");
+    assert_eq!(vm.core.fault, Some(Fault::FloatingPoint {
+        pc: CODE_BASE + 42,
+        flags: float::DIVIDE_BY_ZERO,
+    }));
+    assert_eq!(reg(&vm, RPC), CODE_BASE + 42);
+    assert_eq!(reg(&vm, GR3), 0x7);
+    // The inexact 1/3 accrued; the trapped exception did not.
+    assert_eq!(vm.core.fcsr, 0x800 | float::INEXACT);
+
+    let mut copy = Helios32::new();
+    copy.load_snapshot(&vm.save_snapshot()).unwrap();
+    assert_eq!(copy.core.fcsr, vm.core.fcsr);
+}
+
+type FloatOp<'a> = dyn Fn(RoundingMode) -> (f32, u32) + 'a;
+type DoubleOp<'a> = dyn Fn(RoundingMode) -> (f64, u32) + 'a;
+
+#[test]
+fn float_rounding_matches_host() {
+    let mut rng = fuzz::Rng::new(7);
+    let mut value = || {
+        let bits = rng.next() as u32;
+        // Mix in small exponents so subnormals and cancellation show up.
+        let f = f32::from_bits(if bits & 1 == 0 { bits } else { bits & 0x81FF_FFFF });
+        if f.is_finite() { f } else { 1.5 }
+    };
+    let modes = [RoundingMode::Down, RoundingMode::Up, RoundingMode::TowardZero, RoundingMode::NearestAway];
+    for _ in 0..20_000 {
+        let (a, b, c) = (value(), value(), value());
+        let cases: [(&FloatOp, f32); 6] = [
+            (&|mode| float::add(a, b, mode), a + b),
+            (&|mode| float::sub(a, b, mode), a - b),
+            (&|mode| float::mul(a, b, mode), a * b),
+            (&|mode| float::div(a, b, mode), a / b),
+            (&|mode| float::sqrt(a.abs(), mode), a.abs().sqrt()),
+            (&|mode| float::fma(a, b, c, mode), a.mul_add(b, c)),
+        ];
+        for (op, host) in cases {
+            let (nearest, flags) = op(RoundingMode::NearestEven);
+            assert_eq!(nearest.to_bits(), host.to_bits(), "{a:e} {b:e} {c:e}");
+            let [down, up, zero, away] = modes.map(|mode| op(mode).0);
+            if flags & float::INEXACT == 0 {
+                assert!(down == nearest && up == nearest && zero == nearest && away == nearest);
+            } else if host.is_finite() && flags & float::OVERFLOW == 0 {
+                assert_eq!(down.next_up(), up, "{a:e} {b:e} {c:e}");
+                assert!(nearest == down || nearest == up);
+                assert!(away == down || away == up);
+                assert_eq!(zero, if down >= 0.0 { down } else { up });
+            }
+        }
+    }
+}
+
+fn double(vm: &Helios32, low: u8) -> f64 {
+    f64::from_bits(reg(vm, low) as u64 | (reg(vm, low + 1) as u64) << 32)
+}
+
+#[test]
+fn double_arithmetic() {
+    let vm = run("
+        ldd gr0 1.0
+        ldd gr2 3.0
+        ddiv gr4 gr0 gr2
+        dadd gr6 gr4 gr4
+        dsub gr8 gr6 gr0
+        dmul gr8 gr8 gr2
+        dsqrt gra gr2
+        deq gr0 gr6 gr6
+        dlt gr1 gr2 gr0
+        dle gr2 gr0 gr0
+        hlt
+    ");
+    assert_eq!(double(&vm, GR4), 1.0 / 3.0);
+    assert_eq!(double(&vm, GR6), 2.0 / 3.0);
+    assert_eq!(double(&vm, GR8), (2.0 / 3.0 - 1.0) * 3.0);
+    assert_eq!(double(&vm, GRA), 3.0f64.sqrt());
+    assert_eq!((reg(&vm, GR0), reg(&vm, GR1), reg(&vm, GR2)), (1, 0, 1));
+    assert_eq!(vm.core.fcsr, float::INEXACT);
+
+    // `ldd` takes two instruction slots, so labels after it move too.
+    let vm = run("
+        ldd gr0 -2.5
+        dabs gr2 gr0
+        dneg gr4 gr2
+        ldd gr6 0x7FF0000000000000
+        ldi gr8 after
+        hlt
+        after:
+    ");
+    assert_eq!(double(&vm, GR2), 2.5);
+    assert_eq!(double(&vm, GR4), -2.5);
+    assert_eq!(double(&vm, GR6), f64::INFINITY);
+    assert_eq!(reg(&vm, GR8), CODE_BASE + 48);
+
+    // Fields that do not name a pair make the instruction a NOP.
+    let mut vm = Helios32::new();
+    vm.core.registers[GR1 as usize] = 0x1234;
+    vm.load_program(CODE_BASE, &[isa::DADD, GR1 | (GR0 << 4), GR0, 0, 0, 0, isa::HLT, 0, 0, 0, 0, 0]).unwrap();
+    vm.run_for(10);
+    assert_eq!(reg(&vm, GR1), 0x1234);
+    assert!(assembler::assemble("dadd gr1 gr0 gr0", CODE_BASE).is_err());
+    let text = disassembler::disassemble(&vm.mem[CODE_BASE as usize..][..12], CODE_BASE);
+    assert!(text.contains(&format!("nop {:28}; 0xC0000000 (encoded as {:02X} 12 01 00 00 00)", "", isa::DADD)), "{text}");
+    let inst = u64::from_le_bytes([isa::DADD, 0x12, 0x01, 0, 0, 0, 0, 0]);
+    assert_eq!(disassembler::instruction(inst), format!("nop ; encoded as {:02X} 12 01 00 00 00", isa::DADD));
+}
+
+#[test]
+fn double_conversions() {
+    let vm = run("
+        ldi gr0 0.1
+        ftod gr2 gr0
+        dtof gr4 gr2
+        ldd gr6 0.1
+        dtof gr5 gr6
+        dtof gra gr6 rdn
+        ldi gr0 -7
+        itod gr6 gr0
+        ldi gr0 0xFFFFFFFF
+        utod gr8 gr0
+        hlt
+    ");
+    assert_eq!(double(&vm, GR2), 0.1f32 as f64);
+    assert_eq!(float(&vm, GR4), 0.1);
+    assert_eq!(float(&vm, GR5), 0.1);
+    assert_eq!(float(&vm, GRA), 0.1f32.next_down());
+    assert_eq!(double(&vm, GR6), -7.0);
+    assert_eq!(double(&vm, GR8), 4294967295.0);
+
+    let vm = run("
+        ldd gr0 -2.5
+        dtoi gr2 gr0
+        dtoi gr3 gr0 rmm
+        dtou gr4 gr0
+        ldd gr0 4294967295.5
+        dtou gr5 gr0 rdn
+        dtou gr6 gr0 rup
+        ldd gr0 1e300
+        dtof gr7 gr0
+        frcsr gr8
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR2), -2i32 as u32);
+    assert_eq!(reg(&vm, GR3), -3i32 as u32);
+    assert_eq!(reg(&vm, GR4), 0);
+    assert_eq!(reg(&vm, GR5), u32::MAX);
+    assert_eq!(reg(&vm, GR6), u32::MAX);
+    assert_eq!(float(&vm, GR7), f32::INFINITY);
+    assert_eq!(reg(&vm, GR8), float::INVALID | float::OVERFLOW | float::INEXACT);
+}
+
+#[test]
+fn double_rounding_matches_host() {
+    let mut rng = fuzz::Rng::new(11);
+    let mut value = || {
+        let bits = rng.next();
+        // Mix in small exponents so subnormals and cancellation show up.
+        let f = f64::from_bits(if bits & 1 == 0 { bits } else { bits & 0x803F_FFFF_FFFF_FFFF });
+        if f.is_finite() { f } else { 1.5 }
+    };
+    let modes = [RoundingMode::Down, RoundingMode::Up, RoundingMode::TowardZero, RoundingMode::NearestAway];
+    for _ in 0..20_000 {
+        let (a, b) = (value(), value());
+        let cases: [(&DoubleOp, f64); 5] = [
+            (&|mode| float::add_f64(a, b, mode), a + b),
+            (&|mode| float::sub_f64(a, b, mode), a - b),
+            (&|mode| float::mul_f64(a, b, mode), a * b),
+            (&|mode| float::div_f64(a, b, mode), a / b),
+            (&|mode| float::sqrt_f64(a.abs(), mode), a.abs().sqrt()),
+        ];
+        for (op, host) in cases {
+            let (nearest, flags) = op(RoundingMode::NearestEven);
+            assert_eq!(nearest.to_bits(), host.to_bits(), "{a:e} {b:e}");
+            let [down, up, zero, away] = modes.map(|mode| op(mode).0);
+            if flags & float::INEXACT == 0 {
+                assert!(down == nearest && up == nearest && zero == nearest && away == nearest);
+            } else if host.is_finite() && flags & float::OVERFLOW == 0 {
+                assert_eq!(down.next_up(), up, "{a:e} {b:e}");
+                assert!(nearest == down || nearest == up);
+                assert!(away == down || away == up);
+                assert_eq!(zero, if down >= 0.0 { down } else { up });
+            }
+        }
+        // Exact cases: products of short significands and perfect squares.
+        let (x, y) = ((a as f32) as f64, (b as f32) as f64);
+        if x.is_finite() && y.is_finite() && (x * y).abs() < f64::MAX {
+            assert_eq!(float::mul_f64(x, y, RoundingMode::Up).1 & float::INEXACT, 0);
+        }
+    }
+    assert_eq!(float::add_f64(1.0, f64::EPSILON / 2.0, RoundingMode::NearestAway).0, 1.0 + f64::EPSILON);
+    assert_eq!(float::add_f64(1.0, f64::EPSILON / 2.0, RoundingMode::NearestEven).0, 1.0);
+    assert_eq!(float::mul_f64(f64::MAX, 2.0, RoundingMode::TowardZero), (f64::MAX, float::OVERFLOW | float::INEXACT));
+    assert_eq!(float::div_f64(f64::MIN_POSITIVE, 3.0, RoundingMode::NearestEven).1, float::UNDERFLOW | float::INEXACT);
+}
+
+#[test]
+fn loads_and_stores() {
+    let vm = run("
+        ldi gr0 0x1000
+        ldi gr1 0x11223344
+        sw gr0 gr1
+        lw gr2 gr0
+        ldi gr3 0x1004
+        ldi gr4 0x1F80
+        sb gr3 gr4
+        lbs gr5 gr3
+        lbu gr6 gr3
+        ldi gr7 0x1001
+        lbu gr8 gr7
+        hlt
+    ");
+    assert_eq!(word(&vm, 0x1000), 0x1122_3344);
+    assert_eq!(vm.mem[0x1000], 0x44);
+    assert_eq!(reg(&vm, GR2), 0x1122_3344);
+    assert_eq!(vm.mem[0x1004], 0x80);
+    assert_eq!(vm.mem[0x1005], 0);
+    assert_eq!(reg(&vm, GR5), 0xFFFF_FF80);
+    assert_eq!(reg(&vm, GR6), 0x80);
+    assert_eq!(reg(&vm, GR8), 0x33);
+}
+
+#[test]
+fn base_offset_addressing() {
+    let vm = run("
+        ldi gr0 0x1000
+        ldi gr1 0x11223344
+        sw [gr0+8] gr1
+        lw gr2 [gr0+0x8]
+        lbu gr3 [gr0+9]
+        ldi gr0 0x1010
+        lw gr4 [gr0-8]
+        sb [gr0-1] gr1
+        lbs gr5 [gr0-0x1]
+        ldi gr1 0xFFFF8001
+        sh [gr0] gr1
+        lhs gr6 [gr0]
+        lhu gr7 [gr0+0]
+        lhu gr8 [gr0-0xF]
+        hlt
+    ");
+    assert_eq!(word(&vm, 0x1008), 0x1122_3344);
+    assert_eq!(reg(&vm, GR2), 0x1122_3344);
+    assert_eq!(reg(&vm, GR3), 0x33);
+    assert_eq!(reg(&vm, GR4), 0x1122_3344);
+    assert_eq!(vm.mem[0x100F], 0x44);
+    assert_eq!(reg(&vm, GR5), 0x44);
+    assert_eq!(word(&vm, 0x1010), 0x8001);
+    assert_eq!(reg(&vm, GR6), 0xFFFF_8001);
+    assert_eq!(reg(&vm, GR7), 0x8001);
+    assert_eq!(reg(&vm, GR8), 0);
+
+    // Halfwords wrap around memory like words do.
+    let vm = run("
+        ldi gr0 0xFFFFFFFF
+        ldi gr1 0xABCD
+        sh [gr0] gr1
+        lhu gr2 [gr0+0]
+        ldi gr3 1
+        lhu gr4 [gr3-2]
+        hlt
+    ");
+    assert_eq!((vm.mem[0xFFFF_FFFF], vm.mem[0]), (0xCD, 0xAB));
+    assert_eq!(reg(&vm, GR2), 0xABCD);
+    assert_eq!(reg(&vm, GR4), 0xABCD);
+
+    // Offset 0 is the plain register form.
+    let forms = assembler::assemble("lw gr0 [gr1]\nlw gr0 gr1\nsw [gr1-4] gr0", CODE_BASE).unwrap();
+    assert_eq!(forms.bytes[..6], forms.bytes[6..12]);
+    assert_eq!(&forms.bytes[12..], &[isa::SW, GR1 | (GR0 << 4), 0xFC, 0xFF, 0xFF, 0xFF]);
+    // Offsets are integers; floats and characters are rejected.
+    for source in ["lw gr0 [gr1", "lw gr0 [gr1+]", "lw gr0 [gr1*2]", "sw gr0 [gr1]", "lw gr0 [gr1+1.5]", "lw gr0 [gr1+'a']", "sw [gr1-inf] gr0"] {
+        assert!(assembler::assemble(source, CODE_BASE).is_err(), "`{source}` assembled");
+    }
+}
+
+#[test]
+fn stores_wrap_around_memory() {
+    let vm = run("
+        ldi gr0 0xFFFFFFFE
+        ldi gr1 0xAABBCCDD
+        sw gr0 gr1
+        lw gr2 gr0
+        hlt
+    ");
+    assert_eq!(vm.mem[0xFFFF_FFFE], 0xDD);
+    assert_eq!(vm.mem[0xFFFF_FFFF], 0xCC);
+    assert_eq!(vm.mem[0], 0xBB);
+    assert_eq!(vm.mem[1], 0xAA);
+    assert_eq!(reg(&vm, GR2), 0xAABB_CCDD);
+}
+
+#[test]
+fn data_stack() {
+    let top = MachineConfig::default().rsp;
+    let vm = run("
+        ldi gr0 0x11223344
+        ldi gr1 0xF0
+        pw gr0
+        pb gr1
+        pb gr1
+        pobs gr2
+        pobu gr3
+        pow gr4
+        pw gr0
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR2), 0xFFFF_FFF0);
+    assert_eq!(reg(&vm, GR3), 0xF0);
+    assert_eq!(reg(&vm, GR4), 0x1122_3344);
+    // Full descending: the word occupies top-3..=top, little-endian.
+    assert_eq!(reg(&vm, RSP), top - 4);
+    assert_eq!(word(&vm, top - 3), 0x1122_3344);
+}
+
+#[test]
+fn absolute_and_relative_jumps() {
+    let vm = run("
+        jmi forward
+        ldi gr0 0xBAD
+    forward:
+        ldi gr1 0x3
+    loop:
+        inc gr2
+        dec gr1
+        jii rel loop gr1
+        jmi rel +12
+        ldi gr0 0xBAD
+        ldi gr3 skip
+        jmr gr3
+        ldi gr0 0xBAD
+    skip:
+        ldi gr4 0xC
+        jmr rel gr4
+        ldi gr0 0xBAD
+        ldi gr4 -6
+        ldi gr5 0x1
+        jri rel gr4 rds
+        ldi gr6 done
+        jri gr6 gr5
+        ldi gr0 0xBAD
+    done:
+        jii done rds
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR0), 0);
+    assert_eq!(reg(&vm, GR1), 0);
+    assert_eq!(reg(&vm, GR2), 3);
+}
+
+#[test]
+fn negative_relative_register_jump() {
+    let vm = run("
+        jmi start
+    back:
+        ldi gr1 0x7
+        hlt
+    start:
+        ldi gr0 -18
+        jmr rel gr0
+    ");
+    assert_eq!(reg(&vm, GR1), 7);
+}
+
+#[test]
+fn call_and_return() {
+    let top = MachineConfig::default().csp;
+    let vm = run("
+        cai func
+        ldi gr1 0x1
+        ldi gr2 peek
+        car gr2
+        hlt
+    func:
+        inc gr0
+        ret
+    peek:
+        addi gr4 csp 0x1
+        lw gr4 gr4
+        ret
+    ");
+    assert_eq!(reg(&vm, GR0), 1);
+    assert_eq!(reg(&vm, GR1), 1);
+    assert_eq!(reg(&vm, CSP), top);
+    // Inside `peek` the return address (after `car`, at +24) sits at csp+1..=csp+4.
+    assert_eq!(word(&vm, top - 3), CODE_BASE + 24);
+    assert_eq!(reg(&vm, GR4), CODE_BASE + 24);
+}
+
+#[test]
+fn conditional_calls() {
+    let vm = run("
+        ldi gr1 0x1
+        cii func rds
+        cii func gr1
+        cii rel func gr1
+        ldi gr2 func
+        cri gr2 rds
+        cri gr2 gr1
+        ldi gr3 0xC
+        cri rel gr3 gr1
+        hlt
+    func:
+        inc gr0
+        ret
+    ");
+    assert_eq!(reg(&vm, GR0), 4);
+    assert_eq!(reg(&vm, CSP), MachineConfig::default().csp);
+}
+
+#[test]
+fn relative_call_register() {
+    let vm = run("
+        ldi gr1 0x12
+        car rel gr1
+        hlt
+        nop
+        inc gr0
+        ret
+    ");
+    assert_eq!(reg(&vm, GR0), 1);
+}
+
+#[test]
+fn stack_faults() {
+    let vm = run("
+        pow gr0
+        hlt
+    ");
+    assert_eq!(vm.core.fault, Some(Fault::StackUnderflow {
+        stack: Stack::Data,
+        pc: CODE_BASE,
+        sp: MachineConfig::default().rsp,
+    }));
+    assert_eq!(reg(&vm, RPC), CODE_BASE);
+
+    let vm = run("
+        ret
+    ");
+    assert!(matches!(vm.core.fault, Some(Fault::StackUnderflow { stack: Stack::Call, .. })));
+
+    let config = MachineConfig { csp_limit: Some(0xBFFF_FF00), ..MachineConfig::default() };
+    let vm = run_on(config, "
+    recurse:
+        cai recurse
+    ");
+    assert!(matches!(vm.core.fault, Some(Fault::StackOverflow { stack: Stack::Call, .. })));
+
+    // High-water marks are kept with and without limits.
+    let source = "
+        pw gr0
+        pw gr0
+        pb gr0
+        pow gr1
+        cai leaf
+        hlt
+    leaf:
+        ret
+    ";
+    let unlimited = MachineConfig { rsp_limit: None, csp_limit: None, ..MachineConfig::default() };
+    for config in [MachineConfig::default(), unlimited] {
+        let vm = run_on(config, source);
+        assert_eq!(vm.core.fault, None);
+        assert_eq!(vm.stack_high_water(Stack::Data), 9);
+        assert_eq!(vm.stack_high_water(Stack::Call), 4);
+    }
+}
+
+#[test]
+fn machine_config() {
+    // The default board starts both stacks at the top of their regions,
+    // bounds-checked.
+    let config = MachineConfig::default();
+    assert_eq!((config.rsp, config.rsp_limit), (0xAFFF_FFFF, Some(0xA000_0000)));
+    assert_eq!((config.csp, config.csp_limit), (0xBFFF_FFFF, Some(0xB000_0000)));
+    let vm = Helios32::builder().build().unwrap();
+    assert_eq!((reg(&vm, RSP), reg(&vm, CSP)), (0xAFFF_FFFF, 0xBFFF_FFFF));
+    assert_eq!(MachineConfig::parse(""), Ok(config));
+
+    let config = MachineConfig::parse("
+        # A small board.
+        ram_size = 0x10_0000   # 1MiB
+        code_base = 0x1000
+        rsp = 0xFFFFF
+        rsp_limit = none
+        csp = 0xEFFFF
+        csp_limit = 0xE0000
+
+        [[device]]
+        name = \"uart#0\"  # comment after a quoted `#`
+        base = 0xF0000000
+        size = 0x1000
+    ").unwrap();
+    assert_eq!(config.ram_size, 0x10_0000);
+    assert_eq!((config.rsp, config.rsp_limit), (0xF_FFFF, None));
+    assert_eq!(config.csp_limit, Some(0xE_0000));
+    assert_eq!(config.devices, [DeviceWindow { name: "uart#0".to_string(), base: 0xF000_0000, size: 0x1000 }]);
+    assert_eq!(config.validate(), Ok(()));
+
+    assert_eq!(
+        MachineConfig::parse("rsp = 0x1\nbogus = 2"),
+        Err("error on line 2 of machine config: unknown key `bogus`".to_string())
+    );
+    assert!(MachineConfig::parse("rsp = 0x1_0000_0000").is_err());
+    assert!(MachineConfig::parse("[[device]]\nname = uart").is_err());
+
+    let device = |name: &str, base, size| DeviceWindow { name: name.to_string(), base, size };
+    for (config, error) in [
+        (
+            MachineConfig { devices: vec![device("a", 0xF000_0000, 0x100), device("b", 0xF000_00FF, 0x10)], ..config.clone() },
+            "device `b` overlaps device `a`",
+        ),
+        (
+            MachineConfig { devices: vec![device("a", 0xFFFF_8000, 0x100)], ..config.clone() },
+            "device `a` overlaps the ROM window",
+        ),
+        (MachineConfig { rsp_limit: Some(0x10_0000), ..config.clone() }, "rsp_limit is above rsp"),
+        (MachineConfig { code_base: 0x20_0000, ..config.clone() }, "code_base 0x00200000 is not mapped"),
+    ] {
+        assert_eq!(config.validate(), Err(error.to_string()));
+        assert!(Helios32::builder().config(config).build().is_err());
+    }
+}
+
+#[test]
+fn boot_rom() {
+    let rom = assembler::assemble("
+        ldi gr0 0xFFFF0000
+        ldi gr1 0xAB
+        sw gr0 gr1
+        lw gr2 gr0
+        ldi gr3 0x3000
+        sw gr3 gr1
+        jmi 0xC0000000
+    ", 0xFFFF_0000).unwrap();
+    let program = assembler::assemble("ldi gr4 1\nhlt", CODE_BASE).unwrap();
+    let rom_word = u32::from_le_bytes(rom.bytes[..4].try_into().unwrap());
+
+    let [vm, threaded] = [Engine::Interpreter, Engine::Threaded].map(|engine| {
+        let mut vm = Helios32::builder().engine(engine).build().unwrap();
+        vm.load_program(program.origin, &program.bytes).unwrap();
+        vm.load_rom(&rom.bytes).unwrap();
+        vm.reset();
+        assert_eq!(reg(&vm, RPC), 0xFFFF_0000);
+        vm.run_for(100);
+        assert!(!vm.core.is_running);
+        vm
+    });
+    assert_eq!(vm.core.registers, threaded.core.registers);
+    assert_same_memory(&vm, &threaded);
+
+    // The store into the ROM was dropped; the one into RAM was not.
+    assert_eq!(vm.core.fault, None);
+    assert_eq!(word(&vm, 0xFFFF_0000), rom_word);
+    assert_eq!(reg(&vm, GR2), rom_word);
+    assert_eq!(word(&vm, 0x3000), 0xAB);
+    assert_eq!(reg(&vm, GR4), 1);
+
+    // A configured reset vector takes precedence over the ROM.
+    let config = MachineConfig { reset_vector: Some(CODE_BASE), ..MachineConfig::default() };
+    let mut vm = Helios32::builder().config(config).build().unwrap();
+    vm.load_rom(&rom.bytes).unwrap();
+    vm.reset();
+    assert_eq!(reg(&vm, RPC), CODE_BASE);
+    assert!(vm.load_rom(&[0; 0x1_0001]).is_err());
+}
+
+#[test]
+fn snapshot_round_trip() {
+    let mut vm = Helios32::new();
+    let assembly = assembler::assemble("
+        ldi gr0 0x12345678
+        ldi gr1 0x3000
+        sw gr1 gr0
+        pw gr0
+        hlt
+    ", CODE_BASE).unwrap();
+    vm.load_program(assembly.origin, &assembly.bytes).unwrap();
+    vm.run_for(3);
+    assert!(vm.core.is_running);
+
+    let snapshot = vm.save_snapshot();
+    let saved = vm.core.registers;
+    let mut copy = Helios32::new();
+    copy.load_snapshot(&snapshot).unwrap();
+    assert_eq!(copy.core.registers, saved);
+    assert!(copy.core.is_running);
+    assert_eq!(word(&copy, 0x3000), 0x1234_5678);
+    assert_same_memory(&vm, &copy);
+
+    // Both finish the same way from the restored state.
+    vm.run();
+    copy.run();
+    assert_eq!(copy.core.registers, vm.core.registers);
+    assert_same_memory(&vm, &copy);
+
+    // Faults and high-water marks are cleared rather than left stale.
+    let mut faulted = run("pow gr0\nhlt");
+    faulted.core.stack_high_water = [4, 4];
+    faulted.load_snapshot(&snapshot).unwrap();
+    assert_eq!(faulted.core.fault, None);
+    assert_eq!(faulted.stack_high_water(Stack::Data), 0);
+    assert_eq!(faulted.core.registers, saved);
+
+    // Malformed snapshots are rejected and leave the machine untouched.
+    let before = copy.core.registers;
+    for len in [0, 3, 6, snapshot.len() - 1] {
+        assert_eq!(copy.load_snapshot(&snapshot[..len]), Err("truncated snapshot".to_string()), "length {len}");
+    }
+    let mut bad_magic = snapshot.clone();
+    bad_magic[0] = b'X';
+    assert_eq!(copy.load_snapshot(&bad_magic), Err("not a Helios-32 snapshot".to_string()));
+    for version in [snapshot::SNAPSHOT_VERSION - 1, snapshot::SNAPSHOT_VERSION + 1] {
+        let mut other = snapshot.clone();
+        other[4..6].copy_from_slice(&version.to_le_bytes());
+        assert_eq!(copy.load_snapshot(&other), Err(format!("unsupported snapshot version {version}")));
+    }
+    assert_eq!(copy.core.registers, before);
+
+    // The machine config is restored with the state, stack bounds included.
+    let config = MachineConfig::parse("
+        rsp_limit = none
+        reset_vector = 0xC0000000
+        [[device]]
+        name = \"uart\"
+        base = 0xF0000000
+        size = 0x1000
+    ").unwrap();
+    let mut custom = Helios32::builder().config(config.clone()).build().unwrap();
+    copy.load_snapshot(&custom.save_snapshot()).unwrap();
+    assert_eq!(copy.config, config);
+    assert_eq!(copy.core.stacks, custom.core.stacks);
+    custom.config.rsp_limit = Some(custom.config.rsp + 1);
+    assert_eq!(
+        copy.load_snapshot(&custom.save_snapshot()),
+        Err("invalid machine config in snapshot: rsp_limit is above rsp".to_string())
+    );
+}
+
+#[test]
+fn unassigned_opcodes_are_nops() {
+    let mut vm = Helios32::new();
+    vm.load_program(CODE_BASE, &[0xFF, 0, 0, 0, 0, 0, isa::HLT, 0, 0, 0, 0, 0]).unwrap();
+    vm.run_for(10);
+    assert!(!vm.core.is_running);
+    assert_eq!(reg(&vm, RPC), CODE_BASE + 12);
+}
+
+#[test]
+fn self_modifying_code() {
+    // The second pass over `patch` must run the copied instruction, in a
+    // block that was translated before the store and in the same block as it.
+    let vm = run("
+        ldi gr9 0
+    again:
+    patch:
+        ldi gr1 1
+        add gr2 gr2 gr1
+        jii rel done gr9
+        ldi gr9 1
+        ldi gr3 template
+        ldi gr4 patch
+        lw gr5 gr3
+        sw gr4 gr5
+        lhu gr5 [gr3+4]
+        sh [gr4+4] gr5
+        jmi rel again
+    done:
+        ldi gr3 template
+        ldi gr4 next
+        lw gr5 [gr3+6]
+        sw gr4 gr5
+        lhu gr5 [gr3+10]
+        sh [gr4+4] gr5
+    next:
+        ldi gr6 1
+        hlt
+    template:
+        ldi gr1 2
+        ldi gr6 7
+    ");
+    assert_eq!((reg(&vm, GR1), reg(&vm, GR2)), (2, 3));
+    assert_eq!(reg(&vm, GR6), 7);
+    let template = reg(&vm, GR3) as usize;
+    assert_eq!(vm.mem[CODE_BASE as usize + 6..][..6], vm.mem[template..][..6]);
+
+    // Blocks are only dropped by stores through `write_u8`: patching `mem`
+    // directly behind the engine's back leaves the stale translation running.
+    let mut vm = Helios32::builder().engine(Engine::Threaded).build().unwrap();
+    let assembly = assembler::assemble("ldi gr1 1\nhlt\nldi gr1 2", CODE_BASE).unwrap();
+    vm.load_program(CODE_BASE, &assembly.bytes).unwrap();
+    let rerun = |vm: &mut Helios32| {
+        vm.core.registers[RPC as usize] = CODE_BASE;
+        vm.run();
+        reg(vm, GR1)
+    };
+    assert_eq!(rerun(&mut vm), 1);
+    let patched = assembly.bytes[12..18].to_vec();
+    vm.mem[CODE_BASE as usize..][..6].copy_from_slice(&patched);
+    assert_eq!(rerun(&mut vm), 1);
+    vm.write_u8(CODE_BASE + 12, patched[0]);
+    assert_eq!(rerun(&mut vm), 1, "a store past the block keeps it");
+    vm.write_u8(CODE_BASE + 2, patched[2]);
+    assert_eq!(rerun(&mut vm), 2);
+}
+
+#[test]
+fn guest_expectations() {
+    let test = expect::GuestTest::parse("
+        .expect gr0 = -2
+        .expect mem[0x10..0x12] = \"A\\0\"
+        .expect halted within 10 cycles
+        ldi gr0 -2
+        ldi gr1 0x10
+        ldi gr2 'A'
+        sb gr1 gr2
+        hlt
+    ").unwrap();
+    assert_eq!(test.cycle_limit, 10);
+    assert_eq!(test.expectations[1], (3, expect::Expectation::Memory { start: 0x10, bytes: vec![b'A', 0] }));
+    assert!(test.run(&MachineConfig::default(), Engine::Threaded).unwrap().is_empty());
+
+    let test = expect::GuestTest::parse("
+        .expect gr0 = 1
+        .expect halted within 10 cycles
+    loop:
+        jmi loop
+    ").unwrap();
+    assert_eq!(test.run(&MachineConfig::default(), Engine::Interpreter).unwrap().len(), 2);
+
+    // `;` starts a comment only outside quotes.
+    let test = expect::GuestTest::parse("
+        .expect gr0 = ';' ; a comment
+        .expect mem[0x10..0x14] = \"a;\\\";\" ; \"b;c\"
+        ldi gr0 59
+        ldi gr1 0x10
+        ldi gr2 0x3B223B61
+        sw gr1 gr2
+        hlt
+    ").unwrap();
+    assert_eq!(test.expectations[1], (3, expect::Expectation::Memory { start: 0x10, bytes: b"a;\";".to_vec() }));
+    assert!(test.run(&MachineConfig::default(), Engine::Interpreter).unwrap().is_empty());
+}
+
+#[test]
+fn malformed_immediates_are_errors() {
+    for source in ["ldi gr0 '", "ldi gr0 ''", "ldi gr0 '\\q'", "ldi gr0 0x", "jmi -", ".org 0xFFFFFFFC\nnop"] {
+        assert!(assembler::assemble(source, CODE_BASE).is_err(), "`{source}` assembled");
+    }
+
+    let assembly = assembler::assemble("ldi gr0 -0x80000000\njmi rel -2147483648", CODE_BASE).unwrap();
+    assert_eq!(&assembly.bytes[..6], &[isa::LDI, GR0, 0, 0, 0, 0x8]);
+    assert_eq!(&assembly.bytes[6..], &[isa::JMI, 0, 0, 0, 0x80, 1]);
+}
+
+#[test]
+fn org_and_base_addresses() {
+    // Without `.org`, labels resolve against the base.
+    let assembly = assembler::assemble("ldi gr0 next\nnext:\n    jmi next", 0x1000).unwrap();
+    assert_eq!(assembly.origin, 0x1000);
+    assert_eq!(&assembly.bytes[..6], &[isa::LDI, GR0 | 0x60, 0x00, 0x01, 0, 0]);
+    assert_eq!(&assembly.bytes[6..], &[isa::JMI, 0x06, 0x10, 0, 0, 0]);
+
+    // A leading `.org` moves the origin and labels already seen; later ones pad.
+    let assembly = assembler::assemble("
+    start:
+        .org 0x3000
+        ldi gr0 start
+        .org 0x3012
+    end:
+        ldi gr1 end
+        hlt
+    ", CODE_BASE).unwrap();
+    assert_eq!(assembly.origin, 0x3000);
+    assert_eq!(assembly.labels, [("start".to_string(), 0), ("end".to_string(), 0x12)]);
+    assert_eq!(assembly.bytes.len(), 0x1E);
+    assert!(assembly.bytes[6..0x12].iter().all(|&byte| byte == 0));
+    let mut vm = Helios32::new();
+    vm.load_program(assembly.origin, &assembly.bytes).unwrap();
+    vm.core.registers[RPC as usize] = assembly.origin;
+    vm.run();
+    assert_eq!((reg(&vm, GR0), reg(&vm, GR1)), (0x3000, 0x3012));
+
+    // Moving backwards or padding huge gaps is rejected.
+    for source in ["nop\n.org 0xFFFFFF00", "nop\nnop\n.org 0xC0000006", "nop\n.org 0xC0100007"] {
+        assert!(assembler::assemble(source, CODE_BASE).is_err(), "`{source}` assembled");
+    }
+    let assembly = assembler::assemble("nop\n.org 0xC0100006\nhlt", CODE_BASE).unwrap();
+    assert_eq!(assembly.bytes.len() as u32, assembler::MAX_ORG_GAP + 12);
+}
+
+#[test]
+fn disassembly_round_trips() {
+    let source = "
+    start:
+        ldi gr0 -5
+        addi gr1 gr0 0xFFFFFFFF
+        jii rel start gr1
+        cri rel gr2 gr3
+        band gr2 gr1 -16
+        lw gr3 [gr2-4]
+        .org 0xC000002B
+        cai start
+        hlt
+        .org 0xC0000040
+    ";
+    let assembly = assembler::assemble(source, CODE_BASE).unwrap();
+    let text = disassembler::disassemble(&assembly.bytes, assembly.origin);
+    let again = assembler::assemble(&text, 0).unwrap();
+    assert_eq!(again.origin, CODE_BASE);
+    assert_eq!(again.bytes, assembly.bytes, "{text}");
+    assert!(text.contains("jii rel -0xC gr1"), "{text}");
+    assert!(text.contains("band gr2 gr1 0xFFFFFFF0"), "{text}");
+    assert!(text.contains("lw gr3 [gr2-0x4]"), "{text}");
+}
+
+#[test]
+fn fuzz_targets_survive_generated_inputs() {
+    assert_eq!(fuzz::fuzz(fuzz::Target::Assemble, 1, 2_000), None);
+    assert_eq!(fuzz::fuzz(fuzz::Target::Execute, 1, 50), None);
+}
+
+#[test]
+fn atomic_memory_operations() {
+    let vm = run("
+        ldi gr0 0x2000
+        ldi gr1 5
+        sw gr0 gr1
+        ldi gr2 9
+        swap gr3 gr0 gr2
+        ldi gr4 3
+        xadd gr5 gr0 gr4
+        ldi gr6 12
+        ldi gr7 100
+        cas gr8 gr0 gr6 gr7
+        cas gr9 gr0 gr6 gr1
+        fence
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR3), 5);
+    assert_eq!(reg(&vm, GR5), 9);
+    // The first compare matches 12 and swaps; the second sees 100 and does not.
+    assert_eq!(reg(&vm, GR8), 12);
+    assert_eq!(reg(&vm, GR9), 100);
+    assert_eq!(word(&vm, 0x2000), 100);
+
+    // Store-conditional succeeds only while the reservation is unbroken.
+    let vm = run("
+        ldi gr0 0x2000
+        ldi gr1 7
+        lr gr2 gr0
+        sc gr3 gr0 gr1
+        sc gr4 gr0 gr1
+        lr gr2 gr0
+        sb [gr0+3] gr1
+        sc gr5 gr0 gr1
+        lr gr2 gr0
+        sb [gr0+4] gr1
+        ldi gr6 0x2004
+        lr gr2 gr6
+        sc gr7 gr0 gr1
+        lr gr2 gr0
+        ldi gr1 8
+        sc gr8 gr0 gr1
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR3), 0);
+    assert_eq!(reg(&vm, GR4), 1, "sc released the reservation");
+    assert_eq!(reg(&vm, GR5), 1, "an overlapping store broke the reservation");
+    assert_eq!(reg(&vm, GR7), 1, "a later lr moved the reservation");
+    assert_eq!(reg(&vm, GR8), 0, "a store past the word left it alone");
+    assert_eq!(word(&vm, 0x2000), 8);
+
+    // The usual retry loop.
+    let vm = run("
+        ldi gr0 0x2000
+        ldi gr9 10
+    loop:
+        lr gr1 gr0
+        addi gr1 gr1 1
+        sc gr2 gr0 gr1
+        jii rel loop gr2
+        dec gr9
+        jii rel loop gr9
+        hlt
+    ");
+    assert_eq!(word(&vm, 0x2000), 10);
+
+    // Reset and snapshots carry the reservation like any other state.
+    let mut vm = run("ldi gr0 0x2000\nlr gr1 gr0\nhlt");
+    assert_eq!(vm.core.reservation, Some(0x2000));
+    let mut copy = Helios32::new();
+    copy.load_snapshot(&vm.save_snapshot()).unwrap();
+    assert_eq!(copy.core.reservation, Some(0x2000));
+    vm.reset();
+    assert_eq!(vm.core.reservation, None);
+}
+
+/// Runs `source` on a system of `cores` cores on both engines until every
+/// core stops, checking that they agree.
+fn run_system(cores: u32, quantum: u64, source: &str) -> system::System {
+    let assembly = assembler::assemble(source, CODE_BASE).unwrap();
+    let [system, threaded] = [Engine::Interpreter, Engine::Threaded].map(|engine| {
+        let mut vm = Helios32::builder().engine(engine).build().unwrap();
+        vm.load_program(assembly.origin, &assembly.bytes).unwrap();
+        let mut system = system::System::new(vm, cores, quantum).unwrap();
+        let executed = system.run_for(1_000_000);
+        assert!(!system.is_running(), "system still running after {executed} instructions");
+        system
+    });
+
+    assert_eq!(system.cores(), threaded.cores(), "engines disagree on cores");
+    system
+}
+
+#[test]
+fn multi_core_system() {
+    let system = run_system(4, 1, "
+        ldi gr0 0x2000
+        cid gr1
+        ncores gr2
+        ldi gr3 1
+        xadd gr4 gr0 gr3
+        shl gr5 gr1 2
+        add gr5 gr5 gr0
+        sw [gr5+4] gr4
+        pw gr1
+        hlt
+    ");
+    let cores = system.cores();
+    assert_eq!(word(&system.machine, 0x2000), 4);
+    for (id, core) in cores.iter().enumerate() {
+        assert_eq!(core.registers[GR1 as usize], id as u32);
+        assert_eq!(core.registers[GR2 as usize], 4);
+        // Round robin by single instructions takes tickets in ID order.
+        assert_eq!(core.registers[GR4 as usize], id as u32);
+        assert_eq!(word(&system.machine, 0x2004 + 4 * id as u32), id as u32);
+        assert_eq!(core.stack_high_water(Stack::Data), 4);
+        assert!(!core.is_running && core.fault.is_none());
+    }
+    // Each core pushed onto its own quarter of the data stack.
+    let slice = (0xAFFF_FFFF - 0xA000_0000 + 1) / 4;
+    for id in 0..4 {
+        let top = 0xAFFF_FFFF - id * slice;
+        assert_eq!(cores[id as usize].registers[RSP as usize], top - 4);
+        assert_eq!(word(&system.machine, top - 3), id);
+    }
+    // The cores leave the machine's configuration, own core and debugger
+    // write log alone.
+    assert_eq!(system.machine.config, MachineConfig::default());
+    assert_eq!(system.machine.core.registers[RSP as usize], 0xAFFF_FFFF);
+    assert!(system.machine.write_log.is_none());
+
+    // Contended increments: every store breaks the other cores' reservations,
+    // so some store-conditionals fail and retry, but none are lost.
+    for quantum in [1, 3, 100] {
+        let system = run_system(4, quantum, "
+            ldi gr0 0x2000
+            ldi gr9 50
+        loop:
+            lr gr1 gr0
+            addi gr1 gr1 1
+            sc gr2 gr0 gr1
+            add gr8 gr8 gr2
+            jii rel loop gr2
+            dec gr9
+            jii rel loop gr9
+            hlt
+        ");
+        assert_eq!(word(&system.machine, 0x2000), 200, "quantum {quantum}");
+        let retries = system.cores().iter().map(|core| core.registers[GR8 as usize]).sum::<u32>();
+        if quantum == 1 {
+            assert!(retries > 0, "no store-conditional failed");
+        }
+    }
+
+    assert!(system::System::new(Helios32::new(), 0, 1).is_err());
+    assert!(system::System::new(Helios32::new(), 33, 1).is_err());
+    assert!(system::System::new(Helios32::new(), 2, 0).is_err());
+}
+
+#[test]
+fn inter_processor_interrupts() {
+    // Core 0 sleeps until core 1 interrupts it, then handles the IPI and resumes.
+    let system = run_system(2, 1, "
+        cid gr0
+        jii rel sender gr0
+        ldi gr1 handler
+        siv gr1
+        wfi
+        ldi gr3 1
+        hlt
+    handler:
+        iack gr2
+        iret
+    sender:
+        ldi gr4 7
+        ipi gr4
+        ipi rds
+        hlt
+    ");
+    let core = &system.cores()[0];
+    assert_eq!(core.registers[GR2 as usize], 0b10);
+    assert_eq!(core.registers[GR3 as usize], 1);
+    assert_eq!(core.registers[CSP as usize], 0xBFFF_FFFF);
+    assert_eq!(core.interrupts, Interrupts { vector: core.registers[GR1 as usize], ..Interrupts::default() });
+
+    // Waiting cores with nobody left to wake them stop the system.
+    let system = run_system(2, 5, "wfi\nhlt");
+    assert!(system.cores().iter().all(|core| core.interrupts.waiting && !core.is_running));
+
+    // A lone machine is core 0 of 1 and can interrupt itself.
+    let vm = run("
+        ldi gr1 handler
+        siv gr1
+        ipi rds
+        ldi gr3 7
+        wfi
+        hlt
+    handler:
+        iack gr2
+        cid gr4
+        ncores gr5
+        iret
+    ");
+    assert_eq!(reg(&vm, GR2), 1);
+    assert_eq!((reg(&vm, GR3), reg(&vm, GR4), reg(&vm, GR5)), (7, 0, 1));
+    assert!(vm.core.interrupts.waiting);
+    assert_eq!(reg(&vm, RPC), CODE_BASE + 30);
+
+    let mut copy = Helios32::new();
+    copy.load_snapshot(&vm.save_snapshot()).unwrap();
+    assert_eq!(copy.core.interrupts, vm.core.interrupts);
+}
+
+#[test]
+fn bit_manipulation() {
+    let vm = run("
+        ldi gr0 0x00F08001
+        clz gr1 gr0
+        ctz gr2 gr0
+        popc gr3 gr0
+        bswap gr4 gr0
+        clz gr5 rds
+        ctz gr6 rds
+        ldi gr7 0x12345678
+        bswap gr7 gr7
+        hlt
+    ");
+    assert_eq!((reg(&vm, GR1), reg(&vm, GR2), reg(&vm, GR3)), (8, 0, 6));
+    assert_eq!(reg(&vm, GR4), 0x0180_F000);
+    assert_eq!((reg(&vm, GR5), reg(&vm, GR6)), (32, 32));
+    assert_eq!(reg(&vm, GR7), 0x7856_3412);
+
+    let vm = run("
+        ldi gr0 0xDEADBEEF
+        bfx gr1 gr0 8 12
+        bfx gr2 gr0 0 32
+        bfx gr3 gr0 31 1
+        ldi gr4 0x11111111
+        bfi gr4 gr0 4 8
+        ldi gr5 0xFFFFFFFF
+        bfi gr5 rds 0x10 16
+        ldi gr6 5
+        bfi gr6 gr0 0 32
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR1), 0xDBE);
+    assert_eq!(reg(&vm, GR2), 0xDEAD_BEEF);
+    assert_eq!(reg(&vm, GR3), 1);
+    assert_eq!(reg(&vm, GR4), 0x1111_1EF1);
+    assert_eq!(reg(&vm, GR5), 0x0000_FFFF);
+    assert_eq!(reg(&vm, GR6), 0xDEAD_BEEF);
+
+    let vm = run("
+        ldi gr0 0x12348480
+        sextb gr1 gr0
+        sexth gr2 gr0
+        zextb gr3 gr0
+        zexth gr4 gr0
+        ldi gr5 0x7F
+        sextb gr5 gr5
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR1), 0xFFFF_FF80);
+    assert_eq!(reg(&vm, GR2), 0xFFFF_8480);
+    assert_eq!(reg(&vm, GR3), 0x80);
+    assert_eq!(reg(&vm, GR4), 0x8480);
+    assert_eq!(reg(&vm, GR5), 0x7F);
+
+    for field in ["0 0", "31 2", "32 1", "0 33", "-1 4", "1.5 2"] {
+        assert!(assembler::assemble(&format!("bfx gr0 gr1 {field}"), CODE_BASE).is_err(), "{field}");
+    }
+    // Fields the assembler rejects are NOPs.
+    let mut vm = Helios32::new();
+    vm.core.registers[GR0 as usize] = 7;
+    vm.core.registers[GR1 as usize] = 0xCAFE;
+    for field in [[0, 0xFF], [40, 8], [31, 2], [0, 0]] {
+        let inst = u64::from_le_bytes([isa::BFI, 0x21, field[0], field[1], 0, 0, 0, 0]);
+        vm.cpu(None).execute(CODE_BASE, inst);
+        assert_eq!(reg(&vm, GR0), 7);
+        assert_eq!(disassembler::instruction(inst), format!("nop ; encoded as {:02X} 21 {:02X} {:02X} 00 00", isa::BFI, field[0], field[1]));
+    }
+}
+
+#[test]
+fn carry_overflow_and_select() {
+    // 64-bit add and subtract on (low, high) word pairs.
+    let vm = run("
+        ldi gr0 0xFFFFFFFF
+        ldi gr1 0x00000001
+        ldi gr2 0x00000001
+        ldi gr3 0x7FFFFFFF
+        adc gr4 gr9 gr0 gr2
+        adc gr5 gr9 gr1 gr3
+        ldi gr8 0
+        sbb gr6 gr8 gr2 gr0
+        sbb gr7 gr8 gr3 gr1
+        hlt
+    ");
+    assert_eq!((reg(&vm, GR4), reg(&vm, GR5)), (0, 0x8000_0001));
+    assert_eq!(reg(&vm, GR9), 0);
+    assert_eq!((reg(&vm, GR6), reg(&vm, GR7)), (2, 0x7FFF_FFFD));
+    assert_eq!(reg(&vm, GR8), 0);
+
+    let vm = run("
+        ldi gr0 0xFFFFFFFF
+        ldi gr1 3
+        adc gr2 gr1 gr0 gr0
+        ldi gr3 0
+        adc gr3 gr3 gr0 rds
+        ldi gr4 1
+        sbb gr5 gr4 rds rds
+        ldi gr6 2
+        sbb gr7 gr6 gr0 gr0
+        hlt
+    ");
+    // Only bit 0 of the carry counts.
+    assert_eq!((reg(&vm, GR2), reg(&vm, GR1)), (0xFFFF_FFFF, 1));
+    assert_eq!(reg(&vm, GR3), 0xFFFF_FFFF, "dest wins over the carry");
+    assert_eq!((reg(&vm, GR5), reg(&vm, GR4)), (0xFFFF_FFFF, 1));
+    assert_eq!((reg(&vm, GR7), reg(&vm, GR6)), (0, 0));
+
+    let vm = run("
+        ldi gr0 0x7FFFFFFF
+        ldi gr1 1
+        ldi gr2 0x80000000
+        addo gr3 gr4 gr0 gr1
+        addo gr5 gr6 gr2 gr0
+        subo gr7 gr8 gr2 gr1
+        subo gr9 gra rds gr2
+        subo grb grb gr1 gr0
+        hlt
+    ");
+    assert_eq!((reg(&vm, GR3), reg(&vm, GR4)), (0x8000_0000, 1));
+    assert_eq!((reg(&vm, GR5), reg(&vm, GR6)), (0xFFFF_FFFF, 0));
+    assert_eq!((reg(&vm, GR7), reg(&vm, GR8)), (0x7FFF_FFFF, 1));
+    assert_eq!((reg(&vm, GR9), reg(&vm, GRA)), (0x8000_0000, 1));
+    assert_eq!(reg(&vm, GRB), 0x8000_0002);
+
+    // Branch-free signed max and absolute value.
+    let vm = run("
+        ldi gr0 -7
+        ldi gr1 5
+        sgt gr2 gr0 gr1
+        sel gr3 gr2 gr0 gr1
+        slt gr4 gr0 rds
+        sub gr5 rds gr0
+        sel gr6 gr4 gr5 gr0
+        sel gr7 rds gr0 gr1
+        hlt
+    ");
+    assert_eq!(reg(&vm, GR3), 5);
+    assert_eq!(reg(&vm, GR6), 7);
+    assert_eq!(reg(&vm, GR7), 5);
+}
+
+fn debugger(source: &str) -> Debugger {
+    let assembly = assembler::assemble(source, CODE_BASE).unwrap();
+    let mut vm = Helios32::new();
+    vm.load_program(assembly.origin, &assembly.bytes).unwrap();
+    Debugger::new(vm)
+}
+
+const COUNTER: &str = "
+    ldi gr0 0x3000
+loop:
+    inc gr1
+    sw gr0 gr1
+    jmi loop
+";
+
+#[test]
+fn reverse_execution() {
+    let mut dbg = debugger(COUNTER);
+    dbg.add_watchpoint(0x3000, 4);
+    assert_eq!(dbg.cont(), StopReason::Watchpoint { addr: 0x3000, pc: CODE_BASE + 12 });
+    assert_eq!(dbg.steps(), 3);
+    assert!(dbg.remove_watchpoint(0x3000));
+    for _ in 0..3 {
+        assert_eq!(dbg.step(), StopReason::Step);
+    }
+    assert_eq!(word(&dbg.vm, 0x3000), 2);
+    assert_eq!(dbg.last_writer(0x3000), Some(LastWrite { step: 5, pc: CODE_BASE + 12, old: 1 }));
+    assert_eq!(dbg.last_writer(0x3004), None);
+
+    // Undoing the store restores memory as well as registers.
+    assert_eq!(dbg.reverse_step(), StopReason::Step);
+    assert_eq!(word(&dbg.vm, 0x3000), 1);
+    assert_eq!(reg(&dbg.vm, RPC), CODE_BASE + 12);
+
+    dbg.add_breakpoint(CODE_BASE + 6);
+    assert_eq!(dbg.reverse_continue(), StopReason::Breakpoint(CODE_BASE + 6));
+    assert_eq!((dbg.steps(), reg(&dbg.vm, GR1)), (4, 1));
+    assert!(dbg.remove_breakpoint(CODE_BASE + 6));
+
+    dbg.add_watchpoint(0x3002, 1);
+    assert_eq!(dbg.reverse_continue(), StopReason::Watchpoint { addr: 0x3002, pc: CODE_BASE + 12 });
+    assert_eq!((dbg.steps(), word(&dbg.vm, 0x3000)), (2, 0));
+    assert!(dbg.remove_watchpoint(0x3002));
+
+    assert_eq!(dbg.reverse_continue(), StopReason::HistoryStart);
+    assert_eq!(dbg.steps(), 0);
+    assert_eq!((reg(&dbg.vm, GR0), reg(&dbg.vm, RPC)), (0, CODE_BASE));
+    assert_eq!(dbg.reverse_step(), StopReason::HistoryStart);
+
+    // Faults are undone with the instruction that raised them.
+    let mut dbg = debugger("pow gr0\nhlt");
+    assert!(matches!(dbg.step(), StopReason::Fault(Fault::StackUnderflow { .. })));
+    assert_eq!(dbg.reverse_step(), StopReason::Step);
+    assert_eq!(dbg.vm.core.fault, None);
+}
+
+#[test]
+fn reverse_execution_replays_from_checkpoints() {
+    let mut dbg = debugger(COUNTER);
+    let steps = Debugger::DEFAULT_HISTORY as u64 + 10;
+    assert_eq!(dbg.cont_for(steps), None);
+    assert_eq!(dbg.steps(), steps);
+
+    // The first steps have left the undo log and are rebuilt from the
+    // checkpoint at step 0.
+    while dbg.steps() > 5 {
+        assert_eq!(dbg.reverse_step(), StopReason::Step);
+    }
+    assert_eq!((reg(&dbg.vm, GR1), word(&dbg.vm, 0x3000)), (2, 1));
+    assert_eq!(reg(&dbg.vm, RPC), CODE_BASE + 12);
+    assert_eq!(dbg.last_writer(0x3000), Some(LastWrite { step: 2, pc: CODE_BASE + 12, old: 0 }));
+
+    assert_eq!(dbg.step(), StopReason::Step);
+    assert_eq!(word(&dbg.vm, 0x3000), 2);
+    assert_eq!(dbg.reverse_continue(), StopReason::HistoryStart);
+    assert_eq!(word(&dbg.vm, 0x3000), 0);
+}
+
+#[test]
+fn profile_calls() {
+    let assembly = assembler::assemble("
+    main:
+        cai square
+        cai square
+        hlt
+    square:
+        mul gr1 gr0 gr0
+        ret
+    ", CODE_BASE).unwrap();
+    let mut vm = Helios32::new();
+    vm.load_program(assembly.origin, &assembly.bytes).unwrap();
+    let symbols = assembly.labels.iter()
+        .map(|(name, offset)| (name.clone(), assembly.origin + offset))
+        .collect();
+    let mut profiler = Profiler::new(symbols);
+    vm.run_observed(100, |vm, pc, inst, before| profiler.observe(vm, pc, inst, before));
+    assert!(!vm.core.is_running);
+
+    assert_eq!(profiler.folded_stacks(), "main 3\nmain;square 4\n");
+    let report = profiler.report();
+    assert!(report.contains("total instructions: 7\n"));
+    // Self and inclusive cycles.
+    assert!(report.contains(&format!("{:>12} {:>6.2}% {:>12} {:>6.2}%  main\n", 3, 300.0 / 7.0, 7, 100.0)));
+    assert!(report.contains(&format!("{:>12} {:>6.2}% {:>12} {:>6.2}%  square\n", 4, 400.0 / 7.0, 4, 400.0 / 7.0)));
+    assert!(report.contains(&format!("{:>12}  main -> square\n", 2)));
+}
+
+#[test]
+fn lcov_branches() {
+    let source = "ldi gr0 2\nloop:\n    dec gr0\n    jii rel loop gr0\n    hlt\n    jii rel loop gr0\n";
+    let assembly = assembler::assemble(source, CODE_BASE).unwrap();
+    let mut vm = Helios32::new();
+    vm.load_program(assembly.origin, &assembly.bytes).unwrap();
+    let mut coverage = Coverage::new();
+    vm.run_observed(100, |_, pc, inst, before| coverage.observe(pc, inst, before));
+    assert!(!vm.core.is_running);
+
+    // The loop branch went each way once; the one after `hlt` never ran.
+    assert_eq!(coverage.lcov("loop.h32", &assembly), "\
+TN:
+SF:loop.h32
+FN:3,loop
+FNDA:2,loop
+FNF:1
+FNH:1
+BRDA:4,0,0,1
+BRDA:4,0,1,1
+BRDA:6,0,0,-
+BRDA:6,0,1,-
+BRF:4
+BRH:2
+DA:1,1
+DA:3,2
+DA:4,2
+DA:5,1
+DA:6,0
+LF:5
+LH:4
+end_of_record
+");
+}
+
+#[test]
+fn debug_info_round_trip() {
+    let assembly = assembler::assemble("start:\n    ldi gr0 1\nloop:\n  inc gr0\n  hlt\n", CODE_BASE).unwrap();
+    let info = DebugInfo::from_assembly("loop.h32", &assembly);
+    assert_eq!(DebugInfo::parse(&info.to_text()), Ok(info.clone()));
+
+    let (sym, offset) = info.symbolize(CODE_BASE + 8).unwrap();
+    assert_eq!((sym.name.as_str(), sym.addr, sym.size, offset), ("loop", CODE_BASE + 6, 12, 2));
+    assert_eq!(info.symbolize(CODE_BASE).map(|(sym, _)| sym.name.as_str()), Some("start"));
+    assert_eq!(info.symbolize(CODE_BASE + 18), None);
+    assert_eq!(info.symbolize(CODE_BASE - 1), None);
+
+    assert_eq!(info.location(CODE_BASE + 6), Some(&LineEntry { addr: CODE_BASE + 6, line: 4, column: 3 }));
+    assert_eq!(info.location(CODE_BASE + 7), None);
+
+    // Records are sorted on parse; comments and blank lines are skipped.
+    let text = "helios32-debug-info 1\n# comment\n\nsym 0x00000010 4 b\nsym 0x00000008 8 a\n";
+    let names = DebugInfo::parse(text).unwrap().symbols.into_iter().map(|sym| sym.name).collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b"]);
+
+    assert!(DebugInfo::parse("sym 0x00000010 4 b\n").is_err());
+    assert_eq!(
+        DebugInfo::parse("helios32-debug-info 1\nsym 0x10 x b\n"),
+        Err("error on line 2 of debug info: invalid size".to_string())
+    );
+}
+
+#[test]
+fn symbol_map_round_trip() {
+    let assembly = assembler::assemble("main:\n    cai square\n    hlt\nsquare:\n    mul gr1 gr0 gr0\n    ret\n", CODE_BASE).unwrap();
+    let map = SymbolMap::from_assembly(&assembly);
+    let entries = map.entries.iter()
+        .map(|entry| (entry.addr, entry.size, entry.name.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(entries, [(CODE_BASE, 12, "main"), (CODE_BASE + 12, 12, "square")]);
+    assert_eq!(SymbolMap::parse(&map.to_text()), Ok(map));
+
+    for (text, error) in [
+        ("0xC0000000 6 .text", "expected `<address> <size> <section> <name>`"),
+        ("0xC0000000 6 .text main extra", "expected `<address> <size> <section> <name>`"),
+        ("C0000000 6 .text main", "invalid address"),
+        ("0xC0000000 -6 .text main", "invalid size"),
+    ] {
+        assert_eq!(
+            SymbolMap::parse(&format!("# header\n{text}\n")),
+            Err(format!("error on line 2 of symbol map: {error}"))
+        );
+    }
+}
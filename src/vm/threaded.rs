@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use super::{Cpu, PAGE_SHIFT};
+use super::registers::*;
+use super::isa;
+
+const MAX_BLOCK_LEN: usize = 64;
+
+/// A register-to-register operation picked once, at translation.
+type Alu = fn(u32, u32) -> u32;
+
+/// The second input of an [`Kind::Alu`] micro-op.
+#[derive(Clone, Copy)]
+enum Operand {
+    Register(u8),
+    Immediate(u32),
+}
+
+/// What a [`MicroOp`] does, with its operands already extracted.
+#[derive(Clone, Copy)]
+enum Kind {
+    /// `dest = imm`.
+    Load { dest: u8, imm: u32 },
+    /// `dest = alu(src1, src2)`.
+    Alu { alu: Alu, dest: u8, src1: u8, src2: Operand },
+    /// Anything that touches memory, the stacks, FCSR or control flow, run
+    /// through [`Cpu::execute`].
+    Execute(u64),
+}
+
+/// A pre-decoded instruction inside a translated block.
+#[derive(Clone, Copy)]
+struct MicroOp {
+    pc: u32,
+    kind: Kind,
+}
+
+impl MicroOp {
+    /// Decodes `inst`. Register-only arithmetic, logic and compares get an
+    /// [`Alu`] handler; everything else keeps the raw instruction.
+    fn decode(pc: u32, inst: u64) -> Self {
+        let opcode = (inst & 0xFF) as u8;
+        let dest = ((inst >> 8) & 0xF) as u8;
+        let src1 = ((inst >> 12) & 0xF) as u8;
+        let register = Operand::Register(((inst >> 16) & 0xF) as u8);
+        let imm = (inst >> 16) as u32;
+        let src2 = if isa::has_immediate_form(opcode) && inst & isa::IMMEDIATE_FLAG != 0 {
+            Operand::Immediate((((imm << 1) as i32) >> 1) as u32)
+        } else {
+            register
+        };
+
+        let (alu, src1, src2): (Alu, u8, Operand) = match opcode {
+            isa::LDI => {
+                let imm = ((inst >> 12) & 0xFFFF_FFFF) as u32;
+                return Self { pc, kind: Kind::Load { dest, imm } };
+            },
+            isa::ADD => (u32::wrapping_add, src1, src2),
+            isa::SUB => (u32::wrapping_sub, src1, src2),
+            isa::ADDI => (u32::wrapping_add, src1, Operand::Immediate(imm)),
+            isa::SUBI => (u32::wrapping_sub, src1, Operand::Immediate(imm)),
+            isa::INC => (u32::wrapping_add, dest, Operand::Immediate(1)),
+            isa::DEC => (u32::wrapping_sub, dest, Operand::Immediate(1)),
+            isa::BOR => (|a, b| a | b, src1, src2),
+            isa::BAND => (|a, b| a & b, src1, src2),
+            isa::BXOR => (|a, b| a ^ b, src1, src2),
+            isa::BNOT => (|a, _| !a, src1, src2),
+            isa::LOR => (|a, b| (a != 0 || b != 0) as u32, src1, src2),
+            isa::LAND => (|a, b| (a != 0 && b != 0) as u32, src1, src2),
+            isa::LXOR => (|a, b| ((a != 0) != (b != 0)) as u32, src1, src2),
+            isa::LNOT => (|a, _| (a == 0) as u32, src1, src2),
+            isa::EQ => (|a, b| (a == b) as u32, src1, src2),
+            isa::NE => (|a, b| (a != b) as u32, src1, src2),
+            isa::GT => (|a, b| (a > b) as u32, src1, src2),
+            isa::LT => (|a, b| (a < b) as u32, src1, src2),
+            isa::GE => (|a, b| (a >= b) as u32, src1, src2),
+            isa::LE => (|a, b| (a <= b) as u32, src1, src2),
+            isa::SGT => (|a, b| ((a as i32) > (b as i32)) as u32, src1, src2),
+            isa::SLT => (|a, b| ((a as i32) < (b as i32)) as u32, src1, src2),
+            isa::SGE => (|a, b| ((a as i32) >= (b as i32)) as u32, src1, src2),
+            isa::SLE => (|a, b| ((a as i32) <= (b as i32)) as u32, src1, src2),
+            isa::SHL => (u32::wrapping_shl, src1, src2),
+            isa::LSHR => (u32::wrapping_shr, src1, src2),
+            isa::ASHR => (|a, b| (a as i32).wrapping_shr(b) as u32, src1, src2),
+            isa::ROTL => (u32::rotate_left, src1, src2),
+            isa::ROTR => (u32::rotate_right, src1, src2),
+            isa::MUL => (u32::wrapping_mul, src1, src2),
+            isa::DIV => (|a, b| a.checked_div(b).unwrap_or(0), src1, src2),
+            isa::REM => (|a, b| a.checked_rem(b).unwrap_or(0), src1, src2),
+            isa::MUHS => (|a, b| ((a as i32 as i64 * b as i32 as i64) >> 32) as u32, src1, src2),
+            isa::MUHU => (|a, b| ((a as u64 * b as u64) >> 32) as u32, src1, src2),
+            _ => return Self { pc, kind: Kind::Execute(inst) },
+        };
+        Self { pc, kind: Kind::Alu { alu, dest, src1, src2 } }
+    }
+}
+
+/// Translated basic blocks, keyed by their start address.
+///
+/// Every memory store goes through [`BlockCache::note_write`], which drops the
+/// blocks covering the written byte so self-modifying code is retranslated.
+#[derive(Clone, Default)]
+pub struct BlockCache {
+    blocks: HashMap<u32, Rc<[MicroOp]>>,
+    pages: HashMap<u32, Vec<u32>>,
+    invalidated: bool,
+}
+
+impl BlockCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.pages.clear();
+        self.invalidated = true;
+    }
+
+    pub fn note_write(&mut self, addr: u32) {
+        let Some(starts) = self.pages.get_mut(&(addr >> PAGE_SHIFT)) else {
+            return;
+        };
+
+        let blocks = &mut self.blocks;
+        let mut invalidated = false;
+        starts.retain(|start| {
+            let covers = blocks.get(start).is_some_and(|ops| {
+                let len = ops.len() as u32 * 6;
+                addr.wrapping_sub(*start) < len
+            });
+            if covers {
+                blocks.remove(start);
+                invalidated = true;
+            }
+            !covers
+        });
+
+        if starts.is_empty() {
+            self.pages.remove(&(addr >> PAGE_SHIFT));
+        }
+        self.invalidated |= invalidated;
+    }
+
+    fn insert(&mut self, start: u32, ops: Rc<[MicroOp]>) {
+        let end = start.wrapping_add(ops.len() as u32 * 6 - 1);
+        let mut page = start >> PAGE_SHIFT;
+        loop {
+            let starts = self.pages.entry(page).or_default();
+            if !starts.contains(&start) {
+                starts.push(start);
+            }
+            if page == end >> PAGE_SHIFT {
+                break;
+            }
+            page = page.wrapping_add(1) & (u32::MAX >> PAGE_SHIFT);
+        }
+        self.blocks.insert(start, ops);
+    }
+}
+
+fn ends_block(opcode: u8) -> bool {
+    matches!(
+        opcode,
+        isa::HLT | isa::JMR | isa::JRI | isa::CAR | isa::CRI
+            | isa::JMI | isa::JII | isa::CAI | isa::CII | isa::RET
+            | isa::IPI | isa::SIV | isa::IRET | isa::WFI
+    )
+}
+
+impl Cpu<'_> {
+    /// Executes the basic block starting at `RPC`, translating it first if needed.
+    /// A deliverable interrupt is taken first, so the block is the handler's.
+    ///
+    /// Execution leaves the block early when the machine halts, control flow
+    /// diverges from straight-line order, a store hits translated code, or
+    /// `budget` instructions have run. Returns the number of instructions executed.
+    pub(super) fn step_block(&mut self, budget: u64) -> u64 {
+        if self.core.interrupts.is_deliverable() && !self.enter_interrupt() {
+            return 0;
+        }
+        let start = self.core.registers[RPC as usize];
+        let block = match self.translations.blocks.get(&start) {
+            Some(block) => block.clone(),
+            None => {
+                let block: Rc<[MicroOp]> = self.translate(start).into();
+                self.translations.insert(start, block.clone());
+                block
+            },
+        };
+
+        self.translations.invalidated = false;
+        let mut executed = 0;
+        for op in block.iter() {
+            if executed == budget {
+                break;
+            }
+
+            let next = op.pc.wrapping_add(6);
+            self.core.registers[RPC as usize] = next;
+            self.run_micro_op(op);
+            executed += 1;
+
+            if !self.core.is_running
+                || self.translations.invalidated
+                || self.core.registers[RPC as usize] != next
+            {
+                break;
+            }
+        }
+
+        executed
+    }
+
+    /// Runs one micro-op. Like [`Cpu::execute`], it starts by zeroing
+    /// `RDS` and clearing the last fault.
+    fn run_micro_op(&mut self, op: &MicroOp) {
+        match op.kind {
+            Kind::Execute(inst) => self.execute(op.pc, inst),
+            Kind::Load { dest, imm } => {
+                self.core.registers[0] = 0;
+                self.core.fault = None;
+                self.core.registers[dest as usize] = imm;
+            },
+            Kind::Alu { alu, dest, src1, src2 } => {
+                self.core.registers[0] = 0;
+                self.core.fault = None;
+                let src2 = match src2 {
+                    Operand::Register(reg) => self.core.registers[reg as usize],
+                    Operand::Immediate(imm) => imm,
+                };
+                self.core.registers[dest as usize] = alu(self.core.registers[src1 as usize], src2);
+            },
+        }
+    }
+
+    fn translate(&self, start: u32) -> Vec<MicroOp> {
+        let mut ops = Vec::new();
+        let mut pc = start;
+
+        loop {
+            let inst = self.fetch(pc);
+            ops.push(MicroOp::decode(pc, inst));
+            pc = pc.wrapping_add(6);
+
+            if ends_block((inst & 0xFF) as u8) || ops.len() == MAX_BLOCK_LEN {
+                break;
+            }
+        }
+
+        ops
+    }
+}